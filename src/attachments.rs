@@ -0,0 +1,239 @@
+use crate::email::{Email, MimePart};
+
+const FEATURE_PREFIX : &str = "attachment:";
+const COUNT_FEATURE : &str = "count=";
+const TYPE_FEATURE : &str = "type:";
+const SNIFFED_FEATURE : &str = "sniffed:";
+const TYPE_MISMATCH_FEATURE : &str = "type-mismatch";
+const EXTENSION_FEATURE : &str = "ext:";
+const DOUBLE_EXTENSION_FEATURE : &str = "double-ext";
+const ARCHIVE_ENTRIES_FEATURE : &str = "archive-entries=";
+const ARCHIVE_EXTENSION_FEATURE : &str = "archive-ext:";
+const ARCHIVE_ENCRYPTED_FEATURE : &str = "archive-encrypted";
+const GENERIC_CONTENT_TYPE : &str = "application/octet-stream";
+const ZIP : &str = "application/zip";
+const OLE : &str = "application/x-ole-storage";
+/// Declared types of files which are zip archives inside: Office Open XML with
+/// and without macros, OpenDocument, Java archives and e-books.
+const ZIP_BASED_TYPES : [&str; 8] = [
+    "zip", "openxmlformats", "opendocument", "java-archive", "epub",
+    "vnd.ms-word.document.macroenabled.12", "vnd.ms-excel.sheet.macroenabled.12", "vnd.ms-powerpoint.presentation.macroenabled.12",
+];
+/// Declared types of files in the legacy Office compound file format.
+const OLE_BASED_TYPES : [&str; 3] = ["msword", "vnd.ms-", "x-msi"];
+/// Extensions which combine with another on purpose, as in `.tar.gz`.
+const COMPOUND_EXTENSIONS : [&str; 1] = ["tar"];
+const MAX_EXTENSION_LENGTH : usize = 5;
+const ZIP_END_OF_CENTRAL_DIRECTORY : u32 = 0x0605_4b50;
+const ZIP_CENTRAL_DIRECTORY_HEADER : u32 = 0x0201_4b50;
+const ZIP_END_OF_CENTRAL_DIRECTORY_SIZE : usize = 22;
+const ZIP_CENTRAL_DIRECTORY_HEADER_SIZE : usize = 46;
+const ZIP_MAX_COMMENT_SIZE : usize = 0xffff;
+const ZIP_ENCRYPTED_FLAG : u16 = 1;
+/// Archives listing more entries than this are only listed up to it.
+const MAX_ARCHIVE_ENTRIES : usize = 1000;
+
+/// Magic numbers of the file types spam and malware usually attach, and the
+/// content type they identify.
+const SIGNATURES : [(&[u8], &str); 12] = [
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", ZIP),
+    (b"PK\x05\x06", ZIP),
+    (b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", OLE),
+    (b"MZ", "application/x-msdownload"),
+    (b"\x7fELF", "application/x-executable"),
+    (b"Rar!\x1a\x07", "application/vnd.rar"),
+    (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"{\\rtf", "application/rtf"),
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+];
+const GIF_SIGNATURES : [&[u8]; 2] = [b"GIF87a", b"GIF89a"];
+const GIF : &str = "image/gif";
+
+/// What the headers and the content of an attachment say about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    pub filename: Option<String>,
+    pub declared_type: String,
+    pub sniffed_type: Option<&'static str>,
+    /// The files in the attachment when it is a zip archive.
+    pub archive_entries: Vec<ArchiveEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub is_encrypted: bool,
+}
+
+impl Attachment {
+
+    pub fn from_part(part: &MimePart) -> Attachment {
+        let sniffed_type = sniff_content_type(&part.body);
+        let archive_entries = match sniffed_type {
+            Some(ZIP) => list_zip_entries(&part.body),
+            _ => Vec::new(),
+        };
+
+        Attachment {
+            filename: part.filename.clone(),
+            declared_type: part.content_type.clone(),
+            sniffed_type,
+            archive_entries,
+        }
+    }
+
+    /// The lowercase extensions of the file name, last one last.
+    pub fn extensions(&self) -> Vec<String> {
+        self.filename.as_deref().map(extensions).unwrap_or_default()
+    }
+
+    /// Whether the content is not what the headers declare, as when an executable
+    /// is sent as `application/pdf`.
+    pub fn has_type_mismatch(&self) -> bool {
+        match self.sniffed_type {
+            Some(sniffed) => self.declared_type != GENERIC_CONTENT_TYPE && !is_compatible(&self.declared_type, sniffed),
+            None => false,
+        }
+    }
+
+    /// Whether the file name hides its real extension behind another one, as in
+    /// `invoice.pdf.exe`.
+    pub fn has_double_extension(&self) -> bool {
+        let extensions = self.extensions();
+        extensions.len() >= 2 && !COMPOUND_EXTENSIONS.contains(&extensions[extensions.len() - 2].as_str())
+    }
+}
+
+/// Features describing the attachments of an e-mail: how many there are, their
+/// declared and actual types, their extensions and the extensions of the files
+/// inside zip archives.
+pub fn attachment_features(email: &Email) -> Vec<String> {
+    let attachments: Vec<Attachment> = email.attachments().map(Attachment::from_part).collect();
+    if attachments.is_empty() {
+        return Vec::new();
+    }
+
+    let mut features = vec![feature(&format!("{}{}", COUNT_FEATURE, attachments.len().next_power_of_two()))];
+    for attachment in &attachments {
+        features.push(feature(&format!("{}{}", TYPE_FEATURE, attachment.declared_type)));
+        if let Some(sniffed_type) = attachment.sniffed_type {
+            features.push(feature(&format!("{}{}", SNIFFED_FEATURE, sniffed_type)));
+        }
+        if attachment.has_type_mismatch() {
+            features.push(feature(TYPE_MISMATCH_FEATURE));
+        }
+        if let Some(extension) = attachment.extensions().last() {
+            features.push(feature(&format!("{}.{}", EXTENSION_FEATURE, extension)));
+        }
+        if attachment.has_double_extension() {
+            features.push(feature(DOUBLE_EXTENSION_FEATURE));
+        }
+
+        if !attachment.archive_entries.is_empty() {
+            features.push(feature(&format!("{}{}", ARCHIVE_ENTRIES_FEATURE, attachment.archive_entries.len().next_power_of_two())));
+
+            let mut archive_extensions: Vec<String> = attachment.archive_entries.iter()
+                .filter_map(|entry| extensions(&entry.name).pop())
+                .collect();
+            archive_extensions.sort();
+            archive_extensions.dedup();
+            features.extend(archive_extensions.iter().map(|extension| feature(&format!("{}.{}", ARCHIVE_EXTENSION_FEATURE, extension))));
+
+            if attachment.archive_entries.iter().any(|entry| entry.is_encrypted) {
+                features.push(feature(ARCHIVE_ENCRYPTED_FEATURE));
+            }
+        }
+    }
+
+    features
+}
+
+fn feature(name: &str) -> String {
+    format!("{}{}", FEATURE_PREFIX, name)
+}
+
+/// The lowercase extensions of a file name. Only short alphanumeric suffixes
+/// count, so that `Mr. Smith.pdf` has a single extension.
+fn extensions(filename: &str) -> Vec<String> {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or_default().to_lowercase();
+    let mut segments: Vec<&str> = name.split('.').skip(1).collect();
+    let first_extension = segments.iter()
+        .rposition(|segment| segment.is_empty() || segment.len() > MAX_EXTENSION_LENGTH || !segment.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(|invalid| invalid + 1)
+        .unwrap_or(0);
+
+    segments.drain(..first_extension);
+    segments.into_iter().map(String::from).collect()
+}
+
+/// Identifies the type of the content from its first bytes.
+pub fn sniff_content_type(content: &[u8]) -> Option<&'static str> {
+    if GIF_SIGNATURES.iter().any(|signature| content.starts_with(signature)) {
+        return Some(GIF);
+    }
+
+    SIGNATURES.iter()
+        .find(|(signature, _)| content.starts_with(signature))
+        .map(|(_, content_type)| *content_type)
+}
+
+fn is_compatible(declared: &str, sniffed: &str) -> bool {
+    match sniffed {
+        ZIP => ZIP_BASED_TYPES.iter().any(|zip_based| declared.contains(zip_based)),
+        OLE => OLE_BASED_TYPES.iter().any(|ole_based| declared.contains(ole_based)),
+        "image/jpeg" => declared == "image/jpeg" || declared == "image/jpg" || declared == "image/pjpeg",
+        "application/x-msdownload" => declared.contains("msdownload") || declared.contains("executable") || declared.contains("dosexec"),
+        _ => declared == sniffed,
+    }
+}
+
+/// Lists the files of a zip archive from its central directory. A damaged or
+/// truncated archive lists the entries which could be read.
+pub fn list_zip_entries(archive: &[u8]) -> Vec<ArchiveEntry> {
+    let end_of_central_directory = match find_end_of_central_directory(archive) {
+        Some(offset) => offset,
+        None => return Vec::new(),
+    };
+
+    let entry_count = read_u16(archive, end_of_central_directory + 10).unwrap_or(0) as usize;
+    let mut offset = read_u32(archive, end_of_central_directory + 16).unwrap_or(u32::MAX) as usize;
+
+    let mut entries = Vec::new();
+    while entries.len() < entry_count.min(MAX_ARCHIVE_ENTRIES) && read_u32(archive, offset) == Some(ZIP_CENTRAL_DIRECTORY_HEADER) {
+        let header = (read_u16(archive, offset + 8), read_u16(archive, offset + 28), read_u16(archive, offset + 30), read_u16(archive, offset + 32));
+        let (flags, name_length, extra_length, comment_length) = match header {
+            (Some(flags), Some(name), Some(extra), Some(comment)) => (flags, name as usize, extra as usize, comment as usize),
+            _ => break,
+        };
+
+        let name_start = offset + ZIP_CENTRAL_DIRECTORY_HEADER_SIZE;
+        let name = match archive.get(name_start..name_start + name_length) {
+            Some(name) => String::from_utf8_lossy(name).to_string(),
+            None => break,
+        };
+
+        entries.push(ArchiveEntry { name, is_encrypted: flags & ZIP_ENCRYPTED_FLAG != 0 });
+        offset = name_start + name_length + extra_length + comment_length;
+    }
+
+    entries
+}
+
+fn find_end_of_central_directory(archive: &[u8]) -> Option<usize> {
+    let last = archive.len().checked_sub(ZIP_END_OF_CENTRAL_DIRECTORY_SIZE)?;
+    let first = last.saturating_sub(ZIP_MAX_COMMENT_SIZE);
+    (first..=last).rev().find(|&offset| read_u32(archive, offset) == Some(ZIP_END_OF_CENTRAL_DIRECTORY))
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let field = bytes.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_le_bytes([field[0], field[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let field = bytes.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+}
@@ -1,19 +1,28 @@
 use std::collections::HashMap;
-use std::fs::File;
+use std::collections::hash_map::Entry;
 use std::io;
-use std::io::{BufRead,BufReader};
+use std::io::{Read, Write};
 use serde::{Deserialize, Serialize};
-use serde_json::{from_reader, to_writer};
-use unicode_segmentation::UnicodeSegmentation;
+use serde_json::from_reader;
+use crate::classification::{Classification, Explanation, FeatureContribution};
+use crate::spam_classifier::{SpamClassifier, BAYESIAN_MODEL_TYPE};
+use crate::dataset::LabelledMessage;
+use crate::email::{looks_like_email, Email};
+use crate::features::FeatureExtractor;
+use crate::header_features::HeaderFeatures;
+use crate::label::Label;
+use crate::ledger::Ledger;
+use crate::tokenizer::{analyze_email, analyze_text};
+use crate::training::{TrainingReport, TrainingStrategy};
 
-const HAM : &str= "ham";
-const SPAM : &str= "spam";
-const TAB : char= '\t';
 const INITIAL_RATING : f64 = 0.5;
-const DATASET_FILE_FORMAT_INVALID : &str = "The specified dataset file is in invalid format!";
 const ZEROES_REVERTER : f64 = 10000.0;
+/// The counts a token starts with when it is first seen in a ham message.
+const HAM_SEED : Counter = Counter{ham:2,spam:1};
+/// The counts a token starts with when it is first seen in a spam message.
+const SPAM_SEED : Counter = Counter{ham:1,spam:2};
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 struct Counter {
     ham: u32,
     spam: u32,
@@ -22,6 +31,12 @@ struct Counter {
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub struct BayesianSpamClassifier {
     token_table: HashMap<String, Counter>,
+    #[serde(default)]
+    features: FeatureExtractor,
+    #[serde(default)]
+    header_features: HeaderFeatures,
+    #[serde(default)]
+    ledger: Ledger,
 }
 
 impl BayesianSpamClassifier {
@@ -30,31 +45,124 @@ impl BayesianSpamClassifier {
         Default::default()
     }
 
-    pub fn new_from_pre_trained(file: &mut File) -> Result<Self, io::Error> {
-        let pre_trained_model = from_reader(file)?;
+    pub fn with_features(features: FeatureExtractor) -> Self {
+        BayesianSpamClassifier { features, ..Default::default() }
+    }
+
+    pub fn features(&self) -> FeatureExtractor {
+        self.features
+    }
+
+    pub fn header_features(&self) -> &HeaderFeatures {
+        &self.header_features
+    }
+
+    /// Makes the model also learn and score the tokens of the given e-mail headers.
+    /// Changing them after training makes the learned header tokens unreachable.
+    pub fn set_header_features(&mut self, header_features: HeaderFeatures) {
+        self.header_features = header_features;
+    }
+
+    pub fn new_from_pre_trained(reader: impl Read) -> Result<Self, io::Error> {
+        let pre_trained_model = from_reader(reader)?;
         Ok(pre_trained_model)
     }
 
+    /// Learns the message as `label`, as `SpamClassifier::train` does, for callers
+    /// which do not import the trait.
+    pub fn train(&mut self, msg: &str, label: Label) {
+        SpamClassifier::train(self, msg, label)
+    }
+
+    pub fn is_misclassified(&self, msg: &str, label: Label) -> bool {
+        SpamClassifier::is_misclassified(self, msg, label)
+    }
+
+    pub fn classify(&self, msg: &str) -> Classification {
+        SpamClassifier::classify(self, msg)
+    }
+
+    pub fn classify_batch(&self, messages: &[&str]) -> Vec<Classification> {
+        SpamClassifier::classify_batch(self, messages)
+    }
+
+    /// An e-mail is parsed once, for both its body and its header tokens.
     fn split_string_into_list_of_words(&self,msg: &str) -> Vec<String> {
-        msg.unicode_words().map(|word| word.to_string()).collect()
+        if !looks_like_email(msg) {
+            return self.features.extract_from(analyze_text(msg));
+        }
+
+        let email = Email::parse(msg);
+        let mut words = self.features.extract_from(analyze_email(&email));
+        words.extend(self.header_features.extract_from(&email));
+        words
+    }
+
+    /// The messages the model has learned.
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
     }
 
+    /// Learns the message as spam. A message already learned as spam is skipped
+    /// and one learned as ham is unlearned first.
     pub fn train_spam(&mut self, msg: &str) {
+        self.learn(msg, Label::Spam);
+    }
+
+    /// Learns the message as ham. A message already learned as ham is skipped
+    /// and one learned as spam is unlearned first.
+    pub fn train_ham(&mut self, msg: &str) {
+        self.learn(msg, Label::Ham);
+    }
+
+    fn learn(&mut self, msg: &str, label: Label) {
+        match self.ledger.record(msg, label) {
+            Some((previous, _)) if previous == label => return,
+            Some((previous, times)) => self.remove_words(msg, previous, times),
+            None => {}
+        }
+
+        self.add_words(msg, label);
+    }
+
+    fn add_words(&mut self, msg: &str, label: Label) {
         for word in self.split_string_into_list_of_words(msg) {
-            let new_counter = Counter{ham:1,spam:2};
-            let counter = self.token_table.entry(word).or_insert(new_counter);
-            counter.spam += 1;
+            let counter = match label {
+                Label::Ham => self.token_table.entry(word).or_insert(HAM_SEED),
+                Label::Spam => self.token_table.entry(word).or_insert(SPAM_SEED),
+            };
+            match label {
+                Label::Ham => counter.ham += 1,
+                Label::Spam => counter.spam += 1,
+            }
         }
     }
 
-    pub fn train_ham(&mut self, msg: &str) {
+    /// Takes back the counts `learn` added for the message, `times` times over. A
+    /// token left with the counts it was seeded with has no message left and is
+    /// dropped.
+    fn remove_words(&mut self, msg: &str, label: Label, times: usize) {
+        let times = times as u32;
         for word in self.split_string_into_list_of_words(msg) {
-            let new_counter = Counter{ham:2,spam:1};
-            let counter = self.token_table.entry(word).or_insert(new_counter);
-            counter.ham += 1;
+            if let Entry::Occupied(mut entry) = self.token_table.entry(word) {
+                let counter = entry.get_mut();
+                match label {
+                    Label::Ham => counter.ham = counter.ham.saturating_sub(times),
+                    Label::Spam => counter.spam = counter.spam.saturating_sub(times),
+                }
+                if *counter == HAM_SEED || *counter == SPAM_SEED {
+                    entry.remove();
+                }
+            }
         }
     }
 
+    /// Trains the model on a batch of messages, letting the strategy decide which
+    /// of them are learned. Can be called repeatedly to keep training incrementally.
+    pub fn train_with_strategy(&mut self, messages: &[LabelledMessage], strategy: TrainingStrategy) -> TrainingReport {
+        crate::training::train_messages(self, messages, strategy)
+    }
+
     fn spam_total_count(&self) -> u32 {
         self.token_table.values().map(|x| x.spam).sum()
     }
@@ -63,16 +171,14 @@ impl BayesianSpamClassifier {
         self.token_table.values().map(|x| x.ham).sum()
     }
 
-    fn rate_words(&self, msg: &str) -> Vec<(f64,f64)> {
+    fn rate_words(&self, msg: &str, ham_total_count: f64, spam_total_count: f64) -> Vec<(f64,f64)> {
         let words_list =  self.split_string_into_list_of_words(msg);
 
         let mut ratings_list = Vec::new();
         for word in words_list.into_iter(){
-            let counter = self.token_table.get(&word);
-            if !counter.is_none() {
-                let counter_unwraped = counter.unwrap();
-                let ham_rating = (counter_unwraped.ham as f64) / (self.ham_total_count() as f64);
-                let spam_rating = (counter_unwraped.spam as f64) / (self.spam_total_count() as f64); 
+            if let Some(counter) = self.token_table.get(&word) {
+                let ham_rating = (counter.ham as f64) / ham_total_count;
+                let spam_rating = (counter.spam as f64) / spam_total_count;
                 ratings_list.push((ham_rating,spam_rating));
                 ratings_list.push((ZEROES_REVERTER,ZEROES_REVERTER));
             } else{
@@ -80,56 +186,91 @@ impl BayesianSpamClassifier {
             }
         }
 
-        return ratings_list;
+        ratings_list
     }
 
-    pub fn create_model_from_dataset(&mut self, dataset_file: &mut File, model_file: &mut File) -> Result<(), crate::utills::SpamClassifierError> {
-        let reader = BufReader::new(dataset_file);
+    /// Trains the model on every message of the dataset. The model is not written
+    /// anywhere, use `save` or `to_bytes` for that.
+    pub fn create_model_from_dataset(&mut self, dataset: impl Read) -> Result<TrainingReport, crate::utills::SpamClassifierError> {
+        self.create_model_from_dataset_with_strategy(dataset, TrainingStrategy::TrainEverything)
+    }
 
-        for line in reader.lines() {
-            match line {
-                Ok(_) => {},
-                Err(error) => return Err(crate::utills::SpamClassifierError::IO(error)),
-            }
+    pub fn create_model_from_dataset_with_strategy(&mut self, dataset: impl Read, strategy: TrainingStrategy) -> Result<TrainingReport, crate::utills::SpamClassifierError> {
+        crate::training::train_from_dataset(self, dataset, strategy)
+    }
 
-            let line_unwraped = line.unwrap();
+    pub fn get_spam_ham_probabilities(&self, msg: &str) -> (f64,f64) {
+        let ham_total_count = self.ham_total_count() as f64;
+        let spam_total_count = self.spam_total_count() as f64;
+        let prob_list = self.rate_words(msg, ham_total_count, spam_total_count);
 
-            let split_line = crate::utills::take_and_skip(&line_unwraped, TAB);
-            if split_line.is_none() {
-                return Err(crate::utills::SpamClassifierError::InvalidDatasetFormatError(DATASET_FILE_FORMAT_INVALID.to_string()));
-            }
+        let spam_initial_prob = spam_total_count / (ham_total_count + spam_total_count);
+        let ham_initial_prob = ham_total_count / (ham_total_count + spam_total_count);
 
-            let split_line_unwraped = split_line.unwrap();
-            let (first, second) = split_line_unwraped;
-            if first == "" || second == "" {
-                return Err(crate::utills::SpamClassifierError::InvalidDatasetFormatError(DATASET_FILE_FORMAT_INVALID.to_string()));
-            }
+        let product_ham: f64 = prob_list.iter().map(|(first ,_)| first).product();
+        let product_spam: f64 = prob_list.iter().map(|(_, second)|second).product();
 
-            if first == HAM {
-                self.train_ham(second);
-            } else if first == SPAM {
-                self.train_spam(second);
-            }
+        let final_product_spam = product_spam + spam_initial_prob;
+        let final_product_ham = product_ham + ham_initial_prob;
+
+        (final_product_spam,final_product_ham)
+    }
+}
+
+impl SpamClassifier for BayesianSpamClassifier {
+
+    fn model_type(&self) -> &'static str {
+        BAYESIAN_MODEL_TYPE
+    }
+
+    fn train(&mut self, msg: &str, label: Label) {
+        match label {
+            Label::Ham => self.train_ham(msg),
+            Label::Spam => self.train_spam(msg),
         }
+    }
+
+    fn classify(&self, msg: &str) -> Classification {
+        Classification::from(self.get_spam_ham_probabilities(msg))
+    }
 
-        match to_writer(model_file, &self) {
-            Ok(_) => return Ok(()),
-            Err(error) => return Err(crate::utills::SpamClassifierError::Serde(error)),
+    /// Counts the message once more, even if it was already learned as `label`.
+    /// The ledger keeps how many times, so relabelling it takes back every count.
+    fn reinforce(&mut self, msg: &str, label: Label) {
+        if self.ledger.label_of(msg) != Some(label) {
+            return self.learn(msg, label);
         }
+
+        self.ledger.repeat(msg);
+        self.add_words(msg, label);
     }
 
-    pub fn get_spam_ham_probabilities(&self, msg: &str) -> (f64,f64) {
-        let prob_list = self.rate_words(msg);
+    fn ledger(&self) -> Option<&Ledger> {
+        Some(&self.ledger)
+    }
 
-        let spam_initial_prob = (self.spam_total_count() as f64) / (self.ham_total_count() as f64 + self.spam_total_count() as f64);
-        let ham_initial_prob = (self.ham_total_count() as f64) / (self.ham_total_count() as f64 + self.spam_total_count() as f64);
+    fn explain(&self, msg: &str) -> Explanation {
+        let ham_total_count = self.ham_total_count() as f64;
+        let spam_total_count = self.spam_total_count() as f64;
 
-        let product_ham: f64 = prob_list.iter().map(|(first ,_)| first).product();
-        let product_spam: f64 = prob_list.iter().map(|(_, second)|second).product();
+        let contributions = self.split_string_into_list_of_words(msg)
+            .into_iter()
+            .filter_map(|word| {
+                let counter = self.token_table.get(&word)?;
+                let ham_rating = (counter.ham as f64) / ham_total_count;
+                let spam_rating = (counter.spam as f64) / spam_total_count;
+                Some(FeatureContribution { feature: word, weight: (spam_rating / ham_rating).ln() })
+            })
+            .collect();
 
-        let final_product_spam = product_spam + spam_initial_prob;
-        let final_product_ham = product_ham + ham_initial_prob;
+        Explanation::new(self.classify(msg), contributions)
+    }
 
-        (final_product_spam,final_product_ham)
+    fn save(&self, writer: &mut dyn Write) -> Result<(), crate::utills::SpamClassifierError> {
+        crate::spam_classifier::save_tagged(writer, BAYESIAN_MODEL_TYPE, self)
+    }
+
+    fn load(reader: &mut dyn Read) -> Result<Self, crate::utills::SpamClassifierError> {
+        from_reader(reader).map_err(crate::utills::SpamClassifierError::Serde)
     }
 }
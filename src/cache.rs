@@ -1,5 +1,6 @@
 extern crate sha2;
 
+use crate::classification::Classification;
 use crate::utills::SpamClassifierError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -29,9 +30,10 @@ impl Cache {
         Ok(Default::default())
     }
     
-    pub fn add_to_cache(&mut self, string : &String, probs: (f64, f64)) ->  Result<(), SpamClassifierError> {
-        let hashed_string = self.hash_string(string);
-        self.cache.insert(hashed_string, probs);
+    /// Remembers the classification of a message by the model with the
+    /// `model_fingerprint`, and writes the cache.
+    pub fn add_to_cache(&mut self, model: &str, string : &str, classification: &Classification) ->  Result<(), SpamClassifierError> {
+        self.insert(model, string, classification);
 
         let file = File::create(DEFAULT_CACHE_PATH);
         match file {
@@ -40,19 +42,20 @@ impl Cache {
         }
 
         match to_writer(file.unwrap(), &self) {
-            Ok(_) => return Ok(()),
-            Err(error) => return Err(SpamClassifierError::Serde(error)),
+            Ok(_) => Ok(()),
+            Err(error) => Err(SpamClassifierError::Serde(error)),
         }
     }
 
-    pub fn get_from_cache(&self,string : &String) -> Option<(f64, f64)> {
-        let hashed_string = self.hash_string(string);
-        let value = self.cache.get(&hashed_string);
-        if value.is_none(){
-            return None;
-        }
+    /// Remembers the classification without writing the cache.
+    pub fn insert(&mut self, model: &str, string: &str, classification: &Classification) {
+        self.cache.insert(cache_key(model, string), (classification.spam_score, classification.ham_score));
+    }
 
-       Some(*value.unwrap())
+    /// The classification of a message by the model with the `model_fingerprint`,
+    /// if it was cached. Other models do not see it.
+    pub fn get_from_cache(&self, model: &str, string : &str) -> Option<Classification> {
+        self.cache.get(&cache_key(model, string)).map(|probs| Classification::from(*probs))
     }
 
     pub fn clean_cache(&self) ->  Result<(), io::Error> {
@@ -60,15 +63,30 @@ impl Cache {
             return Ok(());
         }
 
-        match fs::remove_file(DEFAULT_CACHE_PATH) {
-            Ok(_) => return Ok(()),
-            Err(error) => return Err(error),
-        }
+        fs::remove_file(DEFAULT_CACHE_PATH)
     }
+}
 
-    fn hash_string(&self,string : &String)-> String{
-        let mut hasher = Sha256::new();
-        hasher.update(string);
-        format!("{:X}", hasher.finalize())
+/// Identifies a model by the SHA-256 digest of its files, in order, and of how
+/// they are combined when there are several.
+pub fn model_fingerprint(model_files: &[Vec<u8>], combination: &str) -> String {
+    let mut hasher = Sha256::new();
+    for model_file in model_files {
+        hasher.update((model_file.len() as u64).to_le_bytes());
+        hasher.update(model_file);
     }
+    hasher.update(combination);
+    format!("{:X}", hasher.finalize())
+}
+
+fn cache_key(model: &str, string: &str) -> String {
+    fingerprint(&format!("{}\n{}", model, string))
+}
+
+/// The SHA-256 digest of a string as uppercase hex. Two strings with the same
+/// fingerprint are, for all practical purposes, the same string.
+pub fn fingerprint(string: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(string);
+    format!("{:X}", hasher.finalize())
 }
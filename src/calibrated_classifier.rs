@@ -0,0 +1,106 @@
+use crate::calibration::{CalibrationMethod, Calibrator};
+use crate::classification::{Classification, Explanation};
+use crate::dataset::LabelledMessage;
+use crate::label::Label;
+use crate::ledger::Ledger;
+use crate::spam_classifier::{SpamClassifier, CALIBRATED_MODEL_TYPE};
+use crate::utills::SpamClassifierError;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_reader, Value};
+use std::io::{Read, Write};
+
+/// The persisted form of a calibrated model. The model is stored as the tagged
+/// JSON its own `save` produces.
+#[derive(Serialize, Deserialize)]
+struct CalibratedModel {
+    calibrator: Calibrator,
+    model: Value,
+}
+
+/// A model whose spam probabilities are mapped by a calibrator fitted on a
+/// held-out set, so that a probability of 0.8 means spam four times out of five.
+pub struct CalibratedClassifier {
+    model: Box<dyn SpamClassifier>,
+    calibrator: Calibrator,
+}
+
+impl CalibratedClassifier {
+
+    pub fn new(model: Box<dyn SpamClassifier>, calibrator: Calibrator) -> Self {
+        CalibratedClassifier { model, calibrator }
+    }
+
+    /// Calibrates `model` on `messages`, which should not be the messages it was
+    /// trained on.
+    pub fn fit(model: Box<dyn SpamClassifier>, messages: &[LabelledMessage], method: CalibrationMethod) -> Self {
+        let texts: Vec<&str> = messages.iter().map(|message| message.text.as_str()).collect();
+        let labels: Vec<Label> = messages.iter().map(|message| message.label).collect();
+        let classifications = model.classify_batch(&texts);
+        CalibratedClassifier { calibrator: Calibrator::fit(method, &classifications, &labels), model }
+    }
+
+    pub(crate) fn from_value(value: Value) -> Result<Self, SpamClassifierError> {
+        let model: CalibratedModel = serde_json::from_value(value).map_err(SpamClassifierError::Serde)?;
+        Ok(CalibratedClassifier::new(crate::spam_classifier::model_from_value(model.model)?, model.calibrator))
+    }
+
+    pub fn calibrator(&self) -> &Calibrator {
+        &self.calibrator
+    }
+
+    pub fn model(&self) -> &dyn SpamClassifier {
+        self.model.as_ref()
+    }
+}
+
+impl SpamClassifier for CalibratedClassifier {
+
+    fn model_type(&self) -> &'static str {
+        CALIBRATED_MODEL_TYPE
+    }
+
+    /// Trains the underlying model. The calibrator is left as it is, so it should
+    /// be fitted again after much training.
+    fn train(&mut self, msg: &str, label: Label) {
+        self.model.train(msg, label);
+    }
+
+    fn reinforce(&mut self, msg: &str, label: Label) {
+        self.model.reinforce(msg, label);
+    }
+
+    fn train_batch(&mut self, messages: &[LabelledMessage]) {
+        self.model.train_batch(messages);
+    }
+
+    fn classify(&self, msg: &str) -> Classification {
+        self.calibrator.calibrate(&self.model.classify(msg))
+    }
+
+    fn classify_batch(&self, messages: &[&str]) -> Vec<Classification> {
+        self.model.classify_batch(messages).iter()
+            .map(|classification| self.calibrator.calibrate(classification))
+            .collect()
+    }
+
+    fn explain(&self, msg: &str) -> Explanation {
+        let mut explanation = self.model.explain(msg);
+        explanation.classification = self.calibrator.calibrate(&explanation.classification);
+        explanation
+    }
+
+    fn ledger(&self) -> Option<&Ledger> {
+        self.model.ledger()
+    }
+
+    fn save(&self, writer: &mut dyn Write) -> Result<(), SpamClassifierError> {
+        let model = serde_json::from_slice(&self.model.to_bytes()?).map_err(SpamClassifierError::Serde)?;
+        let calibrated = CalibratedModel { calibrator: self.calibrator.clone(), model };
+        crate::spam_classifier::save_tagged(writer, CALIBRATED_MODEL_TYPE, &calibrated)
+    }
+
+    fn load(reader: &mut dyn Read) -> Result<Self, SpamClassifierError> {
+        let value: Value = from_reader(reader).map_err(SpamClassifierError::Serde)?;
+        CalibratedClassifier::from_value(value)
+    }
+}
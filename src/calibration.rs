@@ -0,0 +1,302 @@
+use crate::classification::Classification;
+use crate::curves::{render_svg, LabelledScore};
+use crate::evaluation::ratio;
+use crate::label::Label;
+use crate::utills::SpamClassifierError;
+use serde::{Deserialize, Serialize};
+
+pub const PLATT : &str = "platt";
+pub const ISOTONIC : &str = "isotonic";
+pub const DEFAULT_RELIABILITY_BINS : usize = 10;
+/// Log-odds are clamped to this, so that the scores of a model which is certain
+/// stay finite.
+const MAX_LOG_ODDS : f64 = 50.0;
+const PLATT_MAX_ITERATIONS : usize = 100;
+const PLATT_MIN_STEP : f64 = 1e-10;
+const PLATT_SIGMA : f64 = 1e-12;
+const PLATT_EPSILON : f64 = 1e-5;
+const NO_RELIABILITY_BINS : &str = "A reliability diagram needs at least one bin";
+
+/// Maps a raw score to a spam probability with `1 / (1 + exp(a * score + b))`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlattScaling {
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Default for PlattScaling {
+    fn default() -> Self {
+        PlattScaling { a: -1.0, b: 0.0 }
+    }
+}
+
+impl PlattScaling {
+
+    pub fn probability(&self, score: f64) -> f64 {
+        let exponent = self.a * score + self.b;
+        if exponent >= 0.0 {
+            (-exponent).exp() / (1.0 + (-exponent).exp())
+        } else {
+            1.0 / (1.0 + exponent.exp())
+        }
+    }
+
+    /// Fits the sigmoid to the scores with Platt's regularised targets and Newton's
+    /// method with backtracking, as described by Lin, Lin and Weng (2007).
+    pub fn fit(scores: &[f64], labels: &[Label]) -> PlattScaling {
+        let positives = labels.iter().filter(|label| **label == Label::Spam).count() as f64;
+        let negatives = labels.len() as f64 - positives;
+        let high_target = (positives + 1.0) / (positives + 2.0);
+        let low_target = 1.0 / (negatives + 2.0);
+        let targets: Vec<f64> = labels.iter()
+            .map(|label| if *label == Label::Spam { high_target } else { low_target })
+            .collect();
+
+        let mut a = 0.0;
+        let mut b = ((negatives + 1.0) / (positives + 1.0)).ln();
+        let mut objective = platt_objective(scores, &targets, a, b);
+
+        for _ in 0..PLATT_MAX_ITERATIONS {
+            let (mut h11, mut h22, mut h21, mut g1, mut g2) = (PLATT_SIGMA, PLATT_SIGMA, 0.0, 0.0, 0.0);
+            for (score, target) in scores.iter().zip(targets.iter()) {
+                let probability = PlattScaling { a, b }.probability(*score);
+                let variance = probability * (1.0 - probability);
+                h11 += score * score * variance;
+                h22 += variance;
+                h21 += score * variance;
+                let difference = target - probability;
+                g1 += score * difference;
+                g2 += difference;
+            }
+
+            if g1.abs() < PLATT_EPSILON && g2.abs() < PLATT_EPSILON {
+                break;
+            }
+
+            let determinant = h11 * h22 - h21 * h21;
+            let da = -(h22 * g1 - h21 * g2) / determinant;
+            let db = -(-h21 * g1 + h11 * g2) / determinant;
+            let gd = g1 * da + g2 * db;
+
+            let mut step = 1.0;
+            while step >= PLATT_MIN_STEP {
+                let new_a = a + step * da;
+                let new_b = b + step * db;
+                let new_objective = platt_objective(scores, &targets, new_a, new_b);
+                if new_objective < objective + 0.0001 * step * gd {
+                    a = new_a;
+                    b = new_b;
+                    objective = new_objective;
+                    break;
+                }
+                step /= 2.0;
+            }
+
+            if step < PLATT_MIN_STEP {
+                break;
+            }
+        }
+
+        PlattScaling { a, b }
+    }
+}
+
+fn platt_objective(scores: &[f64], targets: &[f64], a: f64, b: f64) -> f64 {
+    scores.iter().zip(targets.iter())
+        .map(|(score, target)| {
+            let exponent = a * score + b;
+            if exponent >= 0.0 {
+                target * exponent + (1.0 + (-exponent).exp()).ln()
+            } else {
+                (target - 1.0) * exponent + (1.0 + exponent.exp()).ln()
+            }
+        })
+        .sum()
+}
+
+/// Maps a score to a spam probability with the non-decreasing step function,
+/// linearly interpolated between steps, which best fits the labels of the
+/// calibration set (pool adjacent violators).
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IsotonicRegression {
+    pub scores: Vec<f64>,
+    pub probabilities: Vec<f64>,
+}
+
+impl IsotonicRegression {
+
+    pub fn probability(&self, score: f64) -> f64 {
+        if self.scores.is_empty() {
+            return 0.5;
+        }
+
+        let upper = self.scores.partition_point(|threshold| *threshold < score);
+        if upper == 0 {
+            return self.probabilities[0];
+        }
+        if upper == self.scores.len() {
+            return self.probabilities[upper - 1];
+        }
+
+        let (low_score, high_score) = (self.scores[upper - 1], self.scores[upper]);
+        let (low_probability, high_probability) = (self.probabilities[upper - 1], self.probabilities[upper]);
+        low_probability + (high_probability - low_probability) * (score - low_score) / (high_score - low_score)
+    }
+
+    pub fn fit(scores: &[f64], labels: &[Label]) -> IsotonicRegression {
+        let mut points: Vec<(f64, f64)> = scores.iter().zip(labels)
+            .map(|(score, label)| (*score, if *label == Label::Spam { 1.0 } else { 0.0 }))
+            .collect();
+        points.sort_by(|first, second| first.0.partial_cmp(&second.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        // Blocks of (lowest score, highest score, sum of targets, count).
+        let mut blocks: Vec<(f64, f64, f64, f64)> = Vec::new();
+        for (score, target) in points {
+            blocks.push((score, score, target, 1.0));
+            while blocks.len() >= 2 {
+                let last = blocks[blocks.len() - 1];
+                let previous = blocks[blocks.len() - 2];
+                let is_violation = previous.2 / previous.3 >= last.2 / last.3;
+                if !is_violation && previous.1 != last.0 {
+                    break;
+                }
+
+                blocks.pop();
+                let merged = blocks.last_mut().unwrap();
+                merged.1 = last.1;
+                merged.2 += last.2;
+                merged.3 += last.3;
+            }
+        }
+
+        let mut isotonic = IsotonicRegression::default();
+        for (lowest, highest, sum, count) in blocks {
+            for score in if lowest == highest { vec![lowest] } else { vec![lowest, highest] } {
+                isotonic.scores.push(score);
+                isotonic.probabilities.push(sum / count);
+            }
+        }
+        isotonic
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationMethod {
+    Platt,
+    Isotonic,
+}
+
+impl CalibrationMethod {
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            PLATT => Some(CalibrationMethod::Platt),
+            ISOTONIC => Some(CalibrationMethod::Isotonic),
+            _ => None,
+        }
+    }
+}
+
+/// Turns the log-odds of a model's classification into a spam probability which
+/// matches how often messages with that score are actually spam.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "lowercase")]
+pub enum Calibrator {
+    Platt(PlattScaling),
+    Isotonic(IsotonicRegression),
+}
+
+impl Calibrator {
+
+    /// Fits the calibrator to the classifications a model gave a calibration set,
+    /// which should not be the messages it was trained on.
+    pub fn fit(method: CalibrationMethod, classifications: &[Classification], labels: &[Label]) -> Calibrator {
+        let scores: Vec<f64> = classifications.iter().map(log_odds).collect();
+        match method {
+            CalibrationMethod::Platt => Calibrator::Platt(PlattScaling::fit(&scores, labels)),
+            CalibrationMethod::Isotonic => Calibrator::Isotonic(IsotonicRegression::fit(&scores, labels)),
+        }
+    }
+
+    pub fn calibrate(&self, classification: &Classification) -> Classification {
+        let score = log_odds(classification);
+        let spam_probability = match self {
+            Calibrator::Platt(platt) => platt.probability(score),
+            Calibrator::Isotonic(isotonic) => isotonic.probability(score),
+        };
+        Classification::new(spam_probability, 1.0 - spam_probability)
+    }
+}
+
+/// The log-odds of spam of a classification, `ln(spam_score / ham_score)`. Unlike
+/// the spam probability, it still tells apart the scores of a Bayesian model which
+/// are all rounded to 0 or 1.
+pub fn log_odds(classification: &Classification) -> f64 {
+    let log_odds = (classification.spam_score / classification.ham_score).ln();
+    if log_odds.is_nan() {
+        return 0.0;
+    }
+
+    log_odds.clamp(-MAX_LOG_ODDS, MAX_LOG_ODDS)
+}
+
+/// The test messages whose spam probability fell into `[lower, upper)`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReliabilityBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub messages: usize,
+    pub mean_probability: f64,
+    pub spam_fraction: f64,
+}
+
+/// How far predicted spam probabilities are from the observed share of spam: a
+/// well calibrated model has bins on the diagonal.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reliability {
+    pub bins: Vec<ReliabilityBin>,
+    /// The mean distance of the bins from the diagonal, weighted by their size.
+    pub expected_calibration_error: f64,
+}
+
+impl Reliability {
+
+    pub fn from_scores(scores: &[LabelledScore], bin_count: usize) -> Result<Reliability, SpamClassifierError> {
+        if bin_count == 0 {
+            return Err(SpamClassifierError::InvalidParameter(NO_RELIABILITY_BINS.to_string()));
+        }
+
+        let mut sums = vec![(0usize, 0.0, 0usize); bin_count];
+        for score in scores {
+            let bin = ((score.spam_probability * bin_count as f64) as usize).min(bin_count - 1);
+            sums[bin].0 += 1;
+            sums[bin].1 += score.spam_probability;
+            sums[bin].2 += (score.label == Label::Spam) as usize;
+        }
+
+        let bins: Vec<ReliabilityBin> = sums.iter().enumerate()
+            .map(|(bin, (messages, probability_sum, spam))| ReliabilityBin {
+                lower: bin as f64 / bin_count as f64,
+                upper: (bin + 1) as f64 / bin_count as f64,
+                messages: *messages,
+                mean_probability: if *messages > 0 { probability_sum / *messages as f64 } else { 0.0 },
+                spam_fraction: ratio(*spam, *messages),
+            })
+            .collect();
+        let expected_calibration_error = bins.iter()
+            .map(|bin| ratio(bin.messages, scores.len()) * (bin.mean_probability - bin.spam_fraction).abs())
+            .sum();
+
+        Ok(Reliability { bins, expected_calibration_error })
+    }
+
+    /// The reliability diagram as a standalone SVG image.
+    pub fn svg(&self) -> String {
+        let points: Vec<(f64, f64)> = self.bins.iter()
+            .filter(|bin| bin.messages > 0)
+            .map(|bin| (bin.mean_probability, bin.spam_fraction))
+            .collect();
+        render_svg(&format!("Reliability diagram (ECE {:.4})", self.expected_calibration_error),
+                "Predicted spam probability", "Share of spam", &points, ((0.0, 0.0), (1.0, 1.0)))
+    }
+}
@@ -0,0 +1,77 @@
+use crate::label::Label;
+use crate::spam_classifier::SpamClassifier;
+use crate::utills::SpamClassifierError;
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+const TEMPORARY_EXTENSION : &str = "tmp";
+
+/// Trains a model one message at a time and writes it to `path` after every
+/// `every` messages, so a long running feedback stream can be stopped at any
+/// point without losing more than `every` messages.
+///
+/// Checkpoints are written to a temporary file first and then renamed over
+/// `path`, so readers never see a half written model.
+pub struct CheckpointedTrainer<C: SpamClassifier + ?Sized> {
+    classifier: Box<C>,
+    path: PathBuf,
+    every: usize,
+    since_checkpoint: usize,
+    checkpoints: usize,
+}
+
+impl<C: SpamClassifier + ?Sized> CheckpointedTrainer<C> {
+
+    pub fn new(classifier: Box<C>, path: &Path, every: usize) -> Self {
+        CheckpointedTrainer {
+            classifier,
+            path: path.to_path_buf(),
+            every,
+            since_checkpoint: 0,
+            checkpoints: 0,
+        }
+    }
+
+    pub fn classifier(&self) -> &C {
+        &self.classifier
+    }
+
+    /// Number of checkpoints written so far.
+    pub fn checkpoints(&self) -> usize {
+        self.checkpoints
+    }
+
+    /// Trains the message and writes a checkpoint if one is due. Returns whether
+    /// a checkpoint was written.
+    pub fn train(&mut self, msg: &str, label: Label) -> Result<bool, SpamClassifierError> {
+        self.classifier.train(msg, label);
+        self.since_checkpoint += 1;
+
+        if self.every == 0 || self.since_checkpoint < self.every {
+            return Ok(false);
+        }
+
+        self.checkpoint()?;
+        Ok(true)
+    }
+
+    /// Writes the current model to the checkpoint path.
+    pub fn checkpoint(&mut self) -> Result<(), SpamClassifierError> {
+        let temporary_path = self.path.with_extension(TEMPORARY_EXTENSION);
+        let mut file = File::create(&temporary_path).map_err(SpamClassifierError::IO)?;
+        self.classifier.save(&mut file)?;
+        file.sync_all().map_err(SpamClassifierError::IO)?;
+        fs::rename(&temporary_path, &self.path).map_err(SpamClassifierError::IO)?;
+
+        self.since_checkpoint = 0;
+        self.checkpoints += 1;
+        Ok(())
+    }
+
+    /// Writes a final checkpoint and returns the trained model.
+    pub fn finish(mut self) -> Result<Box<C>, SpamClassifierError> {
+        self.checkpoint()?;
+        Ok(self.classifier)
+    }
+}
@@ -0,0 +1,92 @@
+use crate::label::Label;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const UNDECIDED_PROBABILITY : f64 = 0.5;
+
+/// The spam and ham scores a model assigned to a message. The scores are only
+/// comparable with each other, `spam_probability` normalises them into `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Classification {
+    pub spam_score: f64,
+    pub ham_score: f64,
+}
+
+impl Classification {
+
+    pub fn new(spam_score: f64, ham_score: f64) -> Self {
+        Classification { spam_score, ham_score }
+    }
+
+    pub fn is_spam(&self) -> bool {
+        self.spam_score > self.ham_score
+    }
+
+    pub fn label(&self) -> Label {
+        if self.is_spam() { Label::Spam } else { Label::Ham }
+    }
+
+    pub fn spam_probability(&self) -> f64 {
+        let probability = self.spam_score / (self.spam_score + self.ham_score);
+        if probability.is_finite() {
+            return probability;
+        }
+
+        match (self.spam_score.is_infinite(), self.ham_score.is_infinite()) {
+            (true, false) => 1.0,
+            (false, true) => 0.0,
+            _ => UNDECIDED_PROBABILITY,
+        }
+    }
+}
+
+/// How much a single feature of a message pushed its classification towards
+/// spam (positive weight) or ham (negative weight).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureContribution {
+    pub feature: String,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Explanation {
+    pub classification: Classification,
+    pub contributions: Vec<FeatureContribution>,
+    /// The explanations of the member models, for models combining several others.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<MemberExplanation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberExplanation {
+    pub model_type: String,
+    pub explanation: Explanation,
+}
+
+impl Explanation {
+
+    pub fn new(classification: Classification, contributions: Vec<FeatureContribution>) -> Self {
+        Explanation { classification, contributions, members: Vec::new() }
+    }
+
+    /// Returns up to `count` contributions with the largest absolute weight.
+    pub fn strongest_contributions(&self, count: usize) -> Vec<&FeatureContribution> {
+        let mut contributions: Vec<&FeatureContribution> = self.contributions.iter().collect();
+        contributions.sort_by(|first, second| second.weight.abs().partial_cmp(&first.weight.abs()).unwrap_or(std::cmp::Ordering::Equal));
+        contributions.truncate(count);
+        contributions
+    }
+}
+
+impl From<(f64, f64)> for Classification {
+    fn from((spam_score, ham_score): (f64, f64)) -> Self {
+        Classification::new(spam_score, ham_score)
+    }
+}
+
+/// Classifies the messages in parallel on the current rayon pool, keeping the
+/// order of the input.
+pub fn classify_in_parallel<F>(messages: &[&str], classify: F) -> Vec<Classification>
+    where F: Fn(&str) -> Classification + Sync {
+    messages.par_iter().map(|message| classify(message)).collect()
+}
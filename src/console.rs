@@ -1,33 +1,194 @@
 extern crate clap;
 
-use spamclassifier::bayesian_spam_classifier::BayesianSpamClassifier;
-use spamclassifier::cache::Cache;
+use spamclassifier::cache::{model_fingerprint, Cache};
+use spamclassifier::classification::{Classification, Explanation};
+use spamclassifier::logistic_regression_classifier::{SGD, ADAGRAD};
+use spamclassifier::dataset::{read_labelled_messages, validate_dataset, snippet, ColumnMapping, DatasetFormat, LabelledMessage, ValidatedDataset, TSV, CSV, JSONL};
+use spamclassifier::ensemble_classifier::{EnsembleClassifier, CombinationMethod, MAJORITY_VOTE, WEIGHTED_AVERAGE, STACKING};
+use spamclassifier::passive_aggressive_classifier::{PassiveAggressiveClassifier, PA, PA_I, PA_II};
+use spamclassifier::checkpoint::CheckpointedTrainer;
+use spamclassifier::mailbox::{read_mailbox, FolderMapping, MAILBOX};
+use spamclassifier::corpus::{read_corpus, default_directory_mapping, CORPUS};
+use spamclassifier::deduplication::{deduplicate, Deduplication, EXACT, NEAR, DEFAULT_MAX_DISTANCE};
+use spamclassifier::evaluation::{evaluate, Cutoffs, Evaluation, DEFAULT_CUTOFF};
+use spamclassifier::cross_validation::{validate, Validation, ValidationReport, MetricSummary, DEFAULT_SEED};
+use spamclassifier::tuning::{tune, Configuration, Hyperparameters, Metric, Search, SearchDimension, TUNABLE_PARAMETERS, GRID, RANDOM, DEFAULT_TRIALS, DEFAULT_FOLDS,
+    ACCURACY, PRECISION, RECALL, F1, FALSE_POSITIVE_RATE, UNSURE_RATE, ROC_AUC, PR_AUC, EXPECTED_CALIBRATION_ERROR};
+use spamclassifier::curves::Curves;
+use spamclassifier::calibration::{CalibrationMethod, Calibrator, Reliability, PLATT, ISOTONIC};
+use spamclassifier::calibrated_classifier::CalibratedClassifier;
+use spamclassifier::dataset_stats::{DatasetStats, LabelStats, DEFAULT_TOP_TOKENS};
+use spamclassifier::label::Label;
+use spamclassifier::utills::SpamClassifierError;
+use spamclassifier::email::looks_like_email;
+use spamclassifier::header_features::{HeaderFeatures, DEFAULT_HEADERS};
+use spamclassifier::features::{UNIGRAM, OSB, SBPH};
+use spamclassifier::dataset::parse_dataset_line;
+use spamclassifier::spam_classifier::{SpamClassifier, load_model, load_model_from_bytes, classify_batch_on, BAYESIAN_MODEL_TYPE, LOGISTIC_REGRESSION_MODEL_TYPE, LINEAR_SVM_MODEL_TYPE, PASSIVE_AGGRESSIVE_MODEL_TYPE};
+use spamclassifier::training::{train_messages, TRAIN_EVERYTHING, TRAIN_ON_ERROR, TRAIN_UNTIL_MATURE, TRAIN_UNTIL_NO_ERROR};
 use clap::{Arg, App, SubCommand,AppSettings,ArgMatches};
 use std::io::prelude::*;
 use std::fs::File;
 use std::path::Path;
-use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
 
 const NEUTRAL_MULTIPLIER : f64 = 1.0;
+const EXPLAINED_FEATURES_COUNT : usize = 10;
 const APPLICATION_AUTHOR: &str = "Ivan Ivanov";
 const APPLICATION_NAME: &str = "Spam classifier";
 const DEFAULT_MODEL_PATH: &str = "resources/model.json";
+const ALL_DATASET_FORMATS: [&str; 5] = [TSV, CSV, JSONL, MAILBOX, CORPUS];
+const TEXT_DATASET_FORMATS: [&str; 3] = [TSV, CSV, JSONL];
 const CLEAN_CACHE_SUBCOMMAND: &str = "clean-cache";
 const CREATE_MODEL_FROM_DATASET_SUBCOMMAND: &str = "create-model-from-dataset";
 const FROM_MODEL_PARAMETER: &str = "from-model";
+const CREATE_ENSEMBLE_SUBCOMMAND: &str = "create-ensemble";
+const MEMBER_MODEL_PARAMETER: &str = "member-model";
+const MEMBER_WEIGHT_PARAMETER: &str = "member-weight";
+const ENSEMBLE_METHOD_PARAMETER: &str = "ensemble-method";
 const MESSAGE_PARAMETER: &str = "message";
 const MESSAGE_FROM_FILE_PARAMETER: &str = "message-from-file";
 const DATASET_PATH_PARAMETER: &str = "dataset-path";
 const MODEL_PATH_PARAMETER: &str = "model-path";
+const TRAINING_STRATEGY_PARAMETER: &str = "training-strategy";
+const MATURITY_PARAMETER: &str = "maturity";
+const MAX_PASSES_PARAMETER: &str = "max-passes";
+const MODEL_TYPE_PARAMETER: &str = "model-type";
+const LEARNING_RATE_PARAMETER: &str = "learning-rate";
+const L1_PARAMETER: &str = "l1";
+const L2_PARAMETER: &str = "l2";
+const EPOCHS_PARAMETER: &str = "epochs";
+const OPTIMIZER_PARAMETER: &str = "optimizer";
+const LAMBDA_PARAMETER: &str = "lambda";
+const FEATURES_PARAMETER: &str = "features";
+const FEATURE_WINDOW_PARAMETER: &str = "feature-window";
+const HEADERS_PARAMETER: &str = "headers";
+const PA_VARIANT_PARAMETER: &str = "pa-variant";
+const AGGRESSIVENESS_PARAMETER: &str = "aggressiveness";
+const TRAIN_ONLINE_SUBCOMMAND: &str = "train-online";
+const CHECKPOINT_EVERY_PARAMETER: &str = "checkpoint-every";
+const DEFAULT_CHECKPOINT_EVERY: usize = 1000;
+const STANDARD_INPUT_PATH: &str = "-";
+const SEED_PARAMETER: &str = "seed";
+const DATASET_FORMAT_PARAMETER: &str = "dataset-format";
+const LABEL_COLUMN_PARAMETER: &str = "label-column";
+const TEXT_COLUMN_PARAMETER: &str = "text-column";
+const SPAM_LABEL_PARAMETER: &str = "spam-label";
+const HAM_LABEL_PARAMETER: &str = "ham-label";
+const VALIDATE_DATASET_SUBCOMMAND: &str = "validate-dataset";
+const SKIP_INVALID_PARAMETER: &str = "skip-invalid";
+const REJECTS_PATH_PARAMETER: &str = "rejects-path";
+const DEDUPLICATE_PARAMETER: &str = "deduplicate";
+const MAX_DISTANCE_PARAMETER: &str = "max-distance";
+const LIST_TRAINED_SUBCOMMAND: &str = "list-trained";
+const HOLDOUT_PARAMETER: &str = "holdout";
+const CROSS_VALIDATE_PARAMETER: &str = "cross-validate";
+const EVALUATE_SUBCOMMAND: &str = "evaluate";
+const CURVES_DIRECTORY_PARAMETER: &str = "curves-directory";
+const ROC_CSV_FILE: &str = "roc.csv";
+const PR_CSV_FILE: &str = "pr.csv";
+const ROC_SVG_FILE: &str = "roc.svg";
+const PR_SVG_FILE: &str = "pr.svg";
+const RELIABILITY_CSV_FILE: &str = "reliability.csv";
+const RELIABILITY_SVG_FILE: &str = "reliability.svg";
+const CALIBRATE_SUBCOMMAND: &str = "calibrate";
+const CALIBRATION_METHOD_PARAMETER: &str = "calibration-method";
+const CALIBRATED_MODEL_PATH_PARAMETER: &str = "calibrated-model-path";
+/// The share of the calibration set which only measures the calibration error.
+const CALIBRATION_TEST_FRACTION: f64 = 0.3;
+const TUNE_SUBCOMMAND: &str = "tune";
+const SEARCH_PARAMETER: &str = "search";
+const SEARCH_STRATEGY_PARAMETER: &str = "search-strategy";
+const TRIALS_PARAMETER: &str = "trials";
+const METRIC_PARAMETER: &str = "metric";
+const BEST_CONFIG_PATH_PARAMETER: &str = "best-config-path";
+const HAM_CUTOFF_PARAMETER: &str = "ham-cutoff";
+const SPAM_CUTOFF_PARAMETER: &str = "spam-cutoff";
+const DATASET_STATS_SUBCOMMAND: &str = "dataset-stats";
+const TOP_TOKENS_PARAMETER: &str = "top-tokens";
+const OUTPUT_FORMAT_PARAMETER: &str = "output-format";
+const TEXT_OUTPUT: &str = "text";
+const JSON_OUTPUT: &str = "json";
+const SPAM_FOLDER_PARAMETER: &str = "spam-folder";
+const HAM_FOLDER_PARAMETER: &str = "ham-folder";
+const THREADS_PARAMETER: &str = "threads";
+const EXPLAIN_PARAMETER: &str = "explain";
 const APP_VERSION: &str = "1.0";
 const SUBCOMMAND_VERSION: &str = "1.3";
 const APP_ABOUT: &str = "Classifies whether a message is a spam or not";
 const CREATE_MODEL_FROM_DATASET_SUBCOMMAND_ABOUT: &str = "Creates a new model from the specified dataset and writes it to the specified file";
-const DATASET_PATH_PARAMETER_HELP: &str = "The path to the dataset file";
-const MODEL_PATH_PARAMETER_HELP: &str = "The path to the model file to be created";
+const DATASET_PATH_PARAMETER_HELP: &str = "The path to the dataset file, or - to read it from the standard input";
+const MODEL_PATH_PARAMETER_HELP: &str = "The path to the model file to be created. With --holdout or --cross-validate, the model trained on the whole dataset is written there";
+const TRAINING_STRATEGY_PARAMETER_HELP: &str = "Which messages of the dataset are learned by the model";
+const MATURITY_PARAMETER_HELP: &str = "Messages per label learned unconditionally by the train-until-mature strategy";
+const MAX_PASSES_PARAMETER_HELP: &str = "Maximum passes over the dataset made by the train-until-no-error strategy";
+const MODEL_TYPE_PARAMETER_HELP: &str = "The type of model to create";
+const LEARNING_RATE_PARAMETER_HELP: &str = "Learning rate of the logistic regression model";
+const L1_PARAMETER_HELP: &str = "L1 regularisation strength of the logistic regression model";
+const L2_PARAMETER_HELP: &str = "L2 regularisation strength of the logistic regression model";
+const EPOCHS_PARAMETER_HELP: &str = "Number of shuffled passes over the dataset made by the logistic regression and linear SVM models";
+const LAMBDA_PARAMETER_HELP: &str = "Regularisation strength of the linear SVM model";
+const FEATURES_PARAMETER_HELP: &str = "Features counted by the bayesian model: single words, orthogonal sparse bigrams or sparse binary polynomials";
+const FEATURE_WINDOW_PARAMETER_HELP: &str = "Number of consecutive words the osb and sbph features are built from, 2 to 16 for osb and 2 to 8 for sbph";
+const HEADERS_PARAMETER_HELP: &str = "Comma separated e-mail headers whose tokens the bayesian model learns with the header name as prefix. An empty list learns none";
+const PA_VARIANT_PARAMETER_HELP: &str = "Update rule of the passive-aggressive model";
+const AGGRESSIVENESS_PARAMETER_HELP: &str = "Aggressiveness (C) of the PA-I and PA-II update rules";
+const TRAIN_ONLINE_SUBCOMMAND_ABOUT: &str = "Trains a model message by message from a dataset or the standard input, periodically writing it to the model file";
+const ONLINE_MODEL_PATH_PARAMETER_HELP: &str = "The model file to update. A new passive-aggressive model is created if it does not exist";
+const ONLINE_DATASET_PATH_PARAMETER_HELP: &str = "The path to the dataset file. The standard input is read if omitted";
+const CHECKPOINT_EVERY_PARAMETER_HELP: &str = "Number of messages learned between two writes of the model file";
+const OPTIMIZER_PARAMETER_HELP: &str = "Optimizer used to train the logistic regression model";
+const SEED_PARAMETER_HELP: &str = "Seed used to shuffle the dataset between epochs and to split it for --holdout and --cross-validate";
+const DATASET_FORMAT_PARAMETER_HELP: &str = "Format of the dataset: label<TAB>text lines, CSV with a header row, one JSON object per line, a mailbox export (an mbox file, a Maildir, or a directory of them) or a directory per label with one message per file";
+const LABEL_COLUMN_PARAMETER_HELP: &str = "Name of the CSV column or JSON field holding the label";
+const TEXT_COLUMN_PARAMETER_HELP: &str = "Name of the CSV column or JSON field holding the message";
+const SPAM_LABEL_PARAMETER_HELP: &str = "A label value meaning spam. Can be given several times";
+const HAM_LABEL_PARAMETER_HELP: &str = "A label value meaning ham. Can be given several times";
+const VALIDATE_DATASET_SUBCOMMAND_ABOUT: &str = "Reports every record of a dataset which cannot be trained on, with its line number, and the number of messages per label";
+const DEDUPLICATE_PARAMETER_HELP: &str = "Drops the messages of the dataset which repeat an earlier message with the same label, exactly or with a few words changed";
+const MAX_DISTANCE_PARAMETER_HELP: &str = "Number of differing simhash bits up to which --deduplicate near treats two messages as duplicates";
+const LIST_TRAINED_SUBCOMMAND_ABOUT: &str = "Lists the fingerprint and label of every message a bayesian model has learned";
+const LIST_TRAINED_MODEL_PATH_PARAMETER_HELP: &str = "The path to the model file to read";
+const LIST_TRAINED_MESSAGE_PARAMETER_HELP: &str = "Only tells whether and as what this message was learned";
+const HOLDOUT_PARAMETER_HELP: &str = "Fraction of the dataset held out to test a model trained on the rest, keeping the share of spam";
+const CROSS_VALIDATE_PARAMETER_HELP: &str = "Number of stratified folds to cross-validate the model on. The model file is only written when --model-path is given";
+const EVALUATE_SUBCOMMAND_ABOUT: &str = "Measures a model on a labelled test set: accuracy, precision, recall, F1, false positive rate, the confusion matrix and the misclassified messages";
+const EVALUATE_MODEL_PATH_PARAMETER_HELP: &str = "The path to the model file to evaluate";
+const EVALUATE_DATASET_PATH_PARAMETER_HELP: &str = "The path to the labelled test set, or - to read it from the standard input";
+const EVALUATE_SKIP_INVALID_PARAMETER_HELP: &str = "Evaluates on the valid records of the test set instead of stopping when some cannot be parsed";
+const CURVES_DIRECTORY_PARAMETER_HELP: &str = "A directory to write the ROC and precision-recall curves and the reliability diagram to, as CSV points and SVG images";
+const CALIBRATE_SUBCOMMAND_ABOUT: &str = "Fits a calibrator on a held-out labelled set so that the spam probabilities of a model match how often its messages are spam, and writes the calibrated model";
+const CALIBRATE_MODEL_PATH_PARAMETER_HELP: &str = "The path to the model file to calibrate";
+const CALIBRATE_DATASET_PATH_PARAMETER_HELP: &str = "The path to the held-out labelled set the calibrator is fitted on, which the model was not trained on";
+const CALIBRATION_METHOD_PARAMETER_HELP: &str = "Platt scaling fits a sigmoid to the scores, isotonic regression fits any non-decreasing function and needs more messages";
+const CALIBRATED_MODEL_PATH_PARAMETER_HELP: &str = "The path to the calibrated model file to be created";
+const TUNE_SUBCOMMAND_ABOUT: &str = "Searches the hyperparameters of a model by cross-validating every configuration on a dataset, ranks them by a metric and writes the best configuration and its model trained on the whole dataset";
+const SEARCH_PARAMETER_HELP: &str = "A hyperparameter and the comma separated values to try, as name=value,value, e.g. l2=0.0001,0.001. Can be given several times; options not searched keep their given value";
+const SEARCH_STRATEGY_PARAMETER_HELP: &str = "Whether every combination of the values is tried or --trials combinations drawn at random";
+const TRIALS_PARAMETER_HELP: &str = "Number of combinations tried by the random search";
+const METRIC_PARAMETER_HELP: &str = "The validation metric the configurations are ranked by. The rates and the calibration error rank lowest first";
+const TUNE_MODEL_PATH_PARAMETER_HELP: &str = "The path to the model file to be created with the best configuration";
+const BEST_CONFIG_PATH_PARAMETER_HELP: &str = "The path to a JSON file to write the best configuration and its score to";
+const TUNE_VALIDATION_PARAMETER_HELP: &str = "Number of stratified folds every configuration is cross-validated on";
+const HAM_CUTOFF_PARAMETER_HELP: &str = "Spam probability at or below which a message is ham. Messages between the two cutoffs are unsure";
+const SPAM_CUTOFF_PARAMETER_HELP: &str = "Spam probability at or above which a message is spam. Messages between the two cutoffs are unsure";
+const DATASET_STATS_SUBCOMMAND_ABOUT: &str = "Reports the messages per label, their lengths, the vocabulary, the most frequent tokens per label and the duplicates of a dataset";
+const TOP_TOKENS_PARAMETER_HELP: &str = "Number of most frequent tokens reported per label";
+const OUTPUT_FORMAT_PARAMETER_HELP: &str = "Whether the report is printed as text or as JSON";
+const SKIP_INVALID_PARAMETER_HELP: &str = "Trains on the valid records of the dataset instead of stopping when some cannot be parsed";
+const REJECTS_PATH_PARAMETER_HELP: &str = "The path to a file to write the records which cannot be trained on to";
+const SPAM_FOLDER_PARAMETER_HELP: &str = "A mail folder or corpus directory holding spam, by default Junk and Spam for mailboxes and spam and spam_2 for corpora. Can be given several times";
+const HAM_FOLDER_PARAMETER_HELP: &str = "A mail folder or corpus directory holding ham, by default INBOX for mailboxes and ham, easy_ham, easy_ham_2 and hard_ham for corpora. Can be given several times";
 const CLEAN_CACHE_SUBCOMMAND_ABOUT: &str = "Cleans the spam classification cache";
-const FROM_MODEL_PARAMETER_HELP: &str = "Loads a model from file to use for the classifications";
+const FROM_MODEL_PARAMETER_HELP: &str = "Loads a model from file to use for the classifications. When given several times the models are combined into an ensemble";
+const CREATE_ENSEMBLE_SUBCOMMAND_ABOUT: &str = "Combines several model files into an ensemble model and writes it to the specified file";
+const MEMBER_MODEL_PARAMETER_HELP: &str = "Path to a model file to include in the ensemble";
+const MEMBER_WEIGHT_PARAMETER_HELP: &str = "Weight of the member model given at the same position";
+const ENSEMBLE_METHOD_PARAMETER_HELP: &str = "How the classifications of the models of an ensemble are combined";
+const STACKING_DATASET_PATH_PARAMETER_HELP: &str = "The path to the dataset the stacking meta-classifier is trained on";
 const MESSAGE_PARAMETER_HELP: &str = "The message to classify";
+const THREADS_PARAMETER_HELP: &str = "Number of threads used to classify the lines of --message-from-file";
+const EXPLAIN_PARAMETER_HELP: &str = "Prints the features of --message which influenced its classification the most";
 const MESSAGE_FROM_FILE_PARAMETER_HELP: &str = "Path to the file from which to extract the message to classify";
 
 fn main() {
@@ -47,17 +208,269 @@ fn main() {
                                 .help(DATASET_PATH_PARAMETER_HELP))
                             .arg(Arg::with_name(MODEL_PATH_PARAMETER)
                                 .long(MODEL_PATH_PARAMETER)
+                                .required_unless_one(&[HOLDOUT_PARAMETER, CROSS_VALIDATE_PARAMETER])
+                                .takes_value(true)
+                                .help(MODEL_PATH_PARAMETER_HELP))
+                            .arg(Arg::with_name(HOLDOUT_PARAMETER)
+                                .long(HOLDOUT_PARAMETER)
+                                .takes_value(true)
+                                .conflicts_with(CROSS_VALIDATE_PARAMETER)
+                                .help(HOLDOUT_PARAMETER_HELP))
+                            .arg(Arg::with_name(CROSS_VALIDATE_PARAMETER)
+                                .long(CROSS_VALIDATE_PARAMETER)
+                                .takes_value(true)
+                                .help(CROSS_VALIDATE_PARAMETER_HELP))
+                            .args(&cutoff_arguments())
+                            .args(&model_arguments())
+                            .args(&dataset_format_arguments(&ALL_DATASET_FORMATS))
+                            .args(&folder_mapping_arguments())
+                            .arg(Arg::with_name(SKIP_INVALID_PARAMETER)
+                                .long(SKIP_INVALID_PARAMETER)
+                                .help(SKIP_INVALID_PARAMETER_HELP))
+                            .arg(Arg::with_name(DEDUPLICATE_PARAMETER)
+                                .long(DEDUPLICATE_PARAMETER)
+                                .takes_value(true)
+                                .possible_values(&[EXACT, NEAR])
+                                .help(DEDUPLICATE_PARAMETER_HELP))
+                            .arg(Arg::with_name(MAX_DISTANCE_PARAMETER)
+                                .long(MAX_DISTANCE_PARAMETER)
+                                .takes_value(true)
+                                .requires(DEDUPLICATE_PARAMETER)
+                                .help(MAX_DISTANCE_PARAMETER_HELP))
+                            .arg(Arg::with_name(REJECTS_PATH_PARAMETER)
+                                .long(REJECTS_PATH_PARAMETER)
+                                .takes_value(true)
+                                .help(REJECTS_PATH_PARAMETER_HELP)))
+                        .subcommand(SubCommand::with_name(TUNE_SUBCOMMAND)
+                            .about(TUNE_SUBCOMMAND_ABOUT)
+                            .version(SUBCOMMAND_VERSION)
+                            .author(APPLICATION_AUTHOR)
+                            .arg(Arg::with_name(DATASET_PATH_PARAMETER)
+                                .long(DATASET_PATH_PARAMETER)
                                 .required(true)
                                 .takes_value(true)
-                                .help(MODEL_PATH_PARAMETER_HELP)))
+                                .help(DATASET_PATH_PARAMETER_HELP))
+                            .arg(Arg::with_name(MODEL_PATH_PARAMETER)
+                                .long(MODEL_PATH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .help(TUNE_MODEL_PATH_PARAMETER_HELP))
+                            .arg(Arg::with_name(BEST_CONFIG_PATH_PARAMETER)
+                                .long(BEST_CONFIG_PATH_PARAMETER)
+                                .takes_value(true)
+                                .help(BEST_CONFIG_PATH_PARAMETER_HELP))
+                            .arg(Arg::with_name(SEARCH_PARAMETER)
+                                .long(SEARCH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1)
+                                .help(SEARCH_PARAMETER_HELP))
+                            .arg(Arg::with_name(SEARCH_STRATEGY_PARAMETER)
+                                .long(SEARCH_STRATEGY_PARAMETER)
+                                .takes_value(true)
+                                .possible_values(&[GRID, RANDOM])
+                                .default_value(GRID)
+                                .help(SEARCH_STRATEGY_PARAMETER_HELP))
+                            .arg(Arg::with_name(TRIALS_PARAMETER)
+                                .long(TRIALS_PARAMETER)
+                                .takes_value(true)
+                                .help(TRIALS_PARAMETER_HELP))
+                            .arg(Arg::with_name(METRIC_PARAMETER)
+                                .long(METRIC_PARAMETER)
+                                .takes_value(true)
+                                .possible_values(&[ACCURACY, PRECISION, RECALL, F1, FALSE_POSITIVE_RATE, UNSURE_RATE, ROC_AUC, PR_AUC, EXPECTED_CALIBRATION_ERROR])
+                                .default_value(F1)
+                                .help(METRIC_PARAMETER_HELP))
+                            .arg(Arg::with_name(HOLDOUT_PARAMETER)
+                                .long(HOLDOUT_PARAMETER)
+                                .takes_value(true)
+                                .conflicts_with(CROSS_VALIDATE_PARAMETER)
+                                .help(HOLDOUT_PARAMETER_HELP))
+                            .arg(Arg::with_name(CROSS_VALIDATE_PARAMETER)
+                                .long(CROSS_VALIDATE_PARAMETER)
+                                .takes_value(true)
+                                .help(TUNE_VALIDATION_PARAMETER_HELP))
+                            .args(&model_arguments())
+                            .args(&cutoff_arguments())
+                            .args(&dataset_format_arguments(&ALL_DATASET_FORMATS))
+                            .args(&folder_mapping_arguments())
+                            .arg(Arg::with_name(SKIP_INVALID_PARAMETER)
+                                .long(SKIP_INVALID_PARAMETER)
+                                .help(SKIP_INVALID_PARAMETER_HELP)))
+                        .subcommand(SubCommand::with_name(VALIDATE_DATASET_SUBCOMMAND)
+                            .about(VALIDATE_DATASET_SUBCOMMAND_ABOUT)
+                            .version(SUBCOMMAND_VERSION)
+                            .author(APPLICATION_AUTHOR)
+                            .arg(Arg::with_name(DATASET_PATH_PARAMETER)
+                                .long(DATASET_PATH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .help(DATASET_PATH_PARAMETER_HELP))
+                            .args(&dataset_format_arguments(&TEXT_DATASET_FORMATS))
+                            .arg(Arg::with_name(REJECTS_PATH_PARAMETER)
+                                .long(REJECTS_PATH_PARAMETER)
+                                .takes_value(true)
+                                .help(REJECTS_PATH_PARAMETER_HELP)))
+                        .subcommand(SubCommand::with_name(CALIBRATE_SUBCOMMAND)
+                            .about(CALIBRATE_SUBCOMMAND_ABOUT)
+                            .version(SUBCOMMAND_VERSION)
+                            .author(APPLICATION_AUTHOR)
+                            .arg(Arg::with_name(MODEL_PATH_PARAMETER)
+                                .long(MODEL_PATH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .help(CALIBRATE_MODEL_PATH_PARAMETER_HELP))
+                            .arg(Arg::with_name(DATASET_PATH_PARAMETER)
+                                .long(DATASET_PATH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .help(CALIBRATE_DATASET_PATH_PARAMETER_HELP))
+                            .args(&dataset_format_arguments(&ALL_DATASET_FORMATS))
+                            .args(&folder_mapping_arguments())
+                            .arg(Arg::with_name(SKIP_INVALID_PARAMETER)
+                                .long(SKIP_INVALID_PARAMETER)
+                                .help(EVALUATE_SKIP_INVALID_PARAMETER_HELP))
+                            .arg(Arg::with_name(CALIBRATION_METHOD_PARAMETER)
+                                .long(CALIBRATION_METHOD_PARAMETER)
+                                .takes_value(true)
+                                .possible_values(&[PLATT, ISOTONIC])
+                                .default_value(PLATT)
+                                .help(CALIBRATION_METHOD_PARAMETER_HELP))
+                            .arg(Arg::with_name(CALIBRATED_MODEL_PATH_PARAMETER)
+                                .long(CALIBRATED_MODEL_PATH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .help(CALIBRATED_MODEL_PATH_PARAMETER_HELP)))
+                        .subcommand(SubCommand::with_name(DATASET_STATS_SUBCOMMAND)
+                            .about(DATASET_STATS_SUBCOMMAND_ABOUT)
+                            .version(SUBCOMMAND_VERSION)
+                            .author(APPLICATION_AUTHOR)
+                            .arg(Arg::with_name(DATASET_PATH_PARAMETER)
+                                .long(DATASET_PATH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .help(DATASET_PATH_PARAMETER_HELP))
+                            .args(&dataset_format_arguments(&ALL_DATASET_FORMATS))
+                            .args(&folder_mapping_arguments())
+                            .arg(Arg::with_name(TOP_TOKENS_PARAMETER)
+                                .long(TOP_TOKENS_PARAMETER)
+                                .takes_value(true)
+                                .help(TOP_TOKENS_PARAMETER_HELP))
+                            .arg(output_format_argument()))
+                        .subcommand(SubCommand::with_name(EVALUATE_SUBCOMMAND)
+                            .about(EVALUATE_SUBCOMMAND_ABOUT)
+                            .version(SUBCOMMAND_VERSION)
+                            .author(APPLICATION_AUTHOR)
+                            .arg(Arg::with_name(MODEL_PATH_PARAMETER)
+                                .long(MODEL_PATH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .help(EVALUATE_MODEL_PATH_PARAMETER_HELP))
+                            .arg(Arg::with_name(DATASET_PATH_PARAMETER)
+                                .long(DATASET_PATH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .help(EVALUATE_DATASET_PATH_PARAMETER_HELP))
+                            .args(&dataset_format_arguments(&ALL_DATASET_FORMATS))
+                            .args(&folder_mapping_arguments())
+                            .arg(Arg::with_name(SKIP_INVALID_PARAMETER)
+                                .long(SKIP_INVALID_PARAMETER)
+                                .help(EVALUATE_SKIP_INVALID_PARAMETER_HELP))
+                            .arg(Arg::with_name(CURVES_DIRECTORY_PARAMETER)
+                                .long(CURVES_DIRECTORY_PARAMETER)
+                                .takes_value(true)
+                                .help(CURVES_DIRECTORY_PARAMETER_HELP))
+                            .args(&cutoff_arguments())
+                            .arg(output_format_argument()))
+                        .subcommand(SubCommand::with_name(TRAIN_ONLINE_SUBCOMMAND)
+                            .about(TRAIN_ONLINE_SUBCOMMAND_ABOUT)
+                            .version(SUBCOMMAND_VERSION)
+                            .author(APPLICATION_AUTHOR)
+                            .arg(Arg::with_name(MODEL_PATH_PARAMETER)
+                                .long(MODEL_PATH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .help(ONLINE_MODEL_PATH_PARAMETER_HELP))
+                            .arg(Arg::with_name(DATASET_PATH_PARAMETER)
+                                .long(DATASET_PATH_PARAMETER)
+                                .takes_value(true)
+                                .help(ONLINE_DATASET_PATH_PARAMETER_HELP))
+                            .arg(Arg::with_name(CHECKPOINT_EVERY_PARAMETER)
+                                .long(CHECKPOINT_EVERY_PARAMETER)
+                                .takes_value(true)
+                                .help(CHECKPOINT_EVERY_PARAMETER_HELP))
+                            .arg(Arg::with_name(PA_VARIANT_PARAMETER)
+                                .long(PA_VARIANT_PARAMETER)
+                                .takes_value(true)
+                                .possible_values(&[PA, PA_I, PA_II])
+                                .help(PA_VARIANT_PARAMETER_HELP))
+                            .arg(Arg::with_name(AGGRESSIVENESS_PARAMETER)
+                                .long(AGGRESSIVENESS_PARAMETER)
+                                .takes_value(true)
+                                .help(AGGRESSIVENESS_PARAMETER_HELP)))
+                        .subcommand(SubCommand::with_name(LIST_TRAINED_SUBCOMMAND)
+                            .about(LIST_TRAINED_SUBCOMMAND_ABOUT)
+                            .version(SUBCOMMAND_VERSION)
+                            .author(APPLICATION_AUTHOR)
+                            .arg(Arg::with_name(MODEL_PATH_PARAMETER)
+                                .long(MODEL_PATH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .help(LIST_TRAINED_MODEL_PATH_PARAMETER_HELP))
+                            .arg(Arg::with_name(MESSAGE_PARAMETER)
+                                .long(MESSAGE_PARAMETER)
+                                .takes_value(true)
+                                .help(LIST_TRAINED_MESSAGE_PARAMETER_HELP)))
                         .subcommand(SubCommand::with_name(CLEAN_CACHE_SUBCOMMAND)
                             .about(CLEAN_CACHE_SUBCOMMAND_ABOUT)
                             .version(SUBCOMMAND_VERSION)
                             .author(APPLICATION_AUTHOR))
+                        .subcommand(SubCommand::with_name(CREATE_ENSEMBLE_SUBCOMMAND)
+                            .about(CREATE_ENSEMBLE_SUBCOMMAND_ABOUT)
+                            .version(SUBCOMMAND_VERSION)
+                            .author(APPLICATION_AUTHOR)
+                            .arg(Arg::with_name(MEMBER_MODEL_PARAMETER)
+                                .long(MEMBER_MODEL_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1)
+                                .help(MEMBER_MODEL_PARAMETER_HELP))
+                            .arg(Arg::with_name(MEMBER_WEIGHT_PARAMETER)
+                                .long(MEMBER_WEIGHT_PARAMETER)
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1)
+                                .help(MEMBER_WEIGHT_PARAMETER_HELP))
+                            .arg(Arg::with_name(ENSEMBLE_METHOD_PARAMETER)
+                                .long(ENSEMBLE_METHOD_PARAMETER)
+                                .takes_value(true)
+                                .possible_values(&[MAJORITY_VOTE, WEIGHTED_AVERAGE, STACKING])
+                                .default_value(MAJORITY_VOTE)
+                                .help(ENSEMBLE_METHOD_PARAMETER_HELP))
+                            .arg(Arg::with_name(DATASET_PATH_PARAMETER)
+                                .long(DATASET_PATH_PARAMETER)
+                                .required_if(ENSEMBLE_METHOD_PARAMETER, STACKING)
+                                .takes_value(true)
+                                .help(STACKING_DATASET_PATH_PARAMETER_HELP))
+                            .arg(Arg::with_name(MODEL_PATH_PARAMETER)
+                                .long(MODEL_PATH_PARAMETER)
+                                .required(true)
+                                .takes_value(true)
+                                .help(MODEL_PATH_PARAMETER_HELP)))
                         .arg(Arg::with_name(FROM_MODEL_PARAMETER)
                             .long(FROM_MODEL_PARAMETER)
                             .help(FROM_MODEL_PARAMETER_HELP)
-                            .takes_value(true))
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1))
+                        .arg(Arg::with_name(ENSEMBLE_METHOD_PARAMETER)
+                            .long(ENSEMBLE_METHOD_PARAMETER)
+                            .takes_value(true)
+                            .possible_values(&[MAJORITY_VOTE, WEIGHTED_AVERAGE])
+                            .default_value(MAJORITY_VOTE)
+                            .help(ENSEMBLE_METHOD_PARAMETER_HELP))
                         .arg(Arg::with_name(MESSAGE_PARAMETER)
                             .long(MESSAGE_PARAMETER)
                             .required(true)
@@ -70,6 +483,14 @@ fn main() {
                             .conflicts_with(MESSAGE_PARAMETER)
                             .help(MESSAGE_FROM_FILE_PARAMETER_HELP)
                             .takes_value(true))
+                        .arg(Arg::with_name(EXPLAIN_PARAMETER)
+                            .long(EXPLAIN_PARAMETER)
+                            .requires(MESSAGE_PARAMETER)
+                            .help(EXPLAIN_PARAMETER_HELP))
+                        .arg(Arg::with_name(THREADS_PARAMETER)
+                            .long(THREADS_PARAMETER)
+                            .help(THREADS_PARAMETER_HELP)
+                            .takes_value(true))
                         .get_matches();
 
     let cache = Cache::new();
@@ -82,17 +503,33 @@ fn main() {
 
     execute_clean_cache_if_specified(&cache_unwraped, &matches);
 
+    execute_validate_dataset_if_specified(&matches);
+
+    execute_dataset_stats_if_specified(&matches);
+
+    execute_evaluate_if_specified(&matches);
+
+    execute_calibrate_if_specified(&matches);
+
     execute_create_model_from_dataset_if_specified(&matches);
+
+    execute_tune_if_specified(&matches);
     
-    let model_filename = get_model_filename(&matches);
+    execute_create_ensemble_if_specified(&matches);
+
+    execute_train_online_if_specified(&matches);
+
+    execute_list_trained_if_specified(&matches);
+
+    let model_filenames = get_model_filenames(&matches);
     
-    execute_message_if_specified(&model_filename, &mut cache_unwraped, &matches);
+    execute_message_if_specified(&model_filenames, &mut cache_unwraped, &matches);
 
-    execute_message_from_file_if_specified(&model_filename, &cache_unwraped, &matches);
+    execute_message_from_file_if_specified(&model_filenames, &cache_unwraped, &matches);
 }
 
 pub fn execute_clean_cache_if_specified(cache : &Cache, matches: &ArgMatches){
-    if let Some(_) = matches.subcommand_matches(CLEAN_CACHE_SUBCOMMAND) {
+    if matches.subcommand_matches(CLEAN_CACHE_SUBCOMMAND).is_some() {
         match cache.clean_cache() {
             Ok(_) => {}
             Err(error) => panic!("An error ocurred while trying to clean the cache - {:?}", error),
@@ -102,21 +539,166 @@ pub fn execute_clean_cache_if_specified(cache : &Cache, matches: &ArgMatches){
 
 pub fn execute_create_model_from_dataset_if_specified(matches: &ArgMatches){
     if let Some(matches) = matches.subcommand_matches(CREATE_MODEL_FROM_DATASET_SUBCOMMAND) {
+        let dataset_path = matches.value_of(DATASET_PATH_PARAMETER).unwrap();
+        let model_path = matches.value_of(MODEL_PATH_PARAMETER);
+
+        if let Some(model_path) = model_path.filter(|model_path| Path::new(model_path).exists()) {
+            panic!("The specified model file - {:?}, already exist!", model_path);
+        }
+
+        let mut messages = read_training_messages(dataset_path, matches);
+        if let Some(deduplication) = get_deduplication(matches) {
+            let (unique_messages, report) = deduplicate(messages, deduplication);
+            println!("Dropped {} ham and {} spam duplicate(s)", report.ham_dropped, report.spam_dropped);
+            messages = unique_messages;
+        }
+
+        let hyperparameters = get_hyperparameters(matches);
+        let strategy = hyperparameters.strategy();
+        if let Some(validation) = get_validation(matches) {
+            match validate(&messages, validation, strategy, get_cutoffs(matches), || hyperparameters.new_classifier()) {
+                Ok(report) => print_validation_report(&report),
+                Err(error) => panic!("An error ocurred while validating the model - {:?}", error),
+            }
+        }
+
+        let model_path = match model_path {
+            Some(model_path) => model_path,
+            None => return,
+        };
+        let model_file = File::create(model_path);
+        match model_file {
+            Ok(_) => {}
+            Err(error) => panic!("Failed to create file with name {} - {:?}",model_path, error),
+        }
+
+        let mut classifier = hyperparameters.new_classifier();
+        let report = train_messages(classifier.as_mut(), &messages, strategy);
+        println!("Learned {} of {} messages ({} ham, {} spam) in {} pass(es)",
+                report.messages_learned, report.messages_seen, report.ham_learned, report.spam_learned, report.passes);
+
+        match classifier.save(&mut model_file.unwrap()) {
+            Ok(_) => {}
+            Err(error) => panic!("An error ocurred while publishing the model to the file - {:?}", error),
+        }
+    }
+}
+
+pub fn execute_tune_if_specified(matches: &ArgMatches){
+    if let Some(matches) = matches.subcommand_matches(TUNE_SUBCOMMAND) {
         let dataset_path = matches.value_of(DATASET_PATH_PARAMETER).unwrap();
         let model_path = matches.value_of(MODEL_PATH_PARAMETER).unwrap();
+        let best_config_path = matches.value_of(BEST_CONFIG_PATH_PARAMETER);
+        for path in Some(model_path).iter().chain(best_config_path.iter()) {
+            if Path::new(path).exists(){
+                panic!("The specified file - {:?}, already exist!", path);
+            }
+        }
 
-        if !Path::new(dataset_path).exists(){
-            panic!("The specified dataset file path - {:?}, does not exist!", dataset_path);
+        let dimensions: Vec<SearchDimension> = matches.values_of(SEARCH_PARAMETER).unwrap()
+            .map(|specification| match SearchDimension::parse(specification) {
+                Ok(dimension) => dimension,
+                Err(error) => panic!("Invalid --{} {:?} - {:?}", SEARCH_PARAMETER, specification, error),
+            })
+            .collect();
+        let seed = parse_parameter(matches, SEED_PARAMETER, DEFAULT_SEED);
+        let search_name = matches.value_of(SEARCH_STRATEGY_PARAMETER).unwrap();
+        let search = match Search::from_name(search_name, parse_parameter(matches, TRIALS_PARAMETER, DEFAULT_TRIALS), seed) {
+            Some(search) => search,
+            None => panic!("Unknown search strategy - {:?}", search_name),
+        };
+        let metric_name = matches.value_of(METRIC_PARAMETER).unwrap();
+        let metric = match Metric::from_name(metric_name) {
+            Some(metric) => metric,
+            None => panic!("Unknown metric - {:?}", metric_name),
+        };
+        let validation = get_validation(matches).unwrap_or(Validation::CrossValidation { folds: DEFAULT_FOLDS, seed });
+
+        let base = get_hyperparameters(matches);
+        let messages = read_training_messages(dataset_path, matches);
+        let configurations = match search.configurations(&dimensions) {
+            Ok(configurations) => configurations,
+            Err(error) => panic!("Invalid --{} - {:?}", SEARCH_PARAMETER, error),
+        };
+        println!("Trying {} configuration(s), ranked by {}", configurations.len(), metric.as_str());
+        let report = tune(&messages, &base, configurations, validation, metric, |trial| {
+            println!("{}: {:.4} ± {:.4}", configuration_arguments(&trial.configuration), trial.score.mean, trial.score.standard_deviation());
+        });
+        let report = match report {
+            Ok(report) => report,
+            Err(error) => panic!("An error ocurred while tuning the model - {:?}", error),
+        };
+        if !report.skipped.is_empty() {
+            println!("Skipped {} configuration(s) whose ham cutoff is above their spam cutoff", report.skipped.len());
+        }
+
+        println!("{:>4}  {:>16}  Configuration", "Rank", metric.as_str());
+        for (rank, trial) in report.trials.iter().enumerate() {
+            let score = format!("{:.4} ± {:.4}", trial.score.mean, trial.score.standard_deviation());
+            println!("{:>4}  {:>16}  {}", rank + 1, score, configuration_arguments(&trial.configuration));
+        }
+
+        let best = match report.best() {
+            Some(best) => best,
+            None => panic!("None of the configurations could be tried"),
+        };
+        println!("Best configuration: {}", configuration_arguments(&best.configuration));
+
+        if let Some(best_config_path) = best_config_path {
+            let written = File::create(best_config_path)
+                .map_err(SpamClassifierError::IO)
+                .and_then(|file| serde_json::to_writer_pretty(file, best).map_err(SpamClassifierError::Serde));
+            if let Err(error) = written {
+                panic!("An error ocurred while writing the best configuration to {} - {:?}", best_config_path, error);
+            }
         }
 
+        let hyperparameters = match base.with_configuration(&best.configuration) {
+            Ok(hyperparameters) => hyperparameters,
+            Err(error) => panic!("An error ocurred while applying the best configuration - {:?}", error),
+        };
+        let model_file = File::create(model_path);
+        match model_file {
+            Ok(_) => {}
+            Err(error) => panic!("Failed to create file with name {} - {:?}",model_path, error),
+        }
+
+        let mut classifier = hyperparameters.new_classifier();
+        let training_report = train_messages(classifier.as_mut(), &messages, hyperparameters.strategy());
+        println!("Learned {} of {} messages ({} ham, {} spam) in {} pass(es)",
+                training_report.messages_learned, training_report.messages_seen, training_report.ham_learned, training_report.spam_learned, training_report.passes);
+
+        match classifier.save(&mut model_file.unwrap()) {
+            Ok(_) => {}
+            Err(error) => panic!("An error ocurred while publishing the model to the file - {:?}", error),
+        }
+    }
+}
+
+/// The configuration as the command line options which select it.
+fn configuration_arguments(configuration: &Configuration) -> String {
+    configuration.iter()
+        .map(|(name, value)| format!("--{} {}", name, value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn execute_create_ensemble_if_specified(matches: &ArgMatches){
+    if let Some(matches) = matches.subcommand_matches(CREATE_ENSEMBLE_SUBCOMMAND) {
+        let model_path = matches.value_of(MODEL_PATH_PARAMETER).unwrap();
         if Path::new(model_path).exists(){
             panic!("The specified model file - {:?}, already exist!", model_path);
         }
 
-        let dataset_file = File::open(dataset_path);
-        match dataset_file {
-            Ok(_) => {}
-            Err(error) => panic!("Failed to open file with name {} - {:?}",dataset_path, error),
+        let members = matches.values_of(MEMBER_MODEL_PARAMETER).unwrap().map(load_model_file).collect();
+        let weights = matches.values_of(MEMBER_WEIGHT_PARAMETER).map(|weights| weights.map(|weight| match weight.parse() {
+            Ok(parsed) => parsed,
+            Err(error) => panic!("The value {:?} of --{} is not a valid number - {:?}", weight, MEMBER_WEIGHT_PARAMETER, error),
+        }).collect());
+
+        let mut ensemble = new_ensemble(members, weights, matches);
+        if ensemble.method() == CombinationMethod::Stacking {
+            fit_stacking(&mut ensemble, matches.value_of(DATASET_PATH_PARAMETER).unwrap());
         }
 
         let model_file = File::create(model_path);
@@ -125,102 +707,815 @@ pub fn execute_create_model_from_dataset_if_specified(matches: &ArgMatches){
             Err(error) => panic!("Failed to create file with name {} - {:?}",model_path, error),
         }
 
-        let mut classifier = BayesianSpamClassifier::new();
-        let result = classifier.create_model_from_dataset(&mut dataset_file.unwrap(),&mut model_file.unwrap());
-        match result {
+        match ensemble.save(&mut model_file.unwrap()) {
             Ok(_) => {}
-            Err(error) => panic!("An error ocurred while creating the model and publishing it to the file - {:?}", error),
+            Err(error) => panic!("An error ocurred while publishing the ensemble to the file - {:?}", error),
         }
     }
 }
 
-pub fn get_model_filename(matches: &ArgMatches) -> String{
-    if let Some(from_model) = matches.value_of(FROM_MODEL_PARAMETER) {
-        if !Path::new(from_model).exists(){
-            panic!("The specified model file path - {:?}, does not exist!", from_model);
+fn fit_stacking(ensemble: &mut EnsembleClassifier, dataset_path: &str) {
+    let messages = match read_labelled_messages(open_dataset(dataset_path)) {
+        Ok(messages) => messages,
+        Err(error) => panic!("An error ocurred while reading the dataset {} - {:?}", dataset_path, error),
+    };
+
+    ensemble.fit_stacking(&messages);
+}
+
+pub fn execute_train_online_if_specified(matches: &ArgMatches){
+    if let Some(matches) = matches.subcommand_matches(TRAIN_ONLINE_SUBCOMMAND) {
+        let model_path = matches.value_of(MODEL_PATH_PARAMETER).unwrap();
+        let classifier: Box<dyn SpamClassifier> = if Path::new(model_path).exists() {
+            load_model_file(model_path)
+        } else {
+            Box::new(PassiveAggressiveClassifier::with_config(get_hyperparameters(matches).passive_aggressive))
+        };
+
+        let checkpoint_every = parse_parameter(matches, CHECKPOINT_EVERY_PARAMETER, DEFAULT_CHECKPOINT_EVERY);
+        let mut trainer = CheckpointedTrainer::new(classifier, Path::new(model_path), checkpoint_every);
+
+        let reader: Box<dyn BufRead> = match matches.value_of(DATASET_PATH_PARAMETER) {
+            Some(dataset_path) => match File::open(dataset_path) {
+                Ok(file) => Box::new(std::io::BufReader::new(file)),
+                Err(error) => panic!("Failed to open file with name {} - {:?}",dataset_path, error),
+            },
+            None => Box::new(std::io::BufReader::new(std::io::stdin())),
+        };
+
+        let mut learned = 0;
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(error) => panic!("An error ocurred while reading line {} - {:?}", line_number + 1, error),
+            };
+
+            let message = match parse_dataset_line(&line) {
+                Ok(Some(message)) => message,
+                Ok(None) => continue,
+                Err(error) => panic!("Line {} is not a valid dataset line - {:?}", line_number + 1, error),
+            };
+
+            if let Err(error) = trainer.train(&message.text, message.label) {
+                panic!("An error ocurred while writing a checkpoint to {} - {:?}", model_path, error);
+            }
+            learned += 1;
         }
 
-        return from_model.to_string();
+        let checkpoints = trainer.checkpoints() + 1;
+        match trainer.finish() {
+            Ok(_) => println!("Learned {} messages, wrote {} checkpoint(s) to {}", learned, checkpoints, model_path),
+            Err(error) => panic!("An error ocurred while writing the model to {} - {:?}", model_path, error),
+        }
     }
+}
+
+pub fn execute_list_trained_if_specified(matches: &ArgMatches){
+    if let Some(matches) = matches.subcommand_matches(LIST_TRAINED_SUBCOMMAND) {
+        let model_path = matches.value_of(MODEL_PATH_PARAMETER).unwrap();
+        let classifier = load_model_file(model_path);
+        let ledger = match classifier.ledger() {
+            Some(ledger) => ledger,
+            None => panic!("The model {} is a {} model, which does not record the messages it learns", model_path, classifier.model_type()),
+        };
+
+        if let Some(message) = matches.value_of(MESSAGE_PARAMETER) {
+            match ledger.label_of(message) {
+                Some(label) => println!("The message - {:?}, was learned as {}", message, label.as_str()),
+                None => println!("The message - {:?}, was not learned", message),
+            }
+            return;
+        }
 
-    DEFAULT_MODEL_PATH.to_string()
+        match ledger.write(&mut std::io::stdout()) {
+            Ok(_) => {}
+            Err(error) => panic!("An error ocurred while listing the learned messages - {:?}", error),
+        }
+        println!("Learned {} ham and {} spam messages", ledger.count(Label::Ham), ledger.count(Label::Spam));
+    }
 }
 
-pub fn execute_message_if_specified(model_filename: &str, cache: &mut Cache,matches: &ArgMatches){
-    if let Some(message) = matches.value_of(MESSAGE_PARAMETER) {        
-        let file = File::open(model_filename);
+/// Opens the dataset at `dataset_path`, or the standard input when the path is `-`.
+fn open_dataset(dataset_path: &str) -> Box<dyn Read> {
+    if dataset_path == STANDARD_INPUT_PATH {
+        return Box::new(std::io::stdin());
+    }
+
+    if !Path::new(dataset_path).exists(){
+        panic!("The specified dataset file path - {:?}, does not exist!", dataset_path);
+    }
+
+    match File::open(dataset_path) {
+        Ok(file) => Box::new(file),
+        Err(error) => panic!("Failed to open file with name {} - {:?}",dataset_path, error),
+    }
+}
+
+fn read_training_messages(dataset_path: &str, matches: &ArgMatches) -> Vec<LabelledMessage> {
+    let format = matches.value_of(DATASET_FORMAT_PARAMETER);
+    if (format == Some(MAILBOX) || format == Some(CORPUS)) && !Path::new(dataset_path).exists() {
+        panic!("The specified dataset path - {:?}, does not exist!", dataset_path);
+    }
+
+    let messages = match format {
+        Some(MAILBOX) => read_mailbox(Path::new(dataset_path), &get_folder_mapping(matches, FolderMapping::default())),
+        Some(CORPUS) => read_corpus_messages(dataset_path, matches),
+        _ => {
+            let validated = validate_dataset_file(dataset_path, matches);
+            if !validated.is_valid() && !matches.is_present(SKIP_INVALID_PARAMETER) {
+                panic!("The dataset {} has {} invalid record(s), use --{} to train on the rest",
+                    dataset_path, validated.invalid_records().count(), SKIP_INVALID_PARAMETER);
+            }
+            Ok(validated.messages)
+        }
+    };
+
+    match messages {
+        Ok(messages) => messages,
+        Err(error) => panic!("An error ocurred while reading the dataset {} - {:?}", dataset_path, error),
+    }
+}
+
+pub fn execute_validate_dataset_if_specified(matches: &ArgMatches){
+    if let Some(matches) = matches.subcommand_matches(VALIDATE_DATASET_SUBCOMMAND) {
+        let dataset_path = matches.value_of(DATASET_PATH_PARAMETER).unwrap();
+        validate_dataset_file(dataset_path, matches);
+    }
+}
+
+/// Reads the whole dataset, printing every problem and the number of messages per
+/// label, and writes the rejected records to --rejects-path when given.
+fn validate_dataset_file(dataset_path: &str, matches: &ArgMatches) -> ValidatedDataset {
+    let validated = read_dataset_file(dataset_path, matches);
+
+    for problem in &validated.problems {
+        println!("Line {} - {} - {:?}", problem.line, problem.reason, problem.snippet());
+    }
+    let unknown_labels = validated.problems.len() - validated.invalid_records().count();
+    println!("Read {} ham and {} spam messages, {} with an unknown label, {} invalid record(s)",
+            validated.count(Label::Ham), validated.count(Label::Spam), unknown_labels, validated.invalid_records().count());
+
+    if let Some(rejects_path) = matches.value_of(REJECTS_PATH_PARAMETER) {
+        let rejects_file = File::create(rejects_path);
+        match rejects_file {
+            Ok(_) => {}
+            Err(error) => panic!("Failed to create file with name {} - {:?}", rejects_path, error),
+        }
+
+        match validated.write_rejects(&mut rejects_file.unwrap()) {
+            Ok(_) => {}
+            Err(error) => panic!("An error ocurred while writing the rejected records to {} - {:?}", rejects_path, error),
+        }
+    }
+
+    validated
+}
+
+/// Reads a dataset in any format without printing anything. Mailboxes and corpora
+/// have no records which can be invalid.
+fn read_dataset_file(dataset_path: &str, matches: &ArgMatches) -> ValidatedDataset {
+    let format = matches.value_of(DATASET_FORMAT_PARAMETER);
+    if (format == Some(MAILBOX) || format == Some(CORPUS)) && !Path::new(dataset_path).exists() {
+        panic!("The specified dataset path - {:?}, does not exist!", dataset_path);
+    }
+
+    let validated = match format {
+        Some(MAILBOX) => read_mailbox(Path::new(dataset_path), &get_folder_mapping(matches, FolderMapping::default()))
+            .map(|messages| ValidatedDataset { messages, ..ValidatedDataset::default() }),
+        Some(CORPUS) => read_corpus(Path::new(dataset_path), &get_folder_mapping(matches, default_directory_mapping()))
+            .map(|corpus| ValidatedDataset { messages: corpus.messages, ..ValidatedDataset::default() }),
+        _ => validate_dataset(open_dataset(dataset_path), get_dataset_format(matches), &get_column_mapping(matches)),
+    };
+
+    match validated {
+        Ok(validated) => validated,
+        Err(error) => panic!("An error ocurred while reading the dataset {} - {:?}", dataset_path, error),
+    }
+}
+
+pub fn execute_dataset_stats_if_specified(matches: &ArgMatches){
+    if let Some(matches) = matches.subcommand_matches(DATASET_STATS_SUBCOMMAND) {
+        let dataset_path = matches.value_of(DATASET_PATH_PARAMETER).unwrap();
+        let top_tokens = parse_parameter(matches, TOP_TOKENS_PARAMETER, DEFAULT_TOP_TOKENS);
+        let stats = DatasetStats::compute(&read_dataset_file(dataset_path, matches), top_tokens);
+
+        if matches.value_of(OUTPUT_FORMAT_PARAMETER) == Some(JSON_OUTPUT) {
+            match serde_json::to_string_pretty(&stats) {
+                Ok(json) => println!("{}", json),
+                Err(error) => panic!("An error ocurred while serializing the dataset statistics - {:?}", error),
+            }
+            return;
+        }
+
+        println!("Messages: {} ({} ham, {} spam)", stats.messages, stats.ham.messages, stats.spam.messages);
+        println!("Vocabulary size: {}", stats.vocabulary_size);
+        println!("Duplicates: {}", stats.duplicates);
+        println!("Non-UTF-8 lines: {}", stats.invalid_utf8_lines);
+        println!("Invalid records: {}", stats.invalid_records);
+        println!("Unknown labels: {}", stats.unknown_labels);
+        print_label_stats(Label::Ham, &stats.ham);
+        print_label_stats(Label::Spam, &stats.spam);
+    }
+}
+
+fn print_label_stats(label: Label, stats: &LabelStats) {
+    println!("{:?}: {} messages, {} duplicates", label, stats.messages, stats.duplicates);
+    for (unit, lengths) in [("characters", &stats.characters), ("tokens", &stats.tokens)].iter() {
+        println!("  Length in {}: min {}, median {}, mean {:.1}, 90th percentile {}, max {}",
+                unit, lengths.min, lengths.median, lengths.mean, lengths.p90, lengths.max);
+    }
+    let top_tokens: Vec<String> = stats.top_tokens.iter().map(|token| format!("{} ({})", token.token, token.count)).collect();
+    println!("  Top tokens: {}", top_tokens.join(", "));
+}
+
+pub fn execute_evaluate_if_specified(matches: &ArgMatches){
+    if let Some(matches) = matches.subcommand_matches(EVALUATE_SUBCOMMAND) {
+        let model_path = matches.value_of(MODEL_PATH_PARAMETER).unwrap();
+        let dataset_path = matches.value_of(DATASET_PATH_PARAMETER).unwrap();
+        let classifier = load_model_file(model_path);
+        let messages = read_test_messages(dataset_path, matches);
+        let evaluation = evaluate(classifier.as_ref(), &messages, get_cutoffs(matches));
+        if let Some(curves_directory) = matches.value_of(CURVES_DIRECTORY_PARAMETER) {
+            write_curves(&evaluation.curves, &evaluation.reliability, Path::new(curves_directory));
+        }
+
+        if matches.value_of(OUTPUT_FORMAT_PARAMETER) == Some(JSON_OUTPUT) {
+            match serde_json::to_string_pretty(&evaluation) {
+                Ok(json) => println!("{}", json),
+                Err(error) => panic!("An error ocurred while serializing the evaluation - {:?}", error),
+            }
+            return;
+        }
+
+        print_evaluation(&evaluation);
+        for mistake in &evaluation.mistakes {
+            println!("Message {} - {} classified as {} (spam probability {:.4}) - {:?}",
+                    mistake.message, mistake.label.as_str(), mistake.verdict.as_str(), mistake.spam_probability, snippet(&mistake.text));
+        }
+    }
+}
+
+pub fn execute_calibrate_if_specified(matches: &ArgMatches){
+    if let Some(matches) = matches.subcommand_matches(CALIBRATE_SUBCOMMAND) {
+        let model_path = matches.value_of(MODEL_PATH_PARAMETER).unwrap();
+        let dataset_path = matches.value_of(DATASET_PATH_PARAMETER).unwrap();
+        let calibrated_model_path = matches.value_of(CALIBRATED_MODEL_PATH_PARAMETER).unwrap();
+        if Path::new(calibrated_model_path).exists(){
+            panic!("The specified model file - {:?}, already exist!", calibrated_model_path);
+        }
+
+        let method_name = matches.value_of(CALIBRATION_METHOD_PARAMETER).unwrap();
+        let method = match CalibrationMethod::from_name(method_name) {
+            Some(method) => method,
+            None => panic!("Unknown calibration method - {:?}", method_name),
+        };
+
+        let messages = read_test_messages(dataset_path, matches);
+        let model = load_model_file(model_path);
+        let texts: Vec<&str> = messages.iter().map(|message| message.text.as_str()).collect();
+        let labels: Vec<Label> = messages.iter().map(|message| message.label).collect();
+        let classifications = model.classify_batch(&texts);
+
+        let (tested, before, after) = held_out_calibration_errors(&messages, &classifications, method);
+        println!("Expected calibration error on {} messages left out of fitting: {:.4} before, {:.4} after", tested, before, after);
+        let calibrated = CalibratedClassifier::new(model, Calibrator::fit(method, &classifications, &labels));
+
+        let model_file = File::create(calibrated_model_path);
+        match model_file {
+            Ok(_) => {}
+            Err(error) => panic!("Failed to create file with name {} - {:?}", calibrated_model_path, error),
+        }
+
+        match calibrated.save(&mut model_file.unwrap()) {
+            Ok(_) => {}
+            Err(error) => panic!("An error ocurred while publishing the model to the file - {:?}", error),
+        }
+    }
+}
+
+/// Fits a calibrator on a stratified part of the messages and measures the
+/// expected calibration error on the rest, before and after calibrating, since
+/// on the messages it was fitted on a calibrator looks better than it is.
+fn held_out_calibration_errors(messages: &[LabelledMessage], classifications: &[Classification], method: CalibrationMethod) -> (usize, f64, f64) {
+    let validation = Validation::Holdout { test_fraction: CALIBRATION_TEST_FRACTION, seed: DEFAULT_SEED };
+    let split = match validation.splits(messages) {
+        Ok(splits) => splits[0].clone(),
+        Err(error) => panic!("Failed to split the calibration set - {:?}", error),
+    };
+    let part = |indices: &[usize]| -> (Vec<LabelledMessage>, Vec<Classification>) {
+        indices.iter().map(|&index| (messages[index].clone(), classifications[index])).unzip()
+    };
+
+    let (fitted, fitted_classifications) = part(&split.train);
+    let labels: Vec<Label> = fitted.iter().map(|message| message.label).collect();
+    let calibrator = Calibrator::fit(method, &fitted_classifications, &labels);
+    let (tested, tested_classifications) = part(&split.test);
+    let calibrated: Vec<Classification> = tested_classifications.iter().map(|classification| calibrator.calibrate(classification)).collect();
+
+    let calibration_error = |classifications: &[Classification]| {
+        Evaluation::from_classifications(&tested, classifications, Cutoffs::default()).reliability.expected_calibration_error
+    };
+    (tested.len(), calibration_error(&tested_classifications), calibration_error(&calibrated))
+}
+
+/// Reads a labelled test set in any format, stopping on invalid records unless
+/// --skip-invalid is given.
+fn read_test_messages(dataset_path: &str, matches: &ArgMatches) -> Vec<LabelledMessage> {
+    let validated = read_dataset_file(dataset_path, matches);
+    if !validated.is_valid() && !matches.is_present(SKIP_INVALID_PARAMETER) {
+        panic!("The dataset {} has {} invalid record(s), use --{} to evaluate on the rest",
+            dataset_path, validated.invalid_records().count(), SKIP_INVALID_PARAMETER);
+    }
+
+    validated.messages
+}
+
+/// Writes the curves and the reliability diagram as CSV points and as SVG images
+/// into `directory`, creating it if needed.
+fn write_curves(curves: &Curves, reliability: &Reliability, directory: &Path) {
+    match std::fs::create_dir_all(directory) {
+        Ok(_) => {}
+        Err(error) => panic!("Failed to create directory {} - {:?}", directory.display(), error),
+    }
+
+    let write_file = |name: &str, write: &dyn Fn(&mut File) -> std::io::Result<()>| {
+        let path = directory.join(name);
+        let file = File::create(&path);
         match file {
             Ok(_) => {}
-            Err(error) => panic!("Failed to open file with name {} - {:?}",model_filename, error),
+            Err(error) => panic!("Failed to create file with name {} - {:?}", path.display(), error),
         }
 
-        let cached_classification = cache.get_from_cache(&message.to_string());
-        if !cached_classification.is_none(){
-            let (spam_prob, ham_prob) = cached_classification.unwrap();
-            print_spam_or_ham(spam_prob,ham_prob,message);
-        } else {
-            let classifier = BayesianSpamClassifier::new_from_pre_trained(&mut file.unwrap());
-            match classifier {
-                Ok(_) => {}
-                Err(error) => panic!("An error ocurred while creating classifier from the pre-trained model {} - {:?}",model_filename, error),
+        match write(&mut file.unwrap()) {
+            Ok(_) => {}
+            Err(error) => panic!("An error ocurred while writing the curve to {} - {:?}", path.display(), error),
+        }
+    };
+
+    write_file(ROC_CSV_FILE, &|file| curves.write_roc_csv(file));
+    write_file(PR_CSV_FILE, &|file| curves.write_pr_csv(file));
+    write_file(ROC_SVG_FILE, &|file| file.write_all(curves.roc_svg().as_bytes()));
+    write_file(PR_SVG_FILE, &|file| file.write_all(curves.pr_svg().as_bytes()));
+    write_file(RELIABILITY_CSV_FILE, &|file| {
+        writeln!(file, "lower,upper,messages,mean_probability,spam_fraction")?;
+        for bin in &reliability.bins {
+            writeln!(file, "{},{},{},{},{}", bin.lower, bin.upper, bin.messages, bin.mean_probability, bin.spam_fraction)?;
+        }
+        Ok(())
+    });
+    write_file(RELIABILITY_SVG_FILE, &|file| file.write_all(reliability.svg().as_bytes()));
+}
+
+fn print_evaluation(evaluation: &Evaluation) {
+    let matrix = &evaluation.confusion_matrix;
+    println!("Messages: {} ({} ham, {} spam)", evaluation.messages, matrix.ham(), matrix.spam());
+    println!("Accuracy: {:.4}", evaluation.accuracy);
+    println!("Precision: {:.4}", evaluation.precision);
+    println!("Recall: {:.4}", evaluation.recall);
+    println!("F1: {:.4}", evaluation.f1);
+    println!("False positive rate: {:.4}", evaluation.false_positive_rate);
+    println!("Unsure: {} ({:.4})", matrix.unsure(), evaluation.unsure_rate);
+    println!("ROC-AUC: {:.4}", evaluation.roc_auc);
+    println!("PR-AUC: {:.4}", evaluation.pr_auc);
+    println!("Expected calibration error: {:.4}", evaluation.reliability.expected_calibration_error);
+    println!("{:>12}{:>10}{:>10}{:>10}", "", "as ham", "unsure", "as spam");
+    println!("{:>12}{:>10}{:>10}{:>10}", "ham", matrix.ham_as_ham, matrix.ham_as_unsure, matrix.ham_as_spam);
+    println!("{:>12}{:>10}{:>10}{:>10}", "spam", matrix.spam_as_ham, matrix.spam_as_unsure, matrix.spam_as_spam);
+}
+
+fn print_validation_report(report: &ValidationReport) {
+    for (index, evaluation) in report.splits.iter().enumerate() {
+        println!("Split {} - {} test messages, accuracy {:.4}, precision {:.4}, recall {:.4}, F1 {:.4}, false positive rate {:.4}",
+                index + 1, evaluation.messages, evaluation.accuracy, evaluation.precision, evaluation.recall, evaluation.f1, evaluation.false_positive_rate);
+    }
+
+    let metrics = [
+        ("Accuracy", &report.accuracy),
+        ("Precision", &report.precision),
+        ("Recall", &report.recall),
+        ("F1", &report.f1),
+        ("False positive rate", &report.false_positive_rate),
+        ("Unsure rate", &report.unsure_rate),
+        ("ROC-AUC", &report.roc_auc),
+        ("PR-AUC", &report.pr_auc),
+        ("Expected calibration error", &report.expected_calibration_error),
+    ];
+    for (name, summary) in metrics.iter() {
+        print_metric_summary(name, summary);
+    }
+}
+
+fn print_metric_summary(name: &str, summary: &MetricSummary) {
+    println!("{}: {:.4} ± {:.4} (variance {:.6})", name, summary.mean, summary.standard_deviation(), summary.variance);
+}
+
+fn read_corpus_messages(dataset_path: &str, matches: &ArgMatches) -> Result<Vec<LabelledMessage>, SpamClassifierError> {
+    let corpus = read_corpus(Path::new(dataset_path), &get_folder_mapping(matches, default_directory_mapping()))?;
+    println!("Read {} ham and {} spam messages, {} unreadable file(s)",
+            corpus.count(Label::Ham), corpus.count(Label::Spam), corpus.unreadable_files.len());
+    for unreadable_file in &corpus.unreadable_files {
+        println!("Could not read {} - {}", unreadable_file.path.display(), unreadable_file.error);
+    }
+
+    Ok(corpus.messages)
+}
+
+fn dataset_format_arguments<'a>(formats: &'a [&'a str]) -> Vec<Arg<'a, 'a>> {
+    vec![
+        Arg::with_name(DATASET_FORMAT_PARAMETER)
+            .long(DATASET_FORMAT_PARAMETER)
+            .takes_value(true)
+            .possible_values(formats)
+            .default_value(TSV)
+            .help(DATASET_FORMAT_PARAMETER_HELP),
+        Arg::with_name(LABEL_COLUMN_PARAMETER)
+            .long(LABEL_COLUMN_PARAMETER)
+            .takes_value(true)
+            .help(LABEL_COLUMN_PARAMETER_HELP),
+        Arg::with_name(TEXT_COLUMN_PARAMETER)
+            .long(TEXT_COLUMN_PARAMETER)
+            .takes_value(true)
+            .help(TEXT_COLUMN_PARAMETER_HELP),
+        Arg::with_name(SPAM_LABEL_PARAMETER)
+            .long(SPAM_LABEL_PARAMETER)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help(SPAM_LABEL_PARAMETER_HELP),
+        Arg::with_name(HAM_LABEL_PARAMETER)
+            .long(HAM_LABEL_PARAMETER)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help(HAM_LABEL_PARAMETER_HELP),
+    ]
+}
+
+/// The options of a new model and of how it is trained, named as the tunable
+/// hyperparameters.
+fn model_arguments<'a>() -> Vec<Arg<'a, 'a>> {
+    vec![
+        Arg::with_name(TRAINING_STRATEGY_PARAMETER)
+            .long(TRAINING_STRATEGY_PARAMETER)
+            .takes_value(true)
+            .possible_values(&[TRAIN_EVERYTHING, TRAIN_ON_ERROR, TRAIN_UNTIL_MATURE, TRAIN_UNTIL_NO_ERROR])
+            .default_value(TRAIN_EVERYTHING)
+            .help(TRAINING_STRATEGY_PARAMETER_HELP),
+        Arg::with_name(MATURITY_PARAMETER)
+            .long(MATURITY_PARAMETER)
+            .takes_value(true)
+            .help(MATURITY_PARAMETER_HELP),
+        Arg::with_name(MAX_PASSES_PARAMETER)
+            .long(MAX_PASSES_PARAMETER)
+            .takes_value(true)
+            .help(MAX_PASSES_PARAMETER_HELP),
+        Arg::with_name(MODEL_TYPE_PARAMETER)
+            .long(MODEL_TYPE_PARAMETER)
+            .takes_value(true)
+            .possible_values(&[BAYESIAN_MODEL_TYPE, LOGISTIC_REGRESSION_MODEL_TYPE, LINEAR_SVM_MODEL_TYPE, PASSIVE_AGGRESSIVE_MODEL_TYPE])
+            .default_value(BAYESIAN_MODEL_TYPE)
+            .help(MODEL_TYPE_PARAMETER_HELP),
+        Arg::with_name(FEATURES_PARAMETER)
+            .long(FEATURES_PARAMETER)
+            .takes_value(true)
+            .possible_values(&[UNIGRAM, OSB, SBPH])
+            .default_value(UNIGRAM)
+            .help(FEATURES_PARAMETER_HELP),
+        Arg::with_name(FEATURE_WINDOW_PARAMETER)
+            .long(FEATURE_WINDOW_PARAMETER)
+            .takes_value(true)
+            .help(FEATURE_WINDOW_PARAMETER_HELP),
+        Arg::with_name(HEADERS_PARAMETER)
+            .long(HEADERS_PARAMETER)
+            .takes_value(true)
+            .default_value(DEFAULT_HEADERS)
+            .help(HEADERS_PARAMETER_HELP),
+        Arg::with_name(LEARNING_RATE_PARAMETER)
+            .long(LEARNING_RATE_PARAMETER)
+            .takes_value(true)
+            .help(LEARNING_RATE_PARAMETER_HELP),
+        Arg::with_name(L1_PARAMETER)
+            .long(L1_PARAMETER)
+            .takes_value(true)
+            .help(L1_PARAMETER_HELP),
+        Arg::with_name(L2_PARAMETER)
+            .long(L2_PARAMETER)
+            .takes_value(true)
+            .help(L2_PARAMETER_HELP),
+        Arg::with_name(EPOCHS_PARAMETER)
+            .long(EPOCHS_PARAMETER)
+            .takes_value(true)
+            .help(EPOCHS_PARAMETER_HELP),
+        Arg::with_name(OPTIMIZER_PARAMETER)
+            .long(OPTIMIZER_PARAMETER)
+            .takes_value(true)
+            .possible_values(&[SGD, ADAGRAD])
+            .help(OPTIMIZER_PARAMETER_HELP),
+        Arg::with_name(LAMBDA_PARAMETER)
+            .long(LAMBDA_PARAMETER)
+            .takes_value(true)
+            .help(LAMBDA_PARAMETER_HELP),
+        Arg::with_name(PA_VARIANT_PARAMETER)
+            .long(PA_VARIANT_PARAMETER)
+            .takes_value(true)
+            .possible_values(&[PA, PA_I, PA_II])
+            .help(PA_VARIANT_PARAMETER_HELP),
+        Arg::with_name(AGGRESSIVENESS_PARAMETER)
+            .long(AGGRESSIVENESS_PARAMETER)
+            .takes_value(true)
+            .help(AGGRESSIVENESS_PARAMETER_HELP),
+        Arg::with_name(SEED_PARAMETER)
+            .long(SEED_PARAMETER)
+            .takes_value(true)
+            .help(SEED_PARAMETER_HELP),
+    ]
+}
+
+fn cutoff_arguments<'a>() -> Vec<Arg<'a, 'a>> {
+    vec![
+        Arg::with_name(HAM_CUTOFF_PARAMETER)
+            .long(HAM_CUTOFF_PARAMETER)
+            .takes_value(true)
+            .help(HAM_CUTOFF_PARAMETER_HELP),
+        Arg::with_name(SPAM_CUTOFF_PARAMETER)
+            .long(SPAM_CUTOFF_PARAMETER)
+            .takes_value(true)
+            .help(SPAM_CUTOFF_PARAMETER_HELP),
+    ]
+}
+
+fn output_format_argument<'a>() -> Arg<'a, 'a> {
+    Arg::with_name(OUTPUT_FORMAT_PARAMETER)
+        .long(OUTPUT_FORMAT_PARAMETER)
+        .takes_value(true)
+        .possible_values(&[TEXT_OUTPUT, JSON_OUTPUT])
+        .default_value(TEXT_OUTPUT)
+        .help(OUTPUT_FORMAT_PARAMETER_HELP)
+}
+
+fn folder_mapping_arguments<'a>() -> Vec<Arg<'a, 'a>> {
+    vec![
+        Arg::with_name(SPAM_FOLDER_PARAMETER)
+            .long(SPAM_FOLDER_PARAMETER)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help(SPAM_FOLDER_PARAMETER_HELP),
+        Arg::with_name(HAM_FOLDER_PARAMETER)
+            .long(HAM_FOLDER_PARAMETER)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help(HAM_FOLDER_PARAMETER_HELP),
+    ]
+}
+
+fn get_folder_mapping(matches: &ArgMatches, default: FolderMapping) -> FolderMapping {
+    FolderMapping {
+        spam_folders: matches.values_of(SPAM_FOLDER_PARAMETER).map(|folders| folders.map(String::from).collect()).unwrap_or(default.spam_folders),
+        ham_folders: matches.values_of(HAM_FOLDER_PARAMETER).map(|folders| folders.map(String::from).collect()).unwrap_or(default.ham_folders),
+    }
+}
+
+fn get_deduplication(matches: &ArgMatches) -> Option<Deduplication> {
+    let name = matches.value_of(DEDUPLICATE_PARAMETER)?;
+    let max_distance = parse_parameter(matches, MAX_DISTANCE_PARAMETER, DEFAULT_MAX_DISTANCE);
+    match Deduplication::from_name(name, max_distance) {
+        Some(deduplication) => Some(deduplication),
+        None => panic!("Unknown deduplication - {:?}", name),
+    }
+}
+
+fn get_validation(matches: &ArgMatches) -> Option<Validation> {
+    let seed = parse_parameter(matches, SEED_PARAMETER, DEFAULT_SEED);
+    if matches.is_present(HOLDOUT_PARAMETER) {
+        return Some(Validation::Holdout { test_fraction: parse_parameter(matches, HOLDOUT_PARAMETER, 0.0), seed });
+    }
+
+    if matches.is_present(CROSS_VALIDATE_PARAMETER) {
+        return Some(Validation::CrossValidation { folds: parse_parameter(matches, CROSS_VALIDATE_PARAMETER, 0), seed });
+    }
+
+    None
+}
+
+fn get_cutoffs(matches: &ArgMatches) -> Cutoffs {
+    let cutoffs = Cutoffs {
+        ham_cutoff: parse_parameter(matches, HAM_CUTOFF_PARAMETER, DEFAULT_CUTOFF),
+        spam_cutoff: parse_parameter(matches, SPAM_CUTOFF_PARAMETER, DEFAULT_CUTOFF),
+    };
+    if cutoffs.ham_cutoff > cutoffs.spam_cutoff {
+        panic!("The ham cutoff {} is above the spam cutoff {}", cutoffs.ham_cutoff, cutoffs.spam_cutoff);
+    }
+
+    cutoffs
+}
+
+fn get_dataset_format(matches: &ArgMatches) -> DatasetFormat {
+    let format_name = matches.value_of(DATASET_FORMAT_PARAMETER).unwrap();
+    match DatasetFormat::from_name(format_name) {
+        Some(format) => format,
+        None => panic!("Unknown dataset format - {:?}", format_name),
+    }
+}
+
+fn get_column_mapping(matches: &ArgMatches) -> ColumnMapping {
+    let default = ColumnMapping::default();
+    ColumnMapping {
+        label_column: matches.value_of(LABEL_COLUMN_PARAMETER).map(String::from).unwrap_or(default.label_column),
+        text_column: matches.value_of(TEXT_COLUMN_PARAMETER).map(String::from).unwrap_or(default.text_column),
+        spam_labels: matches.values_of(SPAM_LABEL_PARAMETER).map(|labels| labels.map(String::from).collect()).unwrap_or(default.spam_labels),
+        ham_labels: matches.values_of(HAM_LABEL_PARAMETER).map(|labels| labels.map(String::from).collect()).unwrap_or(default.ham_labels),
+    }
+}
+
+/// The hyperparameters given on the command line, on top of the defaults.
+fn get_hyperparameters(matches: &ArgMatches) -> Hyperparameters {
+    let mut hyperparameters = Hyperparameters::default();
+    for name in TUNABLE_PARAMETERS.iter() {
+        if let Some(value) = matches.value_of(name) {
+            if let Err(error) = hyperparameters.set(name, value) {
+                panic!("The value {:?} of --{} is not valid - {:?}", value, name, error);
+            }
+        }
+    }
+
+    if let Err(error) = hyperparameters.check() {
+        panic!("The --{} {:?} does not suit the --{} {:?} - {:?}", FEATURE_WINDOW_PARAMETER, hyperparameters.feature_window, FEATURES_PARAMETER, hyperparameters.features, error);
+    }
+
+    if let Some(headers) = matches.value_of(HEADERS_PARAMETER) {
+        hyperparameters.header_features = HeaderFeatures::from_list(headers);
+    }
+    hyperparameters
+}
+
+fn parse_parameter<T>(matches: &ArgMatches, parameter: &str, default: T) -> T
+    where T: std::str::FromStr, T::Err: std::fmt::Debug {
+    match matches.value_of(parameter) {
+        Some(value) => match value.parse() {
+            Ok(parsed) => parsed,
+            Err(error) => panic!("The value {:?} of --{} is not a valid number - {:?}", value, parameter, error),
+        },
+        None => default,
+    }
+}
+
+pub fn get_model_filenames(matches: &ArgMatches) -> Vec<String>{
+    if let Some(from_models) = matches.values_of(FROM_MODEL_PARAMETER) {
+        return from_models.map(|from_model| {
+            if !Path::new(from_model).exists(){
+                panic!("The specified model file path - {:?}, does not exist!", from_model);
             }
 
-            let classifier_unwraped = classifier.unwrap();
-            let probs = classifier_unwraped.get_spam_ham_probabilities(message);
-            match cache.add_to_cache(&message.to_string(), probs) {
+            from_model.to_string()
+        }).collect();
+    }
+
+    vec![DEFAULT_MODEL_PATH.to_string()]
+}
+
+fn read_model_files(model_filenames: &[String]) -> Vec<Vec<u8>> {
+    model_filenames.iter()
+        .map(|model_filename| match std::fs::read(model_filename) {
+            Ok(bytes) => bytes,
+            Err(error) => panic!("Failed to open file with name {} - {:?}", model_filename, error),
+        })
+        .collect()
+}
+
+/// Identifies the model to classify with in the cache: its files and, for an
+/// ensemble, the ensemble method.
+fn cache_model_key(model_files: &[Vec<u8>], matches: &ArgMatches) -> String {
+    let combination = if model_files.len() > 1 { matches.value_of(ENSEMBLE_METHOD_PARAMETER).unwrap_or_default() } else { "" };
+    model_fingerprint(model_files, combination)
+}
+
+/// Loads the model to classify with from the bytes of its files. Several model
+/// files are combined into an ensemble.
+fn load_classifier(model_filenames: &[String], model_files: &[Vec<u8>], matches: &ArgMatches) -> Box<dyn SpamClassifier> {
+    let mut members: Vec<Box<dyn SpamClassifier>> = model_filenames.iter().zip(model_files)
+        .map(|(model_filename, bytes)| match load_model_from_bytes(bytes) {
+            Ok(classifier) => classifier,
+            Err(error) => panic!("An error ocurred while creating classifier from the pre-trained model {} - {:?}", model_filename, error),
+        })
+        .collect();
+    if members.len() == 1 {
+        return members.remove(0);
+    }
+
+    Box::new(new_ensemble(members, None, matches))
+}
+
+fn new_ensemble(members: Vec<Box<dyn SpamClassifier>>, weights: Option<Vec<f64>>, matches: &ArgMatches) -> EnsembleClassifier {
+    let method_name = matches.value_of(ENSEMBLE_METHOD_PARAMETER).unwrap();
+    let method = match CombinationMethod::from_name(method_name) {
+        Some(method) => method,
+        None => panic!("Unknown ensemble method - {:?}", method_name),
+    };
+
+    let ensemble = match weights {
+        Some(weights) => EnsembleClassifier::with_weights(members, weights, method),
+        None => EnsembleClassifier::new(members, method),
+    };
+    match ensemble {
+        Ok(ensemble) => ensemble,
+        Err(error) => panic!("An error ocurred while combining the models into an ensemble - {:?}", error),
+    }
+}
+
+fn load_model_file(model_filename: &str) -> Box<dyn SpamClassifier> {
+    let file = File::open(model_filename);
+    match file {
+        Ok(_) => {}
+        Err(error) => panic!("Failed to open file with name {} - {:?}", model_filename, error),
+    }
+
+    match load_model(&mut file.unwrap()) {
+        Ok(classifier) => classifier,
+        Err(error) => panic!("An error ocurred while creating classifier from the pre-trained model {} - {:?}", model_filename, error),
+    }
+}
+
+pub fn execute_message_if_specified(model_filenames: &[String], cache: &mut Cache,matches: &ArgMatches){
+    if let Some(message) = matches.value_of(MESSAGE_PARAMETER) {        
+        let model_files = read_model_files(model_filenames);
+        let model_key = cache_model_key(&model_files, matches);
+        let cached_classification = cache.get_from_cache(&model_key, message);
+        let classifier = if matches.is_present(EXPLAIN_PARAMETER) || cached_classification.is_none() {
+            Some(load_classifier(model_filenames, &model_files, matches))
+        } else {
+            None
+        };
+
+        if matches.is_present(EXPLAIN_PARAMETER) {
+            print_explanation(&classifier.as_ref().unwrap().explain(message), "");
+        }
+
+        if let Some(classification) = cached_classification {
+            print_spam_or_ham(&classification,message);
+        } else {
+            let classification = classifier.unwrap().classify(message);
+            match cache.add_to_cache(&model_key, message, &classification) {
                 Ok(_) => {}
                 Err(error) => panic!("An error ocurred while trying to add value {} in cache - {:?}", message, error),
             }
 
-            let (spam_prob, ham_prob) = probs;
-            print_spam_or_ham(spam_prob,ham_prob,message);
+            print_spam_or_ham(&classification,message);
         }
     }
 }
 
-fn print_spam_or_ham(spam_prob: f64, ham_prob: f64,message: &str){
-    if spam_prob > ham_prob {
+fn print_spam_or_ham(classification: &Classification,message: &str){
+    if classification.is_spam() {
         println!("The message - {:?}, is indeed spam!", message);
     } else {
         println!("The message - {:?}, is indeed ham!", message);
     }
 }
 
-pub fn execute_message_from_file_if_specified(model_filename:&str , cache : &Cache,matches: &ArgMatches){
+fn print_explanation(explanation: &Explanation, indent: &str){
+    println!("{}Spam probability: {:.4}", indent, explanation.classification.spam_probability());
+    for contribution in explanation.strongest_contributions(EXPLAINED_FEATURES_COUNT) {
+        println!("{}{:>10.4}  {}", indent, contribution.weight, contribution.feature);
+    }
+
+    for member in &explanation.members {
+        println!("{}Member {}:", indent, member.model_type);
+        print_explanation(&member.explanation, &format!("{}    ", indent));
+    }
+}
+
+pub fn execute_message_from_file_if_specified(model_filenames: &[String], cache : &Cache,matches: &ArgMatches){
     if let Some(file_containing_message) = matches.value_of(MESSAGE_FROM_FILE_PARAMETER) {
-        let file = File::open(file_containing_message);
+        let file = std::fs::read(file_containing_message);
         match file {
             Ok(_) => {}
             Err(error) => panic!("An error ocurred while trying to open provided file with name {} - {:?}", file_containing_message, error),
         }
 
-        let model_file = File::open(model_filename);
-        match model_file {
-            Ok(_) => {}
-            Err(error) => panic!("Failed to open file with name {} - {:?}", model_filename, error),
-        }
+        let model_files = read_model_files(model_filenames);
+        let model_key = cache_model_key(&model_files, matches);
+        let classifier = load_classifier(model_filenames, &model_files, matches);
 
-        let classifier = BayesianSpamClassifier::new_from_pre_trained(&mut model_file.unwrap());
-        match classifier {
-            Ok(_) => {}
-            Err(error) => panic!("An error ocurred while creating classifier from the pre-trained model {} - {:?}", model_filename, error),
-        }
+        // An e-mail is classified as a whole, so that its MIME structure can be parsed.
+        let bytes = file.unwrap();
+        let content = String::from_utf8_lossy(&bytes).to_string();
+        let lines: Vec<String> = if looks_like_email(&content) {
+            vec![content]
+        } else {
+            readable_lines(&bytes)
+        };
+        let uncached_lines: Vec<&str> = lines.iter()
+                    .map(|line| line.as_str())
+                    .filter(|line| cache.get_from_cache(&model_key, line).is_none())
+                    .collect();
+        let cached_classifications = lines.iter().filter_map(|line| cache.get_from_cache(&model_key, line));
+        let classifications = classify_lines(classifier.as_ref(), &uncached_lines, matches);
 
-        let classifier_unwraped = classifier.unwrap();
-
-        let file_buff_reader = std::io::BufReader::new(file.unwrap());
-        let spam_ham_probs = file_buff_reader.lines()
-                    .filter_map(|line: Result<String, _>| line.ok())
-                    .par_bridge()
-                    .map(|line: String| {
-                                let cached_classification = cache.get_from_cache(&line);
-                                if !cached_classification.is_none(){
-                                    return cached_classification.unwrap();
-                                }
-                                
-                                classifier_unwraped.get_spam_ham_probabilities(&line)
-                                })
-                    .reduce( || (NEUTRAL_MULTIPLIER, NEUTRAL_MULTIPLIER),|acc:(f64,f64), curr:(f64,f64)|{ 
+        let spam_ham_probs = cached_classifications.chain(classifications)
+                    .map(|classification| (classification.spam_score, classification.ham_score))
+                    .fold((NEUTRAL_MULTIPLIER, NEUTRAL_MULTIPLIER),|acc:(f64,f64), curr:(f64,f64)|{ 
                         let (first, second) = curr;
                         let (first_acc,second_acc) = acc;
                         let first_new :f64= first_acc * first;
@@ -236,3 +1531,29 @@ pub fn execute_message_from_file_if_specified(model_filename:&str , cache : &Cac
         }
     }
 }
+
+/// The lines of a file of messages. Lines which are not valid UTF-8 are skipped
+/// rather than classified with replacement characters.
+fn readable_lines(bytes: &[u8]) -> Vec<String> {
+    if bytes.is_empty() {
+        return Vec::new();
+    }
+
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    bytes.split(|byte| *byte == b'\n')
+        .filter_map(|line| std::str::from_utf8(line).ok())
+        .map(|line| line.strip_suffix('\r').unwrap_or(line).to_string())
+        .collect()
+}
+
+fn classify_lines(classifier: &dyn SpamClassifier, lines: &[&str], matches: &ArgMatches) -> Vec<Classification> {
+    if !matches.is_present(THREADS_PARAMETER) {
+        return classifier.classify_batch(lines);
+    }
+
+    let threads = parse_parameter(matches, THREADS_PARAMETER, 0);
+    match ThreadPoolBuilder::new().num_threads(threads).build() {
+        Ok(pool) => classify_batch_on(&pool, classifier, lines),
+        Err(error) => panic!("An error ocurred while creating a pool of {} threads - {:?}", threads, error),
+    }
+}
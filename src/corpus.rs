@@ -0,0 +1,96 @@
+use crate::dataset::LabelledMessage;
+use crate::label::Label;
+use crate::mailbox::FolderMapping;
+use crate::utills::SpamClassifierError;
+use rayon::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CORPUS : &str = "corpus";
+pub const DEFAULT_SPAM_DIRECTORIES : [&str; 2] = ["spam", "spam_2"];
+pub const DEFAULT_HAM_DIRECTORIES : [&str; 4] = ["ham", "easy_ham", "easy_ham_2", "hard_ham"];
+const HIDDEN_FILE_PREFIX : char = '.';
+/// The SpamAssassin public corpus keeps the commands which renamed its files in
+/// every directory.
+const IGNORED_FILES : [&str; 1] = ["cmds"];
+
+/// The directory names of the SpamAssassin public corpus and of Enron-Spam.
+pub fn default_directory_mapping() -> FolderMapping {
+    FolderMapping::new(&DEFAULT_SPAM_DIRECTORIES, &DEFAULT_HAM_DIRECTORIES)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnreadableFile {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// The messages of a corpus and the files which could not be read.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Corpus {
+    pub messages: Vec<LabelledMessage>,
+    pub unreadable_files: Vec<UnreadableFile>,
+}
+
+impl Corpus {
+
+    pub fn count(&self, label: Label) -> usize {
+        self.messages.iter().filter(|message| message.label == label).count()
+    }
+}
+
+/// Reads a corpus stored as one message per file, such as the SpamAssassin public
+/// corpus or Enron-Spam. Every file takes the label of the closest directory above
+/// it, `root` included, whose name is mapped; files under no mapped directory and
+/// hidden files are skipped. The files are read in parallel, and files which cannot
+/// be read are reported instead of failing the whole corpus.
+pub fn read_corpus(root: &Path, mapping: &FolderMapping) -> Result<Corpus, SpamClassifierError> {
+    let mut files = Vec::new();
+    collect_files(root, directory_label(root, mapping), mapping, &mut files)?;
+
+    let results: Vec<(Label, PathBuf, std::io::Result<Vec<u8>>)> = files.into_par_iter()
+        .map(|(label, path)| {
+            let content = fs::read(&path);
+            (label, path, content)
+        })
+        .collect();
+
+    let mut corpus = Corpus::default();
+    for (label, path, content) in results {
+        match content {
+            Ok(bytes) => corpus.messages.push(LabelledMessage::new(label, &String::from_utf8_lossy(&bytes))),
+            Err(error) => corpus.unreadable_files.push(UnreadableFile { path, error: error.to_string() }),
+        }
+    }
+
+    Ok(corpus)
+}
+
+fn collect_files(directory: &Path, label: Option<Label>, mapping: &FolderMapping, files: &mut Vec<(Label, PathBuf)>) -> Result<(), SpamClassifierError> {
+    let mut entries = fs::read_dir(directory).map_err(SpamClassifierError::IO)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(SpamClassifierError::IO)?;
+    entries.sort();
+
+    for entry in entries {
+        let name = entry.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        if name.starts_with(HIDDEN_FILE_PREFIX) {
+            continue;
+        }
+
+        if entry.is_dir() {
+            collect_files(&entry, directory_label(&entry, mapping).or(label), mapping, files)?;
+        } else if let Some(label) = label {
+            if !IGNORED_FILES.contains(&name.as_str()) {
+                files.push((label, entry));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn directory_label(directory: &Path, mapping: &FolderMapping) -> Option<Label> {
+    directory.file_name().and_then(|name| mapping.label_for(&name.to_string_lossy()))
+}
@@ -0,0 +1,169 @@
+use crate::dataset::LabelledMessage;
+use crate::evaluation::{evaluate, Cutoffs, Evaluation};
+use crate::label::Label;
+use crate::random::SeededRandom;
+use crate::spam_classifier::SpamClassifier;
+use crate::training::{train_messages, TrainingStrategy};
+use crate::utills::SpamClassifierError;
+use rayon::prelude::*;
+use serde::Serialize;
+
+pub const DEFAULT_SEED : u64 = 42;
+const INVALID_FOLDS : &str = "Cross-validation needs at least 2 folds and a message per fold";
+const INVALID_TEST_FRACTION : &str = "The held out fraction must be strictly between 0 and 1";
+
+/// The indices of the messages a model is trained on and of those it is tested on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Split {
+    pub train: Vec<usize>,
+    pub test: Vec<usize>,
+}
+
+/// How a labelled dataset is split to estimate how well a model generalises.
+/// Both splits are stratified, keeping the share of spam of the whole dataset in
+/// every part, and shuffled with the seed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Validation {
+    /// Trains on the dataset without a `test_fraction` of it and tests on the rest.
+    Holdout { test_fraction: f64, seed: u64 },
+    /// Splits the dataset into `folds` parts and trains a model per part, testing
+    /// it on that part and training it on the others.
+    CrossValidation { folds: usize, seed: u64 },
+}
+
+impl Validation {
+
+    pub fn splits(&self, messages: &[LabelledMessage]) -> Result<Vec<Split>, SpamClassifierError> {
+        match *self {
+            Validation::Holdout { test_fraction, seed } => {
+                if !(test_fraction > 0.0 && test_fraction < 1.0) {
+                    return Err(SpamClassifierError::InvalidParameter(INVALID_TEST_FRACTION.to_string()));
+                }
+
+                let mut train = Vec::new();
+                let mut test = Vec::new();
+                for indices in shuffled_indices_per_label(messages, seed) {
+                    let test_count = (indices.len() as f64 * test_fraction).round() as usize;
+                    test.extend_from_slice(&indices[..test_count]);
+                    train.extend_from_slice(&indices[test_count..]);
+                }
+                train.sort_unstable();
+                test.sort_unstable();
+                Ok(vec![Split { train, test }])
+            }
+            Validation::CrossValidation { folds, seed } => {
+                if folds < 2 || folds > messages.len() {
+                    return Err(SpamClassifierError::InvalidParameter(INVALID_FOLDS.to_string()));
+                }
+
+                let mut fold_indices = vec![Vec::new(); folds];
+                let stratified: Vec<usize> = shuffled_indices_per_label(messages, seed).concat();
+                for (position, index) in stratified.into_iter().enumerate() {
+                    fold_indices[position % folds].push(index);
+                }
+
+                Ok((0..folds).map(|fold| {
+                    let mut test = fold_indices[fold].clone();
+                    test.sort_unstable();
+                    let mut train: Vec<usize> = fold_indices.iter().enumerate()
+                        .filter(|(other, _)| *other != fold)
+                        .flat_map(|(_, indices)| indices.iter().copied())
+                        .collect();
+                    train.sort_unstable();
+                    Split { train, test }
+                }).collect())
+            }
+        }
+    }
+}
+
+/// Dealing the shuffled spam and then the shuffled ham round-robin gives every
+/// fold the same share of spam, give or take a message.
+fn shuffled_indices_per_label(messages: &[LabelledMessage], seed: u64) -> Vec<Vec<usize>> {
+    let mut random = SeededRandom::new(seed);
+    [Label::Spam, Label::Ham].iter()
+        .map(|label| {
+            let mut indices: Vec<usize> = (0..messages.len()).filter(|&index| messages[index].label == *label).collect();
+            random.shuffle(&mut indices);
+            indices
+        })
+        .collect()
+}
+
+/// The mean of a metric over the splits and its variance.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct MetricSummary {
+    pub mean: f64,
+    pub variance: f64,
+}
+
+impl MetricSummary {
+
+    pub fn of(values: &[f64]) -> MetricSummary {
+        if values.is_empty() {
+            return MetricSummary::default();
+        }
+
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        MetricSummary { mean, variance }
+    }
+
+    pub fn standard_deviation(&self) -> f64 {
+        self.variance.sqrt()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationReport {
+    /// The evaluation of the model of every split, in split order.
+    pub splits: Vec<Evaluation>,
+    pub accuracy: MetricSummary,
+    pub precision: MetricSummary,
+    pub recall: MetricSummary,
+    pub f1: MetricSummary,
+    pub false_positive_rate: MetricSummary,
+    pub unsure_rate: MetricSummary,
+    pub roc_auc: MetricSummary,
+    pub pr_auc: MetricSummary,
+    pub expected_calibration_error: MetricSummary,
+}
+
+impl ValidationReport {
+
+    pub fn from_evaluations(splits: Vec<Evaluation>) -> ValidationReport {
+        let summary = |metric: fn(&Evaluation) -> f64| MetricSummary::of(&splits.iter().map(metric).collect::<Vec<_>>());
+        ValidationReport {
+            accuracy: summary(|evaluation| evaluation.accuracy),
+            precision: summary(|evaluation| evaluation.precision),
+            recall: summary(|evaluation| evaluation.recall),
+            f1: summary(|evaluation| evaluation.f1),
+            false_positive_rate: summary(|evaluation| evaluation.false_positive_rate),
+            unsure_rate: summary(|evaluation| evaluation.unsure_rate),
+            roc_auc: summary(|evaluation| evaluation.roc_auc),
+            pr_auc: summary(|evaluation| evaluation.pr_auc),
+            expected_calibration_error: summary(|evaluation| evaluation.reliability.expected_calibration_error),
+            splits,
+        }
+    }
+}
+
+/// Trains a new model from `new_classifier` on the training part of every split,
+/// in parallel, and evaluates it on the test part.
+pub fn validate<F>(messages: &[LabelledMessage], validation: Validation, strategy: TrainingStrategy, cutoffs: Cutoffs, new_classifier: F) -> Result<ValidationReport, SpamClassifierError>
+    where F: Fn() -> Box<dyn SpamClassifier> + Sync {
+    let splits = validation.splits(messages)?;
+
+    let evaluations = splits.par_iter()
+        .map(|split| {
+            let training_messages: Vec<LabelledMessage> = split.train.iter().map(|&index| messages[index].clone()).collect();
+            let test_messages: Vec<LabelledMessage> = split.test.iter().map(|&index| messages[index].clone()).collect();
+
+            let mut classifier = new_classifier();
+            train_messages(classifier.as_mut(), &training_messages, strategy);
+            evaluate(classifier.as_ref(), &test_messages, cutoffs)
+        })
+        .collect();
+
+    Ok(ValidationReport::from_evaluations(evaluations))
+}
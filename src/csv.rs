@@ -0,0 +1,119 @@
+const SEPARATOR : char = ',';
+const QUOTE : char = '"';
+const CARRIAGE_RETURN : char = '\r';
+const NEW_LINE : char = '\n';
+const UNTERMINATED_QUOTE : &str = "A quoted field is not terminated";
+const CHARACTERS_AFTER_QUOTE : &str = "Unexpected characters after the closing quote of a field";
+
+/// A row of a CSV document, the line it starts on (counting from 1) and its text
+/// as it appears in the document, without the final line break.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvRecord {
+    pub line: usize,
+    pub fields: Vec<String>,
+    pub raw: String,
+}
+
+/// A record which could not be parsed, the line it starts on, why, and its text
+/// as it appears in the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvError {
+    pub line: usize,
+    pub reason: String,
+    pub raw: String,
+}
+
+/// The records of a CSV document and the records which could not be parsed,
+/// each in document order.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CsvDocument {
+    pub records: Vec<CsvRecord>,
+    pub errors: Vec<CsvError>,
+}
+
+/// Parses a CSV document as described by RFC 4180: fields are separated by
+/// commas, records by CRLF or LF, and fields containing commas, quotes or line
+/// breaks are enclosed in double quotes, with quotes inside doubled. Empty lines
+/// are skipped. A malformed record is skipped up to the end of its line and
+/// parsing goes on, except after an unterminated quote, which takes the rest of
+/// the document.
+pub fn parse_records(input: &str) -> CsvDocument {
+    let mut document = CsvDocument::default();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut record_start = 0;
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            QUOTE if field.is_empty() => {
+                loop {
+                    match chars.next().map(|(_, inner)| inner) {
+                        Some(QUOTE) if next_char(&mut chars) == Some(QUOTE) => {
+                            chars.next();
+                            field.push(QUOTE);
+                        }
+                        Some(QUOTE) => break,
+                        Some(inner) => {
+                            if inner == NEW_LINE {
+                                line += 1;
+                            }
+                            field.push(inner);
+                        }
+                        None => {
+                            let rest = &input[record_start..];
+                            let raw = rest.strip_suffix(NEW_LINE).unwrap_or(rest).trim_end_matches(CARRIAGE_RETURN).to_string();
+                            document.errors.push(CsvError { line: record_line, reason: UNTERMINATED_QUOTE.to_string(), raw });
+                            return document;
+                        }
+                    }
+                }
+
+                match next_char(&mut chars) {
+                    None | Some(SEPARATOR) | Some(NEW_LINE) | Some(CARRIAGE_RETURN) => {}
+                    Some(_) => {
+                        let end = chars.by_ref().find(|(_, skipped)| *skipped == NEW_LINE).map_or(input.len(), |(end, _)| end);
+                        let raw = input[record_start..end].trim_end_matches(CARRIAGE_RETURN).to_string();
+                        document.errors.push(CsvError { line: record_line, reason: CHARACTERS_AFTER_QUOTE.to_string(), raw });
+                        field.clear();
+                        fields.clear();
+                        line += 1;
+                        record_line = line;
+                        record_start = (end + 1).min(input.len());
+                    }
+                }
+            }
+            SEPARATOR => fields.push(std::mem::take(&mut field)),
+            CARRIAGE_RETURN if next_char(&mut chars) == Some(NEW_LINE) => {}
+            NEW_LINE => {
+                fields.push(std::mem::take(&mut field));
+                let raw = input[record_start..index].trim_end_matches(CARRIAGE_RETURN);
+                push_record(&mut document.records, std::mem::take(&mut fields), record_line, raw);
+                line += 1;
+                record_line = line;
+                record_start = index + 1;
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        push_record(&mut document.records, fields, record_line, &input[record_start..]);
+    }
+
+    document
+}
+
+fn next_char(chars: &mut std::iter::Peekable<std::str::CharIndices>) -> Option<char> {
+    chars.peek().map(|(_, c)| *c)
+}
+
+fn push_record(records: &mut Vec<CsvRecord>, fields: Vec<String>, line: usize, raw: &str) {
+    let is_empty_line = fields.len() == 1 && fields[0].is_empty();
+    if !is_empty_line {
+        records.push(CsvRecord { line, fields, raw: raw.to_string() });
+    }
+}
@@ -0,0 +1,151 @@
+use crate::evaluation::ratio;
+use crate::label::Label;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::Write as _;
+use std::io;
+use std::io::Write;
+
+const SVG_SIZE : f64 = 480.0;
+const SVG_MARGIN : f64 = 60.0;
+const SVG_TICKS : usize = 5;
+const CURVE_COLOR : &str = "#1f77b4";
+const GUIDE_COLOR : &str = "#bbbbbb";
+
+/// The spam probability a model gave a test message, with the message's label.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LabelledScore {
+    pub label: Label,
+    pub spam_probability: f64,
+}
+
+/// A point of the ROC curve: the rates when every message scored at least
+/// `threshold` is called spam.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RocPoint {
+    pub threshold: f64,
+    pub false_positive_rate: f64,
+    pub true_positive_rate: f64,
+}
+
+/// A point of the precision-recall curve, with the same meaning of `threshold`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PrPoint {
+    pub threshold: f64,
+    pub recall: f64,
+    pub precision: f64,
+}
+
+/// The ROC and precision-recall curves of a model over every threshold which
+/// changes a decision, starting from an infinite threshold which calls nothing spam.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Curves {
+    pub roc: Vec<RocPoint>,
+    pub pr: Vec<PrPoint>,
+    /// The area under the ROC curve, by the trapezoidal rule.
+    pub roc_auc: f64,
+    /// The area under the precision-recall curve, as average precision.
+    pub pr_auc: f64,
+}
+
+impl Curves {
+
+    pub fn from_scores(scores: &[LabelledScore]) -> Curves {
+        let mut sorted = scores.to_vec();
+        sorted.sort_by(|first, second| second.spam_probability.partial_cmp(&first.spam_probability).unwrap_or(Ordering::Equal));
+        let spam = sorted.iter().filter(|score| score.label == Label::Spam).count();
+        let ham = sorted.len() - spam;
+
+        let mut roc = vec![RocPoint { threshold: f64::INFINITY, false_positive_rate: 0.0, true_positive_rate: 0.0 }];
+        let mut pr = vec![PrPoint { threshold: f64::INFINITY, recall: 0.0, precision: 1.0 }];
+        let (mut true_positives, mut false_positives) = (0, 0);
+        for (index, score) in sorted.iter().enumerate() {
+            match score.label {
+                Label::Spam => true_positives += 1,
+                Label::Ham => false_positives += 1,
+            }
+
+            let is_last_of_threshold = sorted.get(index + 1).is_none_or(|next| next.spam_probability != score.spam_probability);
+            if is_last_of_threshold {
+                let threshold = score.spam_probability;
+                roc.push(RocPoint { threshold, false_positive_rate: ratio(false_positives, ham), true_positive_rate: ratio(true_positives, spam) });
+                pr.push(PrPoint { threshold, recall: ratio(true_positives, spam), precision: ratio(true_positives, true_positives + false_positives) });
+            }
+        }
+
+        let roc_auc = roc.windows(2)
+            .map(|pair| (pair[1].false_positive_rate - pair[0].false_positive_rate) * (pair[1].true_positive_rate + pair[0].true_positive_rate) / 2.0)
+            .sum();
+        let pr_auc = pr.windows(2)
+            .map(|pair| (pair[1].recall - pair[0].recall) * pair[1].precision)
+            .sum();
+
+        Curves { roc, pr, roc_auc, pr_auc }
+    }
+
+    pub fn write_roc_csv(&self, writer: &mut dyn Write) -> Result<(), io::Error> {
+        writeln!(writer, "threshold,false_positive_rate,true_positive_rate")?;
+        for point in &self.roc {
+            writeln!(writer, "{:?},{},{}", point.threshold, point.false_positive_rate, point.true_positive_rate)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_pr_csv(&self, writer: &mut dyn Write) -> Result<(), io::Error> {
+        writeln!(writer, "threshold,recall,precision")?;
+        for point in &self.pr {
+            writeln!(writer, "{:?},{},{}", point.threshold, point.recall, point.precision)?;
+        }
+        Ok(())
+    }
+
+    /// The ROC curve as a standalone SVG image, with the diagonal of a model
+    /// which guesses.
+    pub fn roc_svg(&self) -> String {
+        let points: Vec<(f64, f64)> = self.roc.iter().map(|point| (point.false_positive_rate, point.true_positive_rate)).collect();
+        render_svg(&format!("ROC curve (AUC {:.4})", self.roc_auc), "False positive rate", "True positive rate", &points, ((0.0, 0.0), (1.0, 1.0)))
+    }
+
+    /// The precision-recall curve as a standalone SVG image, with the precision
+    /// of a model which calls everything spam.
+    pub fn pr_svg(&self) -> String {
+        let points: Vec<(f64, f64)> = self.pr.iter().map(|point| (point.recall, point.precision)).collect();
+        let baseline = self.pr.last().map(|point| point.precision).unwrap_or_default();
+        render_svg(&format!("Precision-recall curve (AUC {:.4})", self.pr_auc), "Recall", "Precision", &points, ((0.0, baseline), (1.0, baseline)))
+    }
+}
+
+/// Draws `points`, both coordinates in `[0, 1]`, with axes, ticks and a dashed guide line.
+pub(crate) fn render_svg(title: &str, x_label: &str, y_label: &str, points: &[(f64, f64)], guide: ((f64, f64), (f64, f64))) -> String {
+    let plot_size = SVG_SIZE - 2.0 * SVG_MARGIN;
+    let x = |value: f64| SVG_MARGIN + value * plot_size;
+    let y = |value: f64| SVG_SIZE - SVG_MARGIN - value * plot_size;
+
+    let mut svg = String::new();
+    let _ = writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\" font-family=\"sans-serif\" font-size=\"12\">", SVG_SIZE);
+    let _ = writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>");
+    let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-size=\"16\">{}</text>", SVG_SIZE / 2.0, SVG_MARGIN / 2.0, escape_xml(title));
+    let _ = writeln!(svg, "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"none\" stroke=\"black\"/>", x(0.0), y(1.0), plot_size);
+
+    for tick in 0..=SVG_TICKS {
+        let value = tick as f64 / SVG_TICKS as f64;
+        let _ = writeln!(svg, "<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"black\"/>", x(value), y(0.0), y(0.0) + 5.0);
+        let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{:.1}</text>", x(value), y(0.0) + 18.0, value);
+        let _ = writeln!(svg, "<line x1=\"{0}\" y1=\"{1}\" x2=\"{2}\" y2=\"{1}\" stroke=\"black\"/>", x(0.0) - 5.0, y(value), x(0.0));
+        let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{:.1}</text>", x(0.0) - 8.0, y(value) + 4.0, value);
+    }
+    let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>", SVG_SIZE / 2.0, SVG_SIZE - SVG_MARGIN / 4.0, escape_xml(x_label));
+    let _ = writeln!(svg, "<text x=\"{0}\" y=\"{1}\" text-anchor=\"middle\" transform=\"rotate(-90 {0} {1})\">{2}</text>", SVG_MARGIN / 4.0, SVG_SIZE / 2.0, escape_xml(y_label));
+
+    let ((guide_x1, guide_y1), (guide_x2, guide_y2)) = guide;
+    let _ = writeln!(svg, "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-dasharray=\"4 4\"/>",
+            x(guide_x1), y(guide_y1), x(guide_x2), y(guide_y2), GUIDE_COLOR);
+    let polyline: Vec<String> = points.iter().map(|(point_x, point_y)| format!("{:.2},{:.2}", x(*point_x), y(*point_y))).collect();
+    let _ = writeln!(svg, "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>", polyline.join(" "), CURVE_COLOR);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
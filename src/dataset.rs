@@ -0,0 +1,365 @@
+use crate::label::{Label, HAM, SPAM};
+use crate::utills::SpamClassifierError;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+
+pub const TSV : &str = "tsv";
+pub const CSV : &str = "csv";
+pub const JSONL : &str = "jsonl";
+pub const DEFAULT_LABEL_COLUMN : &str = "label";
+pub const DEFAULT_TEXT_COLUMN : &str = "text";
+const TAB : char = '\t';
+const DATASET_FILE_FORMAT_INVALID : &str = "The specified dataset file is in invalid format!";
+const MISSING_TAB : &str = "there is no tab between the label and the text";
+const EMPTY_LABEL_OR_TEXT : &str = "the label or the text is empty";
+const UNKNOWN_LABEL : &str = "the label is neither spam nor ham";
+const INVALID_UTF8 : &str = "the record is not valid UTF-8";
+const MISSING_HEADER : &str = "the header row is missing";
+const MISSING_COLUMN : &str = "there is no column named";
+const MISSING_FIELD : &str = "the record has no field";
+const NOT_AN_OBJECT : &str = "the line is not a JSON object";
+const TEXT_NOT_A_STRING : &str = "the text is not a string";
+const LABEL_NOT_A_VALUE : &str = "the label is not a string, a number or a boolean";
+const SNIPPET_LENGTH : usize = 60;
+const SNIPPET_ELLIPSIS : &str = "...";
+
+/// The layouts of labelled datasets `read_dataset` understands.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    /// `label<TAB>text` lines, as in the SMSSpamCollection.
+    #[default]
+    Tsv,
+    /// RFC 4180 CSV with a header row naming the columns.
+    Csv,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl DatasetFormat {
+
+    pub fn from_name(name: &str) -> Option<DatasetFormat> {
+        match name {
+            TSV => Some(DatasetFormat::Tsv),
+            CSV => Some(DatasetFormat::Csv),
+            JSONL => Some(DatasetFormat::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+/// Where the label and text of a message are found and which label values mean
+/// spam and ham. The columns are ignored for tab separated datasets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    pub label_column: String,
+    pub text_column: String,
+    pub spam_labels: Vec<String>,
+    pub ham_labels: Vec<String>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        ColumnMapping {
+            label_column: DEFAULT_LABEL_COLUMN.to_string(),
+            text_column: DEFAULT_TEXT_COLUMN.to_string(),
+            spam_labels: vec![SPAM.to_string()],
+            ham_labels: vec![HAM.to_string()],
+        }
+    }
+}
+
+impl ColumnMapping {
+
+    pub fn label_for(&self, value: &str) -> Option<Label> {
+        if self.spam_labels.iter().any(|spam_label| spam_label == value) {
+            return Some(Label::Spam);
+        }
+
+        if self.ham_labels.iter().any(|ham_label| ham_label == value) {
+            return Some(Label::Ham);
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelledMessage {
+    pub label: Label,
+    pub text: String,
+}
+
+impl LabelledMessage {
+
+    pub fn new(label: Label, text: &str) -> Self {
+        LabelledMessage { label, text: text.to_string() }
+    }
+}
+
+/// Reads a dataset in the `label<TAB>text` format. Lines whose label is neither
+/// `ham` nor `spam` are skipped.
+pub fn read_labelled_messages(dataset: impl Read) -> Result<Vec<LabelledMessage>, SpamClassifierError> {
+    read_dataset(dataset, DatasetFormat::Tsv, &ColumnMapping::default())
+}
+
+/// Parses a single `label<TAB>text` line. Returns `None` for lines whose label is
+/// neither `ham` nor `spam`.
+pub fn parse_dataset_line(line: &str) -> Result<Option<LabelledMessage>, SpamClassifierError> {
+    match parse_tsv_line(line, &ColumnMapping::default()) {
+        Ok(message) => Ok(Some(message)),
+        Err((ProblemKind::UnknownLabel, _)) => Ok(None),
+        Err((_, reason)) => Err(SpamClassifierError::InvalidDatasetFormatError(format!("{} {}", DATASET_FILE_FORMAT_INVALID, reason))),
+    }
+}
+
+fn parse_tsv_line(line: &str, mapping: &ColumnMapping) -> Result<LabelledMessage, (ProblemKind, String)> {
+    let (first, second) = match crate::utills::take_and_skip(line, TAB) {
+        Some(split_line) => split_line,
+        None => return Err((ProblemKind::Malformed, MISSING_TAB.to_string())),
+    };
+
+    if first.is_empty() || second.is_empty() {
+        return Err((ProblemKind::Malformed, EMPTY_LABEL_OR_TEXT.to_string()));
+    }
+
+    match mapping.label_for(first) {
+        Some(label) => Ok(LabelledMessage::new(label, second)),
+        None => Err((ProblemKind::UnknownLabel, unknown_label(first))),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProblemKind {
+    /// The record cannot be parsed.
+    Malformed,
+    /// The record is not valid UTF-8.
+    InvalidUtf8,
+    /// The record is valid but its label maps to neither spam nor ham.
+    UnknownLabel,
+}
+
+/// A record of a dataset which cannot be trained on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DatasetProblem {
+    /// The line the record starts on, counting from 1.
+    pub line: usize,
+    pub kind: ProblemKind,
+    pub reason: String,
+    /// The record as it appears in the dataset.
+    pub record: String,
+}
+
+impl DatasetProblem {
+
+    /// The start of the record, short enough for a report line.
+    pub fn snippet(&self) -> String {
+        snippet(&self.record)
+    }
+}
+
+/// The first line of a text, cut short when longer than a line of a report.
+pub fn snippet(text: &str) -> String {
+    let first_line = text.lines().next().unwrap_or_default();
+    if first_line.chars().count() <= SNIPPET_LENGTH && first_line.len() == text.len() {
+        return first_line.to_string();
+    }
+
+    let start: String = first_line.chars().take(SNIPPET_LENGTH).collect();
+    format!("{}{}", start, SNIPPET_ELLIPSIS)
+}
+
+/// The outcome of reading a whole dataset: the messages which can be trained on
+/// and every record which cannot.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ValidatedDataset {
+    pub messages: Vec<LabelledMessage>,
+    pub problems: Vec<DatasetProblem>,
+    /// The header row of a CSV dataset, which is repeated in the rejects.
+    pub header: Option<String>,
+}
+
+impl ValidatedDataset {
+
+    pub fn count(&self, label: Label) -> usize {
+        self.messages.iter().filter(|message| message.label == label).count()
+    }
+
+    /// Whether every record can be parsed. Records with unknown labels are valid.
+    pub fn is_valid(&self) -> bool {
+        self.invalid_records().next().is_none()
+    }
+
+    pub fn invalid_records(&self) -> impl Iterator<Item = &DatasetProblem> {
+        self.problems.iter().filter(|problem| problem.kind != ProblemKind::UnknownLabel)
+    }
+
+    /// Writes every rejected record as it appeared in the dataset, after the header
+    /// row for CSV, so that the rejects can be fixed and trained on separately.
+    pub fn write_rejects(&self, writer: &mut dyn Write) -> Result<(), SpamClassifierError> {
+        if let Some(header) = &self.header {
+            writeln!(writer, "{}", header).map_err(SpamClassifierError::IO)?;
+        }
+
+        for problem in &self.problems {
+            writeln!(writer, "{}", problem.record).map_err(SpamClassifierError::IO)?;
+        }
+
+        Ok(())
+    }
+
+    fn add(&mut self, line: usize, record: &str, result: Result<LabelledMessage, (ProblemKind, String)>) {
+        match result {
+            Ok(message) => self.messages.push(message),
+            Err((kind, reason)) => self.problems.push(DatasetProblem { line, kind, reason, record: record.to_string() }),
+        }
+    }
+}
+
+/// Reads a dataset in any of the supported formats. Messages whose label maps to
+/// neither spam nor ham are skipped, and the first record which cannot be parsed
+/// fails the whole dataset.
+pub fn read_dataset(dataset: impl Read, format: DatasetFormat, mapping: &ColumnMapping) -> Result<Vec<LabelledMessage>, SpamClassifierError> {
+    let validated = validate_dataset(dataset, format, mapping)?;
+    if let Some(problem) = validated.invalid_records().next() {
+        return Err(invalid_dataset(problem.line, &problem.reason));
+    }
+
+    Ok(validated.messages)
+}
+
+/// Reads a whole dataset, collecting every record which cannot be trained on
+/// instead of stopping at the first. Only failing to read the dataset is an error.
+pub fn validate_dataset(dataset: impl Read, format: DatasetFormat, mapping: &ColumnMapping) -> Result<ValidatedDataset, SpamClassifierError> {
+    match format {
+        DatasetFormat::Tsv => validate_lines(dataset, |line| parse_tsv_line(line, mapping)),
+        DatasetFormat::Csv => validate_csv(dataset, mapping),
+        DatasetFormat::Jsonl => validate_lines(dataset, |line| parse_jsonl_line(line, mapping)),
+    }
+}
+
+/// Validates a dataset of one record per line. Empty lines are skipped.
+fn validate_lines<F>(dataset: impl Read, parse_line: F) -> Result<ValidatedDataset, SpamClassifierError>
+    where F: Fn(&str) -> Result<LabelledMessage, (ProblemKind, String)> {
+    let mut reader = BufReader::new(dataset);
+    let mut validated = ValidatedDataset::default();
+    let mut bytes = Vec::new();
+    let mut line_number = 0;
+
+    loop {
+        bytes.clear();
+        if reader.read_until(b'\n', &mut bytes).map_err(SpamClassifierError::IO)? == 0 {
+            break;
+        }
+        line_number += 1;
+
+        let without_line_break = bytes.strip_suffix(b"\n").unwrap_or(&bytes);
+        let without_line_break = without_line_break.strip_suffix(b"\r").unwrap_or(without_line_break);
+        match std::str::from_utf8(without_line_break) {
+            Ok(line) if line.trim().is_empty() => {}
+            Ok(line) => validated.add(line_number, line, parse_line(line)),
+            Err(_) => {
+                let line = String::from_utf8_lossy(without_line_break);
+                validated.add(line_number, &line, Err((ProblemKind::InvalidUtf8, INVALID_UTF8.to_string())));
+            }
+        }
+    }
+
+    Ok(validated)
+}
+
+fn validate_csv(mut dataset: impl Read, mapping: &ColumnMapping) -> Result<ValidatedDataset, SpamClassifierError> {
+    let mut bytes = Vec::new();
+    dataset.read_to_end(&mut bytes).map_err(SpamClassifierError::IO)?;
+
+    let invalid_lines: Vec<usize> = bytes.split(|&byte| byte == b'\n')
+        .enumerate()
+        .filter(|(_, line)| std::str::from_utf8(line).is_err())
+        .map(|(index, _)| index + 1)
+        .collect();
+    let content = String::from_utf8_lossy(&bytes);
+
+    let mut validated = ValidatedDataset::default();
+    let document = crate::csv::parse_records(&content);
+    let malformed: Vec<DatasetProblem> = document.errors.into_iter()
+        .map(|error| DatasetProblem { line: error.line, kind: ProblemKind::Malformed, reason: error.reason, record: error.raw })
+        .collect();
+
+    let (header, rows) = match document.records.split_first() {
+        Some(split_records) => split_records,
+        None if !malformed.is_empty() => {
+            validated.problems = malformed;
+            return Ok(validated);
+        }
+        None => return Err(invalid_dataset(1, MISSING_HEADER)),
+    };
+    validated.header = Some(header.raw.clone());
+
+    let label_index = column_index(&header.fields, &mapping.label_column, header.line)?;
+    let text_index = column_index(&header.fields, &mapping.text_column, header.line)?;
+
+    for row in rows {
+        let last_line = row.line + row.raw.matches('\n').count();
+        if invalid_lines.iter().any(|line| (row.line..=last_line).contains(line)) {
+            validated.add(row.line, &row.raw, Err((ProblemKind::InvalidUtf8, INVALID_UTF8.to_string())));
+            continue;
+        }
+
+        let result = match (row.fields.get(label_index), row.fields.get(text_index)) {
+            (None, _) => Err((ProblemKind::Malformed, format!("{} {:?}", MISSING_FIELD, mapping.label_column))),
+            (_, None) => Err((ProblemKind::Malformed, format!("{} {:?}", MISSING_FIELD, mapping.text_column))),
+            (Some(label), Some(text)) => match mapping.label_for(label) {
+                Some(label) => Ok(LabelledMessage::new(label, text)),
+                None => Err((ProblemKind::UnknownLabel, unknown_label(label))),
+            },
+        };
+        validated.add(row.line, &row.raw, result);
+    }
+    validated.problems.extend(malformed);
+    validated.problems.sort_by_key(|problem| problem.line);
+
+    Ok(validated)
+}
+
+fn column_index(header: &[String], column: &str, line: usize) -> Result<usize, SpamClassifierError> {
+    header.iter()
+        .position(|name| name.trim() == column)
+        .ok_or_else(|| invalid_dataset(line, &format!("{} {:?}", MISSING_COLUMN, column)))
+}
+
+fn parse_jsonl_line(line: &str, mapping: &ColumnMapping) -> Result<LabelledMessage, (ProblemKind, String)> {
+    let malformed = |reason: String| (ProblemKind::Malformed, reason);
+
+    let value: Value = serde_json::from_str(line).map_err(|error| malformed(error.to_string()))?;
+    let object = value.as_object().ok_or_else(|| malformed(NOT_AN_OBJECT.to_string()))?;
+
+    let label = object.get(&mapping.label_column).ok_or_else(|| malformed(format!("{} {:?}", MISSING_FIELD, mapping.label_column)))?;
+    let text = object.get(&mapping.text_column).ok_or_else(|| malformed(format!("{} {:?}", MISSING_FIELD, mapping.text_column)))?;
+    let text = text.as_str().ok_or_else(|| malformed(TEXT_NOT_A_STRING.to_string()))?;
+    let label = json_label(label).ok_or_else(|| malformed(LABEL_NOT_A_VALUE.to_string()))?;
+
+    match mapping.label_for(&label) {
+        Some(mapped_label) => Ok(LabelledMessage::new(mapped_label, text)),
+        None => Err((ProblemKind::UnknownLabel, unknown_label(&label))),
+    }
+}
+
+/// Label values may be strings, numbers or booleans, so that both `"spam"` and
+/// `1` can be mapped.
+fn json_label(value: &Value) -> Option<String> {
+    match value {
+        Value::String(label) => Some(label.clone()),
+        Value::Number(label) => Some(label.to_string()),
+        Value::Bool(label) => Some(label.to_string()),
+        _ => None,
+    }
+}
+
+fn unknown_label(label: &str) -> String {
+    format!("{} - {:?}", UNKNOWN_LABEL, label)
+}
+
+fn invalid_dataset(line: usize, reason: &str) -> SpamClassifierError {
+    SpamClassifierError::InvalidDatasetFormatError(format!("{} Line {} - {}", DATASET_FILE_FORMAT_INVALID, line, reason))
+}
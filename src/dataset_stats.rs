@@ -0,0 +1,135 @@
+use crate::dataset::{LabelledMessage, ProblemKind, ValidatedDataset};
+use crate::label::Label;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+pub const DEFAULT_TOP_TOKENS : usize = 10;
+const PERCENTILE : f64 = 0.9;
+
+/// Summary of a set of lengths.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct LengthDistribution {
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+    pub median: usize,
+    pub p90: usize,
+}
+
+impl LengthDistribution {
+
+    pub fn of(lengths: &[usize]) -> LengthDistribution {
+        if lengths.is_empty() {
+            return LengthDistribution::default();
+        }
+
+        let mut sorted = lengths.to_vec();
+        sorted.sort_unstable();
+        LengthDistribution {
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean: sorted.iter().sum::<usize>() as f64 / sorted.len() as f64,
+            median: percentile(&sorted, 0.5),
+            p90: percentile(&sorted, PERCENTILE),
+        }
+    }
+}
+
+/// The nearest-rank percentile of sorted values.
+fn percentile(sorted: &[usize], fraction: f64) -> usize {
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TokenCount {
+    pub token: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct LabelStats {
+    pub messages: usize,
+    /// Messages whose text already appeared earlier with the same label.
+    pub duplicates: usize,
+    pub characters: LengthDistribution,
+    pub tokens: LengthDistribution,
+    /// The most frequent tokens of the label, most frequent first.
+    pub top_tokens: Vec<TokenCount>,
+}
+
+/// What a dataset is made of, to look at before training on it.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct DatasetStats {
+    pub messages: usize,
+    pub ham: LabelStats,
+    pub spam: LabelStats,
+    /// Number of distinct tokens in the whole dataset.
+    pub vocabulary_size: usize,
+    pub duplicates: usize,
+    pub invalid_utf8_lines: usize,
+    pub invalid_records: usize,
+    pub unknown_labels: usize,
+}
+
+impl DatasetStats {
+
+    pub fn compute(dataset: &ValidatedDataset, top_tokens: usize) -> DatasetStats {
+        let tokenized: Vec<(Label, Vec<String>)> = dataset.messages.iter()
+            .map(|message| (message.label, crate::tokenizer::tokenize(&message.text)))
+            .collect();
+
+        let vocabulary: HashSet<&String> = tokenized.iter().flat_map(|(_, tokens)| tokens).collect();
+        let ham = label_stats(&dataset.messages, &tokenized, Label::Ham, top_tokens);
+        let spam = label_stats(&dataset.messages, &tokenized, Label::Spam, top_tokens);
+
+        DatasetStats {
+            messages: dataset.messages.len(),
+            vocabulary_size: vocabulary.len(),
+            duplicates: ham.duplicates + spam.duplicates,
+            invalid_utf8_lines: count_problems(dataset, ProblemKind::InvalidUtf8),
+            invalid_records: count_problems(dataset, ProblemKind::Malformed),
+            unknown_labels: count_problems(dataset, ProblemKind::UnknownLabel),
+            ham,
+            spam,
+        }
+    }
+}
+
+fn count_problems(dataset: &ValidatedDataset, kind: ProblemKind) -> usize {
+    dataset.problems.iter().filter(|problem| problem.kind == kind).count()
+}
+
+fn label_stats(messages: &[LabelledMessage], tokenized: &[(Label, Vec<String>)], label: Label, top_tokens: usize) -> LabelStats {
+    let texts: Vec<&str> = messages.iter()
+        .filter(|message| message.label == label)
+        .map(|message| message.text.as_str())
+        .collect();
+    let token_lists: Vec<&Vec<String>> = tokenized.iter()
+        .filter(|(token_label, _)| *token_label == label)
+        .map(|(_, tokens)| tokens)
+        .collect();
+
+    let mut seen = HashSet::new();
+    let duplicates = texts.iter().filter(|text| !seen.insert(**text)).count();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for token in token_lists.iter().flat_map(|tokens| tokens.iter()) {
+        *counts.entry(token).or_insert(0) += 1;
+    }
+    let mut sorted_counts: Vec<(&str, usize)> = counts.into_iter().collect();
+    sorted_counts.sort_by(|(first_token, first_count), (second_token, second_count)| {
+        second_count.cmp(first_count).then(first_token.cmp(second_token))
+    });
+
+    LabelStats {
+        messages: texts.len(),
+        duplicates,
+        characters: LengthDistribution::of(&texts.iter().map(|text| text.chars().count()).collect::<Vec<_>>()),
+        tokens: LengthDistribution::of(&token_lists.iter().map(|tokens| tokens.len()).collect::<Vec<_>>()),
+        top_tokens: sorted_counts.into_iter()
+            .take(top_tokens)
+            .map(|(token, count)| TokenCount { token: token.to_string(), count })
+            .collect(),
+    }
+}
@@ -0,0 +1,165 @@
+use crate::cache::fingerprint;
+use crate::dataset::LabelledMessage;
+use crate::label::Label;
+use std::collections::{HashMap, HashSet};
+
+pub const EXACT : &str = "exact";
+pub const NEAR : &str = "near";
+/// Simhashes of near-duplicates differ in at most this many of their 64 bits.
+/// Changing one word of a short message, as a prize amount, typically moves
+/// about six bits; unrelated messages rarely come closer than ten.
+pub const DEFAULT_MAX_DISTANCE : u32 = 6;
+const FNV_OFFSET_BASIS : u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME : u64 = 0x0000_0100_0000_01b3;
+
+/// How duplicate messages are recognised. Only messages with the same label are
+/// compared, so that a message labelled both ways is kept twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deduplication {
+    /// Drops messages whose text has the fingerprint of an earlier one.
+    Exact,
+    /// Also drops messages whose words are almost those of an earlier one, as
+    /// when spam only differs in a phone number or a prize amount.
+    Near { max_distance: u32 },
+}
+
+impl Deduplication {
+
+    pub fn from_name(name: &str, max_distance: u32) -> Option<Self> {
+        match name {
+            EXACT => Some(Deduplication::Exact),
+            NEAR => Some(Deduplication::Near { max_distance }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DeduplicationReport {
+    pub ham_dropped: usize,
+    pub spam_dropped: usize,
+}
+
+impl DeduplicationReport {
+
+    pub fn dropped(&self, label: Label) -> usize {
+        match label {
+            Label::Ham => self.ham_dropped,
+            Label::Spam => self.spam_dropped,
+        }
+    }
+}
+
+/// Keeps the first of every group of duplicate messages, in the original order.
+/// Messages without words all have the simhash 0, so they are only compared
+/// exactly.
+pub fn deduplicate(messages: Vec<LabelledMessage>, deduplication: Deduplication) -> (Vec<LabelledMessage>, DeduplicationReport) {
+    let mut report = DeduplicationReport::default();
+    let mut fingerprints = HashSet::new();
+    let mut simhashes = match deduplication {
+        Deduplication::Near { max_distance } => Some(SimhashIndex::new(max_distance)),
+        Deduplication::Exact => None,
+    };
+    let mut kept = Vec::with_capacity(messages.len());
+
+    for message in messages {
+        let is_duplicate = if !fingerprints.insert((message.label, fingerprint(&message.text))) {
+            true
+        } else if let Some(simhashes) = simhashes.as_mut() {
+            let words = crate::tokenizer::tokenize(&message.text);
+            if words.is_empty() {
+                false
+            } else {
+                let hash = simhash_of_words(&words);
+                let is_near_duplicate = simhashes.contains_near(message.label, hash);
+                if !is_near_duplicate {
+                    simhashes.insert(message.label, hash);
+                }
+                is_near_duplicate
+            }
+        } else {
+            false
+        };
+
+        if !is_duplicate {
+            kept.push(message);
+        } else if message.label == Label::Spam {
+            report.spam_dropped += 1;
+        } else {
+            report.ham_dropped += 1;
+        }
+    }
+
+    (kept, report)
+}
+
+/// The simhashes of the kept messages, bucketed by label and by the bits of each
+/// of `max_distance + 1` bands. Hashes differing in at most `max_distance` bits
+/// agree on at least one band, so a message is only compared with those sharing
+/// one of its buckets rather than with every kept message.
+struct SimhashIndex {
+    max_distance: u32,
+    band_masks: Vec<u64>,
+    buckets: HashMap<(Label, usize, u64), Vec<u64>>,
+}
+
+impl SimhashIndex {
+
+    fn new(max_distance: u32) -> Self {
+        // Any two hashes are within 64 bits, so a single empty band holds them all.
+        let band_masks = if max_distance >= 64 {
+            vec![0]
+        } else {
+            let band_count = max_distance + 1;
+            let width = 64 / band_count;
+            (0..band_count)
+                .map(|band| {
+                    let start = band * width;
+                    let end = if band + 1 == band_count { 64 } else { start + width };
+                    u64::MAX >> (64 - (end - start)) << start
+                })
+                .collect()
+        };
+        SimhashIndex { max_distance, band_masks, buckets: HashMap::new() }
+    }
+
+    fn contains_near(&self, label: Label, hash: u64) -> bool {
+        self.band_masks.iter().enumerate()
+            .filter_map(|(band, mask)| self.buckets.get(&(label, band, hash & mask)))
+            .flatten()
+            .any(|other| (hash ^ other).count_ones() <= self.max_distance)
+    }
+
+    fn insert(&mut self, label: Label, hash: u64) {
+        for (band, mask) in self.band_masks.iter().enumerate() {
+            self.buckets.entry((label, band, hash & mask)).or_default().push(hash);
+        }
+    }
+}
+
+/// The 64-bit simhash of the lowercase words of a message: every bit is the
+/// majority vote of that bit over the hashes of the words, so messages sharing
+/// most of their words have hashes differing in few bits.
+pub fn simhash(text: &str) -> u64 {
+    simhash_of_words(&crate::tokenizer::tokenize(text))
+}
+
+fn simhash_of_words(words: &[String]) -> u64 {
+    let mut votes = [0i64; 64];
+    for word in words {
+        let hash = fnv1a(&word.to_lowercase());
+        for (bit, vote) in votes.iter_mut().enumerate() {
+            *vote += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    }
+
+    votes.iter().enumerate()
+        .filter(|(_, vote)| **vote > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// FNV-1a, a hash which does not change between Rust versions, unlike the
+/// standard library hasher.
+fn fnv1a(word: &str) -> u64 {
+    word.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
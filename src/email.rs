@@ -0,0 +1,567 @@
+use crate::html::HtmlDocument;
+use encoding_rs::{Encoding, UTF_8};
+
+const CONTENT_TYPE : &str = "content-type";
+const CONTENT_TRANSFER_ENCODING : &str = "content-transfer-encoding";
+const CONTENT_DISPOSITION : &str = "content-disposition";
+const SUBJECT : &str = "subject";
+const DEFAULT_CONTENT_TYPE : &str = "text/plain";
+const MULTIPART_PREFIX : &str = "multipart/";
+const MESSAGE : &str = "message/rfc822";
+const TEXT_PLAIN : &str = "text/plain";
+const TEXT_HTML : &str = "text/html";
+const MULTIPART_DIGEST : &str = "multipart/digest";
+const MULTIPART_ALTERNATIVE : &str = "multipart/alternative";
+const ATTACHMENT : &str = "attachment";
+const BOUNDARY : &str = "boundary";
+const CHARSET : &str = "charset";
+const FILENAME : &str = "filename";
+const NAME : &str = "name";
+const BASE64 : &str = "base64";
+const QUOTED_PRINTABLE : &str = "quoted-printable";
+const MBOX_FROM_LINE : &str = "From ";
+const ENCODED_WORD_START : &str = "=?";
+const ENCODED_WORD_END : &str = "?=";
+/// Parts nested deeper than this are not looked into, so that a crafted message
+/// cannot make parsing recurse without bound.
+const MAX_NESTING : usize = 16;
+/// Headers of which at least one must be present for a text to be taken for an e-mail.
+const ENVELOPE_HEADERS : [&str; 9] = ["from", "to", "subject", "date", "received", "return-path", "message-id", "mime-version", "content-type"];
+const MIN_EMAIL_HEADERS : usize = 2;
+
+/// A header field with its folded lines joined and its encoded words decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub name: String,
+    pub value: String,
+}
+
+/// A leaf of the MIME tree of a message, with its transfer encoding undone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimePart {
+    pub headers: Vec<Header>,
+    /// The lowercase `type/subtype` of the part.
+    pub content_type: String,
+    pub charset: Option<String>,
+    pub filename: Option<String>,
+    pub is_attachment: bool,
+    /// Whether the part is another version of the text of a `multipart/alternative`
+    /// whose preferred version is a different part, as the HTML next to a plain one.
+    pub is_alternative_copy: bool,
+    pub body: Vec<u8>,
+    /// Whether `body` is still in the declared charset, which is not the case for
+    /// 8bit parts of a message which was already decoded to text.
+    needs_charset_decoding: bool,
+}
+
+impl MimePart {
+
+    /// Whether the part is body text rather than an attachment.
+    pub fn is_text(&self) -> bool {
+        !self.is_attachment && (self.content_type == TEXT_PLAIN || self.content_type == TEXT_HTML)
+    }
+
+    pub fn is_html(&self) -> bool {
+        self.content_type == TEXT_HTML
+    }
+
+    /// The body converted from its charset. Unknown charsets are read as UTF-8.
+    pub fn text(&self) -> String {
+        if !self.needs_charset_decoding {
+            return String::from_utf8_lossy(&self.body).to_string();
+        }
+
+        decode_charset(self.charset.as_deref(), &self.body)
+    }
+}
+
+/// An RFC 5322 message, with its MIME tree flattened into its leaf parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub headers: Vec<Header>,
+    pub parts: Vec<MimePart>,
+}
+
+impl Email {
+
+    /// Parses a message. Parsing never fails: anything which is not valid MIME is
+    /// kept as plain text.
+    pub fn parse(raw: &str) -> Email {
+        let normalized = raw.replace("\r\n", "\n");
+        let without_postmark = skip_mbox_from_line(&normalized);
+        let (headers, body) = split_headers(without_postmark);
+
+        let mut parts = Vec::new();
+        collect_parts(&headers, body, DEFAULT_CONTENT_TYPE, 0, &mut parts);
+        Email { headers, parts }
+    }
+
+    /// The value of the first header with the name, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    pub fn header_values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.headers.iter()
+            .filter(move |header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| header.value.as_str())
+    }
+
+    /// The parts which are body text rather than attachments, with a single
+    /// version of every `multipart/alternative`.
+    pub fn text_parts(&self) -> impl Iterator<Item = &MimePart> {
+        self.parts.iter().filter(|part| part.is_text() && !part.is_alternative_copy)
+    }
+
+    pub fn attachments(&self) -> impl Iterator<Item = &MimePart> {
+        self.parts.iter().filter(|part| !part.is_text())
+    }
+
+    /// The subject followed by the decoded text parts, with HTML parts reduced to
+    /// their visible text.
+    pub fn text(&self) -> String {
+        let mut text = self.subject().to_string();
+        for part in self.text_parts() {
+            text.push('\n');
+            match part.is_html() {
+                true => text.push_str(&HtmlDocument::parse(&part.text()).text),
+                false => text.push_str(&part.text()),
+            }
+        }
+
+        text
+    }
+
+    pub fn subject(&self) -> &str {
+        self.header(SUBJECT).unwrap_or_default()
+    }
+}
+
+/// The `local@domain` addresses of an address list header such as `To`, with
+/// display names, comments and angle brackets dropped.
+pub fn parse_addresses(value: &str) -> Vec<String> {
+    split_outside_quotes(value, ',')
+        .into_iter()
+        .filter_map(|mailbox| {
+            let address = match (mailbox.rfind('<'), mailbox.rfind('>')) {
+                (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+                _ => mailbox.split_whitespace().find(|word| word.contains('@'))?,
+            };
+            let address = address.trim();
+            if address.contains('@') { Some(address.to_string()) } else { None }
+        })
+        .collect()
+}
+
+/// Whether the text starts with a header block which looks like that of an e-mail:
+/// every line up to the first empty one is a header field or a continuation of one,
+/// there are at least two fields and one of them is a usual envelope header.
+pub fn looks_like_email(raw: &str) -> bool {
+    let mut fields = 0;
+    let mut has_envelope_header = false;
+    // `lines` also strips the `\r` of CRLF line ends, so the text is not copied.
+    for line in skip_mbox_from_line(raw).lines() {
+        if line.is_empty() {
+            break;
+        }
+
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if fields == 0 {
+                return false;
+            }
+            continue;
+        }
+
+        let name = match header_name(line) {
+            Some(name) => name,
+            None => return false,
+        };
+        fields += 1;
+        has_envelope_header |= ENVELOPE_HEADERS.iter().any(|header| header.eq_ignore_ascii_case(name));
+    }
+
+    fields >= MIN_EMAIL_HEADERS && has_envelope_header
+}
+
+fn skip_mbox_from_line(message: &str) -> &str {
+    if message.starts_with(MBOX_FROM_LINE) {
+        return message.split_once('\n').map(|(_, rest)| rest).unwrap_or_default();
+    }
+
+    message
+}
+
+/// The name of a `name: value` header line. Field names are printable ASCII
+/// without spaces or colons.
+fn header_name(line: &str) -> Option<&str> {
+    let (name, _) = line.split_once(':')?;
+    let is_valid = !name.is_empty() && name.bytes().all(|byte| byte.is_ascii_graphic() && byte != b':');
+    if is_valid { Some(name) } else { None }
+}
+
+/// Splits a message or a part into its headers and its body. A part without
+/// headers starts with an empty line.
+fn split_headers(message: &str) -> (Vec<Header>, &str) {
+    let (header_block, body) = if let Some(body) = message.strip_prefix('\n') {
+        ("", body)
+    } else if let Some(index) = message.find("\n\n") {
+        (&message[..index], &message[index + 2..])
+    } else if header_name(message.lines().next().unwrap_or_default()).is_some() {
+        (message, "")
+    } else {
+        ("", message)
+    };
+
+    let mut headers: Vec<Header> = Vec::new();
+    for line in header_block.lines() {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(last) = headers.last_mut() {
+                last.value.push(' ');
+                last.value.push_str(line.trim());
+            }
+            continue;
+        }
+
+        if let Some(name) = header_name(line) {
+            let value = line[name.len() + 1..].trim().to_string();
+            headers.push(Header { name: name.to_string(), value });
+        }
+    }
+
+    for header in headers.iter_mut() {
+        header.value = decode_encoded_words(&header.value);
+    }
+
+    (headers, body)
+}
+
+fn find_header<'a>(headers: &'a [Header], name: &str) -> Option<&'a str> {
+    headers.iter()
+        .find(|header| header.name.eq_ignore_ascii_case(name))
+        .map(|header| header.value.as_str())
+}
+
+fn collect_parts(headers: &[Header], body: &str, default_content_type: &str, depth: usize, parts: &mut Vec<MimePart>) {
+    let (content_type, parameters) = match find_header(headers, CONTENT_TYPE) {
+        Some(value) => parse_header_parameters(value),
+        None => (default_content_type.to_string(), Vec::new()),
+    };
+
+    if depth < MAX_NESTING {
+        if content_type.starts_with(MULTIPART_PREFIX) {
+            if let Some(boundary) = parameter(&parameters, BOUNDARY) {
+                let child_default = if content_type == MULTIPART_DIGEST { MESSAGE } else { DEFAULT_CONTENT_TYPE };
+                let mut children = Vec::new();
+                for child in split_multipart(body, boundary) {
+                    let (child_headers, child_body) = split_headers(child);
+                    let mut child_parts = Vec::new();
+                    collect_parts(&child_headers, child_body, child_default, depth + 1, &mut child_parts);
+                    children.push(child_parts);
+                }
+
+                if content_type == MULTIPART_ALTERNATIVE {
+                    mark_alternative_copies(&mut children);
+                }
+                parts.extend(children.into_iter().flatten());
+                return;
+            }
+        }
+
+        let is_embedded_message = content_type == MESSAGE && !is_attachment(headers);
+        if is_embedded_message {
+            let (embedded_headers, embedded_body) = split_headers(body);
+            collect_parts(&embedded_headers, embedded_body, DEFAULT_CONTENT_TYPE, depth + 1, parts);
+            return;
+        }
+    }
+
+    let (_, disposition_parameters) = find_header(headers, CONTENT_DISPOSITION)
+        .map(parse_header_parameters)
+        .unwrap_or_default();
+    let filename = parameter(&disposition_parameters, FILENAME)
+        .or_else(|| parameter(&parameters, NAME))
+        .map(String::from);
+
+    let transfer_encoding = find_header(headers, CONTENT_TRANSFER_ENCODING).unwrap_or_default().trim().to_lowercase();
+    let (decoded, needs_charset_decoding) = match transfer_encoding.as_str() {
+        BASE64 => (decode_base64(body), true),
+        QUOTED_PRINTABLE => (decode_quoted_printable(body), true),
+        _ => (body.as_bytes().to_vec(), false),
+    };
+
+    parts.push(MimePart {
+        headers: headers.to_vec(),
+        charset: parameter(&parameters, CHARSET).map(|charset| charset.to_lowercase()),
+        is_attachment: is_attachment(headers) || (filename.is_some() && !content_type.starts_with("text/")),
+        is_alternative_copy: false,
+        content_type,
+        filename,
+        body: decoded,
+        needs_charset_decoding,
+    });
+}
+
+/// Keeps the first plain text version of a `multipart/alternative`, or the first
+/// HTML one when there is no plain text, and marks the text of the others.
+fn mark_alternative_copies(alternatives: &mut [Vec<MimePart>]) {
+    let has_text = |alternative: &Vec<MimePart>, content_type: &str| {
+        alternative.iter().any(|part| part.is_text() && !part.is_alternative_copy && part.content_type == content_type)
+    };
+    let preferred = alternatives.iter().position(|alternative| has_text(alternative, TEXT_PLAIN))
+        .or_else(|| alternatives.iter().position(|alternative| has_text(alternative, TEXT_HTML)));
+
+    for (index, alternative) in alternatives.iter_mut().enumerate() {
+        if preferred.is_some_and(|preferred| preferred != index) {
+            alternative.iter_mut()
+                .filter(|part| part.is_text())
+                .for_each(|part| part.is_alternative_copy = true);
+        }
+    }
+}
+
+fn is_attachment(headers: &[Header]) -> bool {
+    find_header(headers, CONTENT_DISPOSITION)
+        .map(|disposition| parse_header_parameters(disposition).0 == ATTACHMENT)
+        .unwrap_or(false)
+}
+
+fn parameter<'a>(parameters: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    parameters.iter()
+        .find(|(parameter_name, _)| parameter_name == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Splits `value; name=value; name="quoted value"` into the lowercase value and its
+/// parameters. Parameters in the `name*=charset''percent-encoded` form of RFC 2231
+/// are decoded.
+fn parse_header_parameters(header: &str) -> (String, Vec<(String, String)>) {
+    let mut segments = split_outside_quotes(header, ';').into_iter();
+    let value = segments.next().unwrap_or_default().trim().to_lowercase();
+
+    let parameters = segments
+        .filter_map(|segment| {
+            let (name, value) = segment.split_once('=')?;
+            let name = name.trim().to_lowercase();
+            let value = value.trim();
+            match name.strip_suffix('*') {
+                Some(name) => Some((name.to_string(), decode_extended_parameter(value))),
+                None => Some((name, unquote(value))),
+            }
+        })
+        .collect();
+
+    (value, parameters)
+}
+
+fn split_outside_quotes(text: &str, separator: char) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == separator && !in_quotes {
+            segments.push(&text[start..index]);
+            start = index + 1;
+        }
+    }
+    segments.push(&text[start..]);
+
+    segments
+}
+
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => value.to_string(),
+    }
+}
+
+fn decode_extended_parameter(value: &str) -> String {
+    let mut pieces = value.splitn(3, '\'');
+    match (pieces.next(), pieces.next(), pieces.next()) {
+        (Some(charset), Some(_language), Some(encoded)) => decode_charset(Some(charset), &percent_decode(encoded)),
+        _ => String::from_utf8_lossy(&percent_decode(value)).to_string(),
+    }
+}
+
+fn percent_decode(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], hex_byte(bytes.get(i + 1..i + 3))) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    decoded
+}
+
+fn hex_byte(digits: Option<&[u8]>) -> Option<u8> {
+    let digits = std::str::from_utf8(digits?).ok()?;
+    u8::from_str_radix(digits, 16).ok()
+}
+
+/// The bodies of the parts of a multipart body. The preamble and the epilogue are
+/// dropped, and a missing closing delimiter ends the last part at the end of the body.
+fn split_multipart<'a>(body: &'a str, boundary: &str) -> Vec<&'a str> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut part_start: Option<usize> = None;
+    let mut offset = 0;
+
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed.starts_with(&delimiter) {
+            if let Some(start) = part_start {
+                // The line break before the delimiter belongs to the delimiter.
+                let end = if offset > start && body[..offset].ends_with('\n') { offset - 1 } else { offset };
+                parts.push(&body[start..end]);
+            }
+
+            if trimmed[delimiter.len()..].starts_with("--") {
+                return parts;
+            }
+            part_start = Some(offset + line.len());
+        }
+        offset += line.len();
+    }
+
+    if let Some(start) = part_start {
+        parts.push(&body[start.min(body.len())..]);
+    }
+
+    parts
+}
+
+/// Decodes base64, ignoring line breaks and any other character outside the
+/// alphabet, as mail readers do.
+pub fn decode_base64(encoded: &str) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(encoded.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in encoded.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ => continue,
+        };
+
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    decoded
+}
+
+/// Decodes quoted-printable text: `=XX` escapes and `=` soft line breaks.
+pub fn decode_quoted_printable(encoded: &str) -> Vec<u8> {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+
+        if let Some(byte) = hex_byte(bytes.get(i + 1..i + 3)) {
+            decoded.push(byte);
+            i += 3;
+        } else if bytes.get(i + 1) == Some(&b'\n') {
+            i += 2;
+        } else if bytes.get(i + 1) == Some(&b'\r') && bytes.get(i + 2) == Some(&b'\n') {
+            i += 3;
+        } else {
+            decoded.push(b'=');
+            i += 1;
+        }
+    }
+
+    decoded
+}
+
+/// Converts text in the named charset to a string. Unknown charsets are read as UTF-8.
+pub fn decode_charset(charset: Option<&str>, bytes: &[u8]) -> String {
+    let encoding = charset
+        .and_then(|charset| Encoding::for_label(charset.trim().as_bytes()))
+        .unwrap_or(UTF_8);
+    encoding.decode(bytes).0.to_string()
+}
+
+/// Decodes the `=?charset?B?...?=` and `=?charset?Q?...?=` encoded words of RFC 2047.
+/// White space between two adjacent encoded words is dropped.
+pub fn decode_encoded_words(value: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = value;
+    let mut previous_was_encoded = false;
+
+    while let Some(start) = rest.find(ENCODED_WORD_START) {
+        let word = match parse_encoded_word(&rest[start..]) {
+            Some(word) => word,
+            None => {
+                decoded.push_str(&rest[..start + ENCODED_WORD_START.len()]);
+                rest = &rest[start + ENCODED_WORD_START.len()..];
+                previous_was_encoded = false;
+                continue;
+            }
+        };
+
+        let between = &rest[..start];
+        if !(previous_was_encoded && between.trim().is_empty()) {
+            decoded.push_str(between);
+        }
+
+        decoded.push_str(&word.text);
+        rest = &rest[start + word.length..];
+        previous_was_encoded = true;
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+struct EncodedWord {
+    text: String,
+    length: usize,
+}
+
+fn parse_encoded_word(text: &str) -> Option<EncodedWord> {
+    let inner = text.strip_prefix(ENCODED_WORD_START)?;
+    let (charset, inner) = inner.split_once('?')?;
+    let (encoding, inner) = inner.split_once('?')?;
+    let end = inner.find(ENCODED_WORD_END)?;
+    let encoded = &inner[..end];
+    if encoded.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let bytes = match encoding {
+        "B" | "b" => decode_base64(encoded),
+        "Q" | "q" => decode_quoted_printable(&encoded.replace('_', " ")),
+        _ => return None,
+    };
+
+    let length = ENCODED_WORD_START.len() + charset.len() + 1 + encoding.len() + 1 + end + ENCODED_WORD_END.len();
+    // RFC 2231 allows a language after the charset: `charset*language`.
+    let charset = charset.split('*').next().unwrap_or_default();
+    Some(EncodedWord { text: decode_charset(Some(charset), &bytes), length })
+}
@@ -0,0 +1,295 @@
+use std::io::{Read, Write};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_reader, Value};
+use crate::classification::{Classification, Explanation, MemberExplanation};
+use crate::cross_validation::{Validation, DEFAULT_SEED};
+use crate::dataset::LabelledMessage;
+use crate::label::Label;
+use crate::spam_classifier::{SpamClassifier, ENSEMBLE_MODEL_TYPE};
+use crate::utills::SpamClassifierError;
+
+pub const MAJORITY_VOTE : &str = "vote";
+pub const WEIGHTED_AVERAGE : &str = "average";
+pub const STACKING : &str = "stacking";
+const STACKING_LEARNING_RATE : f64 = 0.5;
+const STACKING_ITERATIONS : usize = 500;
+/// The folds `train_batch` splits a batch into to get member outputs on messages
+/// the members were not trained on.
+const STACKING_FOLDS : usize = 5;
+const NO_MEMBERS : &str = "An ensemble needs at least one member model";
+const WEIGHTS_MISMATCH : &str = "An ensemble needs exactly one weight per member model";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CombinationMethod {
+    /// Each member votes for its label with its weight.
+    #[default]
+    MajorityVote,
+    /// Averages the spam probabilities of the members using their weights.
+    WeightedAverage,
+    /// Feeds the spam probabilities of the members into a learned logistic regression.
+    Stacking,
+}
+
+impl CombinationMethod {
+
+    pub fn from_name(name: &str) -> Option<CombinationMethod> {
+        match name {
+            MAJORITY_VOTE => Some(CombinationMethod::MajorityVote),
+            WEIGHTED_AVERAGE => Some(CombinationMethod::WeightedAverage),
+            STACKING => Some(CombinationMethod::Stacking),
+            _ => None,
+        }
+    }
+}
+
+/// The meta-classifier of a stacking ensemble: a logistic regression whose
+/// features are the spam probabilities of the members.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackingModel {
+    pub weights: Vec<f64>,
+    pub bias: f64,
+}
+
+impl StackingModel {
+
+    fn spam_probability(&self, member_probabilities: &[f64]) -> f64 {
+        let margin: f64 = self.bias + self.weights.iter().zip(member_probabilities).map(|(weight, probability)| weight * probability).sum::<f64>();
+        1.0 / (1.0 + (-margin).exp())
+    }
+
+    /// Fits the model with batch gradient descent on the log loss.
+    fn fit(member_probabilities: &[Vec<f64>], labels: &[Label], members_count: usize) -> StackingModel {
+        let mut model = StackingModel { weights: vec![0.0; members_count], bias: 0.0 };
+        if labels.is_empty() {
+            return model;
+        }
+
+        let examples_count = labels.len() as f64;
+        for _ in 0..STACKING_ITERATIONS {
+            let mut weight_gradients = vec![0.0; members_count];
+            let mut bias_gradient = 0.0;
+            for (probabilities, label) in member_probabilities.iter().zip(labels) {
+                let target = if *label == Label::Spam { 1.0 } else { 0.0 };
+                let error = model.spam_probability(probabilities) - target;
+                for (gradient, probability) in weight_gradients.iter_mut().zip(probabilities) {
+                    *gradient += error * probability;
+                }
+                bias_gradient += error;
+            }
+
+            for (weight, gradient) in model.weights.iter_mut().zip(weight_gradients) {
+                *weight -= STACKING_LEARNING_RATE * gradient / examples_count;
+            }
+            model.bias -= STACKING_LEARNING_RATE * bias_gradient / examples_count;
+        }
+
+        model
+    }
+}
+
+/// The persisted form of an ensemble. Members are stored as the tagged JSON
+/// their own `save` produces.
+#[derive(Serialize, Deserialize)]
+struct EnsembleModel {
+    method: CombinationMethod,
+    weights: Vec<f64>,
+    #[serde(default)]
+    stacking: Option<StackingModel>,
+    members: Vec<Value>,
+}
+
+/// Combines the classifications of several member models.
+pub struct EnsembleClassifier {
+    method: CombinationMethod,
+    members: Vec<Box<dyn SpamClassifier>>,
+    weights: Vec<f64>,
+    stacking: Option<StackingModel>,
+}
+
+impl EnsembleClassifier {
+
+    /// Creates an ensemble in which every member has the same weight.
+    pub fn new(members: Vec<Box<dyn SpamClassifier>>, method: CombinationMethod) -> Result<Self, SpamClassifierError> {
+        let weights = vec![1.0; members.len()];
+        EnsembleClassifier::with_weights(members, weights, method)
+    }
+
+    pub fn with_weights(members: Vec<Box<dyn SpamClassifier>>, weights: Vec<f64>, method: CombinationMethod) -> Result<Self, SpamClassifierError> {
+        if members.is_empty() {
+            return Err(SpamClassifierError::InvalidModelError(NO_MEMBERS.to_string()));
+        }
+
+        if members.len() != weights.len() {
+            return Err(SpamClassifierError::InvalidModelError(WEIGHTS_MISMATCH.to_string()));
+        }
+
+        Ok(EnsembleClassifier { method, members, weights, stacking: None })
+    }
+
+    pub(crate) fn from_value(value: Value) -> Result<Self, SpamClassifierError> {
+        let model: EnsembleModel = serde_json::from_value(value).map_err(SpamClassifierError::Serde)?;
+
+        let mut members = Vec::new();
+        for member in model.members {
+            members.push(crate::spam_classifier::model_from_value(member)?);
+        }
+
+        let mut ensemble = EnsembleClassifier::with_weights(members, model.weights, model.method)?;
+        ensemble.stacking = model.stacking;
+        Ok(ensemble)
+    }
+
+    pub fn method(&self) -> CombinationMethod {
+        self.method
+    }
+
+    pub fn members(&self) -> &[Box<dyn SpamClassifier>] {
+        &self.members
+    }
+
+    pub fn stacking(&self) -> Option<&StackingModel> {
+        self.stacking.as_ref()
+    }
+
+    /// Learns the stacking meta-classifier from the members' outputs on `messages`,
+    /// which should not be the messages the members were trained on.
+    pub fn fit_stacking(&mut self, messages: &[LabelledMessage]) {
+        let member_probabilities: Vec<Vec<f64>> = messages.iter()
+            .map(|message| self.member_probabilities(&message.text))
+            .collect();
+        let labels: Vec<Label> = messages.iter().map(|message| message.label).collect();
+
+        self.stacking = Some(StackingModel::fit(&member_probabilities, &labels, self.members.len()));
+    }
+
+    /// The spam probability every member gives every message after learning, from
+    /// its current state, the other folds of `messages`. None when the messages
+    /// are too few to split or a member cannot be copied.
+    fn out_of_fold_probabilities(&self, messages: &[LabelledMessage]) -> Option<Vec<Vec<f64>>> {
+        let validation = Validation::CrossValidation { folds: STACKING_FOLDS.min(messages.len()), seed: DEFAULT_SEED };
+        let splits = validation.splits(messages).ok()?;
+        let snapshots = self.members.iter().map(|member| member.to_bytes()).collect::<Result<Vec<_>, _>>().ok()?;
+
+        let mut probabilities = vec![Vec::with_capacity(self.members.len()); messages.len()];
+        for split in splits {
+            let train: Vec<LabelledMessage> = split.train.iter().map(|&index| messages[index].clone()).collect();
+            for snapshot in &snapshots {
+                let mut member = crate::spam_classifier::load_model_from_bytes(snapshot).ok()?;
+                member.train_batch(&train);
+                for &index in &split.test {
+                    probabilities[index].push(member.classify(&messages[index].text).spam_probability());
+                }
+            }
+        }
+        Some(probabilities)
+    }
+
+    fn member_probabilities(&self, msg: &str) -> Vec<f64> {
+        self.members.iter().map(|member| member.classify(msg).spam_probability()).collect()
+    }
+
+    /// Weights which sum to zero count every member equally, rather than dividing by zero.
+    fn combine(&self, member_classifications: &[Classification]) -> Classification {
+        let equal_weights;
+        let mut weights = &self.weights;
+        if weights.iter().sum::<f64>() <= 0.0 {
+            equal_weights = vec![1.0; self.weights.len()];
+            weights = &equal_weights;
+        }
+        let total_weight: f64 = weights.iter().sum();
+
+        let spam_probability = match self.method {
+            CombinationMethod::MajorityVote => {
+                let spam_votes: f64 = member_classifications.iter().zip(weights)
+                    .filter(|(classification, _)| classification.is_spam())
+                    .map(|(_, weight)| weight)
+                    .sum();
+                spam_votes / total_weight
+            }
+            CombinationMethod::WeightedAverage => {
+                let weighted_sum: f64 = member_classifications.iter().zip(weights)
+                    .map(|(classification, weight)| classification.spam_probability() * weight)
+                    .sum();
+                weighted_sum / total_weight
+            }
+            CombinationMethod::Stacking => {
+                let probabilities: Vec<f64> = member_classifications.iter().map(|classification| classification.spam_probability()).collect();
+                match &self.stacking {
+                    Some(stacking) => stacking.spam_probability(&probabilities),
+                    None => probabilities.iter().sum::<f64>() / probabilities.len() as f64,
+                }
+            }
+        };
+
+        Classification::new(spam_probability, 1.0 - spam_probability)
+    }
+}
+
+impl SpamClassifier for EnsembleClassifier {
+
+    fn model_type(&self) -> &'static str {
+        ENSEMBLE_MODEL_TYPE
+    }
+
+    fn train(&mut self, msg: &str, label: Label) {
+        for member in self.members.iter_mut() {
+            member.train(msg, label);
+        }
+    }
+
+    fn reinforce(&mut self, msg: &str, label: Label) {
+        for member in self.members.iter_mut() {
+            member.reinforce(msg, label);
+        }
+    }
+
+    /// A stacking ensemble also fits its meta-classifier, on what copies of the
+    /// members trained on the other folds of the batch say about each message.
+    fn train_batch(&mut self, messages: &[LabelledMessage]) {
+        let out_of_fold = match self.method {
+            CombinationMethod::Stacking => self.out_of_fold_probabilities(messages),
+            _ => None,
+        };
+
+        for member in self.members.iter_mut() {
+            member.train_batch(messages);
+        }
+
+        if let Some(member_probabilities) = out_of_fold {
+            let labels: Vec<Label> = messages.iter().map(|message| message.label).collect();
+            self.stacking = Some(StackingModel::fit(&member_probabilities, &labels, self.members.len()));
+        }
+    }
+
+    fn classify(&self, msg: &str) -> Classification {
+        let member_classifications: Vec<Classification> = self.members.iter().map(|member| member.classify(msg)).collect();
+        self.combine(&member_classifications)
+    }
+
+    fn explain(&self, msg: &str) -> Explanation {
+        let members: Vec<MemberExplanation> = self.members.iter()
+            .map(|member| MemberExplanation { model_type: member.model_type().to_string(), explanation: member.explain(msg) })
+            .collect();
+        let member_classifications: Vec<Classification> = members.iter().map(|member| member.explanation.classification).collect();
+
+        let mut explanation = Explanation::new(self.combine(&member_classifications), Vec::new());
+        explanation.members = members;
+        explanation
+    }
+
+    fn save(&self, writer: &mut dyn Write) -> Result<(), SpamClassifierError> {
+        let mut members = Vec::new();
+        for member in &self.members {
+            members.push(serde_json::from_slice(&member.to_bytes()?).map_err(SpamClassifierError::Serde)?);
+        }
+
+        let model = EnsembleModel { method: self.method, weights: self.weights.clone(), stacking: self.stacking.clone(), members };
+        crate::spam_classifier::save_tagged(writer, ENSEMBLE_MODEL_TYPE, &model)
+    }
+
+    fn load(reader: &mut dyn Read) -> Result<Self, SpamClassifierError> {
+        let value: Value = from_reader(reader).map_err(SpamClassifierError::Serde)?;
+        EnsembleClassifier::from_value(value)
+    }
+}
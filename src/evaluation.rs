@@ -0,0 +1,200 @@
+use crate::calibration::{Reliability, DEFAULT_RELIABILITY_BINS};
+use crate::classification::Classification;
+use crate::curves::{Curves, LabelledScore};
+use crate::dataset::LabelledMessage;
+use crate::label::{Label, HAM, SPAM};
+use crate::spam_classifier::SpamClassifier;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_CUTOFF : f64 = 0.5;
+pub const UNSURE : &str = "unsure";
+
+/// What a classification means once the unsure band is taken into account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Ham,
+    Unsure,
+    Spam,
+}
+
+impl Verdict {
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::Ham => HAM,
+            Verdict::Unsure => UNSURE,
+            Verdict::Spam => SPAM,
+        }
+    }
+
+    pub fn is_correct(&self, label: Label) -> bool {
+        matches!((self, label), (Verdict::Ham, Label::Ham) | (Verdict::Spam, Label::Spam))
+    }
+}
+
+/// Spam probabilities up to `ham_cutoff` are ham, those from `spam_cutoff` on
+/// are spam, and those strictly between are left unsure, for a person to look
+/// at. Equal cutoffs leave nothing unsure and count a tie at the cutoff as ham.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Cutoffs {
+    pub ham_cutoff: f64,
+    pub spam_cutoff: f64,
+}
+
+impl Default for Cutoffs {
+    fn default() -> Self {
+        Cutoffs { ham_cutoff: DEFAULT_CUTOFF, spam_cutoff: DEFAULT_CUTOFF }
+    }
+}
+
+impl Cutoffs {
+
+    pub fn verdict(&self, classification: &Classification) -> Verdict {
+        let probability = classification.spam_probability();
+        if probability <= self.ham_cutoff {
+            Verdict::Ham
+        } else if probability >= self.spam_cutoff {
+            Verdict::Spam
+        } else {
+            Verdict::Unsure
+        }
+    }
+}
+
+/// How many messages of each label got each verdict.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfusionMatrix {
+    pub ham_as_ham: usize,
+    pub ham_as_unsure: usize,
+    pub ham_as_spam: usize,
+    pub spam_as_ham: usize,
+    pub spam_as_unsure: usize,
+    pub spam_as_spam: usize,
+}
+
+impl ConfusionMatrix {
+
+    pub fn record(&mut self, label: Label, verdict: Verdict) {
+        let count = match (label, verdict) {
+            (Label::Ham, Verdict::Ham) => &mut self.ham_as_ham,
+            (Label::Ham, Verdict::Unsure) => &mut self.ham_as_unsure,
+            (Label::Ham, Verdict::Spam) => &mut self.ham_as_spam,
+            (Label::Spam, Verdict::Ham) => &mut self.spam_as_ham,
+            (Label::Spam, Verdict::Unsure) => &mut self.spam_as_unsure,
+            (Label::Spam, Verdict::Spam) => &mut self.spam_as_spam,
+        };
+        *count += 1;
+    }
+
+    pub fn total(&self) -> usize {
+        self.ham() + self.spam()
+    }
+
+    pub fn ham(&self) -> usize {
+        self.ham_as_ham + self.ham_as_unsure + self.ham_as_spam
+    }
+
+    pub fn spam(&self) -> usize {
+        self.spam_as_ham + self.spam_as_unsure + self.spam_as_spam
+    }
+
+    pub fn unsure(&self) -> usize {
+        self.ham_as_unsure + self.spam_as_unsure
+    }
+}
+
+/// A message of the test set the model did not get right, unsure ones included.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mistake {
+    /// Position of the message in the test set, starting at 1.
+    pub message: usize,
+    pub label: Label,
+    pub verdict: Verdict,
+    pub spam_probability: f64,
+    pub text: String,
+}
+
+/// How well a model classifies a labelled test set. Spam is the positive class
+/// and unsure messages count as not classified correctly, so the recall is the
+/// share of spam caught without a person looking at it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Evaluation {
+    pub messages: usize,
+    pub cutoffs: Cutoffs,
+    pub confusion_matrix: ConfusionMatrix,
+    pub accuracy: f64,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    pub false_positive_rate: f64,
+    pub unsure_rate: f64,
+    pub roc_auc: f64,
+    pub pr_auc: f64,
+    pub reliability: Reliability,
+    pub mistakes: Vec<Mistake>,
+    /// The ROC and precision-recall curves over every threshold.
+    #[serde(skip)]
+    pub curves: Curves,
+}
+
+impl Evaluation {
+
+    pub fn from_classifications(messages: &[LabelledMessage], classifications: &[Classification], cutoffs: Cutoffs) -> Evaluation {
+        let mut confusion_matrix = ConfusionMatrix::default();
+        let mut mistakes = Vec::new();
+        let mut scores = Vec::with_capacity(messages.len());
+
+        for (index, (message, classification)) in messages.iter().zip(classifications).enumerate() {
+            let verdict = cutoffs.verdict(classification);
+            confusion_matrix.record(message.label, verdict);
+            scores.push(LabelledScore { label: message.label, spam_probability: classification.spam_probability() });
+            if !verdict.is_correct(message.label) {
+                mistakes.push(Mistake {
+                    message: index + 1,
+                    label: message.label,
+                    verdict,
+                    spam_probability: classification.spam_probability(),
+                    text: message.text.clone(),
+                });
+            }
+        }
+
+        let matrix = confusion_matrix;
+        let precision = ratio(matrix.spam_as_spam, matrix.spam_as_spam + matrix.ham_as_spam);
+        let recall = ratio(matrix.spam_as_spam, matrix.spam());
+        let curves = Curves::from_scores(&scores);
+        Evaluation {
+            messages: matrix.total(),
+            cutoffs,
+            confusion_matrix,
+            accuracy: ratio(matrix.ham_as_ham + matrix.spam_as_spam, matrix.total()),
+            precision,
+            recall,
+            f1: if precision + recall > 0.0 { 2.0 * precision * recall / (precision + recall) } else { 0.0 },
+            false_positive_rate: ratio(matrix.ham_as_spam, matrix.ham()),
+            unsure_rate: ratio(matrix.unsure(), matrix.total()),
+            roc_auc: curves.roc_auc,
+            pr_auc: curves.pr_auc,
+            reliability: Reliability::from_scores(&scores, DEFAULT_RELIABILITY_BINS).unwrap_or_default(),
+            mistakes,
+            curves,
+        }
+    }
+}
+
+/// Classifies every message of the test set, in parallel, and measures the results.
+pub fn evaluate<C: SpamClassifier + ?Sized>(classifier: &C, messages: &[LabelledMessage], cutoffs: Cutoffs) -> Evaluation {
+    let texts: Vec<&str> = messages.iter().map(|message| message.text.as_str()).collect();
+    let classifications = classifier.classify_batch(&texts);
+    Evaluation::from_classifications(messages, &classifications, cutoffs)
+}
+
+/// `numerator / denominator`, or 0 when there is nothing to divide.
+pub(crate) fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        return 0.0;
+    }
+
+    numerator as f64 / denominator as f64
+}
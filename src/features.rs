@@ -0,0 +1,125 @@
+use crate::tokenizer::MessageTokens;
+use crate::utills::SpamClassifierError;
+use serde::{Deserialize, Serialize};
+
+pub const UNIGRAM : &str = "unigram";
+pub const OSB : &str = "osb";
+pub const SBPH : &str = "sbph";
+pub const DEFAULT_WINDOW : usize = 5;
+pub const MIN_WINDOW : usize = 2;
+/// An OSB window adds `window - 1` pairs per word.
+pub const MAX_OSB_WINDOW : usize = 16;
+/// An SBPH window adds `2^(window - 1)` features per word.
+pub const MAX_SBPH_WINDOW : usize = 8;
+const OSB_PREFIX : &str = "osb:";
+const SBPH_PREFIX : &str = "sbph:";
+const SKIPPED_TOKEN : &str = "<skip>";
+const FNV_OFFSET_BASIS : u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME : u64 = 0x0000_0100_0000_01b3;
+
+/// Turns a message into the features a model counts. It is stored in the model
+/// so that scoring uses the same extractor as training did.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum FeatureExtractor {
+    /// Every word on its own.
+    #[default]
+    Unigram,
+    /// Orthogonal sparse bigrams: the words, plus every pair of words at most
+    /// `window - 1` positions apart, with the size of the gap as part of the pair.
+    Osb { window: usize },
+    /// Sparse binary polynomial hashing: every subsequence of a window of
+    /// `window` words which ends with the last word of the window.
+    Sbph { window: usize },
+}
+
+impl FeatureExtractor {
+
+    /// The extractor of the given name. The window is ignored by unigrams and must
+    /// be within `MIN_WINDOW` and the maximum window of the other extractors.
+    pub fn from_name(name: &str, window: usize) -> Result<FeatureExtractor, SpamClassifierError> {
+        let max_window = match name {
+            UNIGRAM => return Ok(FeatureExtractor::Unigram),
+            OSB => MAX_OSB_WINDOW,
+            SBPH => MAX_SBPH_WINDOW,
+            _ => return Err(SpamClassifierError::InvalidParameter(format!("Unknown features - {:?}", name))),
+        };
+
+        if !(MIN_WINDOW..=max_window).contains(&window) {
+            return Err(SpamClassifierError::InvalidParameter(
+                format!("The {} feature window must be between {} and {}, not {}", name, MIN_WINDOW, max_window, window)));
+        }
+
+        match name {
+            OSB => Ok(FeatureExtractor::Osb { window }),
+            _ => Ok(FeatureExtractor::Sbph { window }),
+        }
+    }
+
+    /// The features of the words of the message, followed by the structural
+    /// features of the message, which are never combined with words.
+    pub fn extract(&self, msg: &str) -> Vec<String> {
+        self.extract_from(crate::tokenizer::analyze(msg))
+    }
+
+    /// The features of tokens which were already split from a message.
+    pub fn extract_from(&self, tokens: MessageTokens) -> Vec<String> {
+        let mut features = match *self {
+            FeatureExtractor::Unigram => tokens.words,
+            // Extractors built directly or read from a model file are not validated,
+            // so their windows are capped here as well.
+            FeatureExtractor::Osb { window } => orthogonal_sparse_bigrams(tokens.words, window.min(MAX_OSB_WINDOW)),
+            FeatureExtractor::Sbph { window } => sparse_binary_polynomials(&tokens.words, window.min(MAX_SBPH_WINDOW)),
+        };
+        features.extend(tokens.structure);
+        features
+    }
+}
+
+fn orthogonal_sparse_bigrams(tokens: Vec<String>, window: usize) -> Vec<String> {
+    let mut features = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        for distance in 1..window.min(i + 1) {
+            let earlier = &tokens[i - distance];
+            let gap = distance - 1;
+            features.push(hashed_feature(OSB_PREFIX, &[earlier, &gap.to_string(), token]));
+        }
+    }
+
+    let mut all_features = tokens;
+    all_features.extend(features);
+    all_features
+}
+
+fn sparse_binary_polynomials(tokens: &[String], window: usize) -> Vec<String> {
+    let mut features = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let preceding = window.saturating_sub(1).min(i);
+        for mask in 0..(1u64 << preceding) {
+            let mut parts: Vec<&str> = (0..preceding)
+                .rev()
+                .map(|distance| {
+                    if mask & (1 << distance) != 0 { tokens[i - distance - 1].as_str() } else { SKIPPED_TOKEN }
+                })
+                .collect();
+            parts.push(token);
+            features.push(hashed_feature(SBPH_PREFIX, &parts));
+        }
+    }
+
+    features
+}
+
+/// Hashes the parts with 64-bit FNV-1a, which unlike the standard library hasher
+/// is stable between runs and platforms.
+fn hashed_feature(prefix: &str, parts: &[&str]) -> String {
+    let mut hash = FNV_OFFSET_BASIS;
+    for part in parts {
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    format!("{}{:016x}", prefix, hash)
+}
@@ -0,0 +1,81 @@
+use crate::email::{looks_like_email, parse_addresses, Email};
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The headers tokenised by models created from the command line.
+pub const DEFAULT_HEADERS : &str = "subject,from,reply-to,to,cc,x-mailer";
+const HEADER_SEPARATOR : char = ',';
+const PREFIX_SEPARATOR : char = ':';
+const COUNT_PREFIX : &str = "count=";
+/// Headers naming the sender, which are tokenised as the domains of their addresses.
+const SENDER_HEADERS : [&str; 4] = ["from", "sender", "reply-to", "return-path"];
+/// Headers naming the recipients, which are tokenised as the number of addresses.
+const RECIPIENT_HEADERS : [&str; 3] = ["to", "cc", "bcc"];
+
+/// Turns the headers of an e-mail into tokens prefixed by the header they come
+/// from, such as `subject:free`, `from:example.com` or `to:count=40`, so that a
+/// word weighs differently in the subject than in the body. It is stored in the
+/// model so that scoring tokenises the same headers as training did.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeaderFeatures {
+    headers: Vec<String>,
+}
+
+impl HeaderFeatures {
+
+    pub fn new(headers: &[&str]) -> Self {
+        HeaderFeatures { headers: headers.iter().map(|header| header.trim().to_lowercase()).collect() }
+    }
+
+    /// Parses a comma separated list of header names. An empty list tokenises no header.
+    pub fn from_list(headers: &str) -> Self {
+        let names: Vec<&str> = headers.split(HEADER_SEPARATOR)
+            .map(str::trim)
+            .filter(|header| !header.is_empty())
+            .collect();
+        HeaderFeatures::new(&names)
+    }
+
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// The header tokens of the message, or none when it is not an e-mail.
+    pub fn extract(&self, msg: &str) -> Vec<String> {
+        if self.headers.is_empty() || !looks_like_email(msg) {
+            return Vec::new();
+        }
+
+        self.extract_from(&Email::parse(msg))
+    }
+
+    pub fn extract_from(&self, email: &Email) -> Vec<String> {
+        let mut tokens = Vec::new();
+        for header in &self.headers {
+            let values: Vec<&str> = email.header_values(header).collect();
+            if values.is_empty() {
+                continue;
+            }
+
+            if SENDER_HEADERS.contains(&header.as_str()) {
+                for address in values.iter().flat_map(|value| parse_addresses(value)) {
+                    let domain = address.rsplit('@').next().unwrap_or_default().to_lowercase();
+                    tokens.push(prefixed(header, &domain));
+                }
+            } else if RECIPIENT_HEADERS.contains(&header.as_str()) {
+                let count: usize = values.iter().map(|value| parse_addresses(value).len()).sum();
+                tokens.push(prefixed(header, &format!("{}{}", COUNT_PREFIX, count)));
+            } else {
+                for word in values.iter().flat_map(|value| value.unicode_words()) {
+                    tokens.push(prefixed(header, word));
+                }
+            }
+        }
+
+        tokens
+    }
+}
+
+fn prefixed(header: &str, token: &str) -> String {
+    format!("{}{}{}", header, PREFIX_SEPARATOR, token)
+}
@@ -0,0 +1,370 @@
+const FEATURE_PREFIX : &str = "html:";
+const LINK_FEATURE : &str = "link:";
+const LINKS_FEATURE : &str = "links=";
+const IMAGES_FEATURE : &str = "images=";
+const ANCHOR_MISMATCHES_FEATURE : &str = "anchor-mismatches=";
+const HIDDEN_FEATURE : &str = "hidden=";
+const FONT_TRICKS_FEATURE : &str = "font-tricks=";
+const COMMENT_START : &str = "<!--";
+const COMMENT_END : &str = "-->";
+const HTTP_SCHEMES : [&str; 2] = ["http://", "https://"];
+const WWW_PREFIX : &str = "www.";
+/// Elements whose content is never displayed.
+const INVISIBLE_ELEMENTS : [&str; 5] = ["script", "style", "title", "head", "template"];
+/// Elements which are never closed.
+const VOID_ELEMENTS : [&str; 10] = ["br", "img", "hr", "meta", "link", "input", "area", "base", "col", "wbr"];
+/// Elements which break the text into separate lines.
+const BLOCK_ELEMENTS : [&str; 18] = ["br", "p", "div", "li", "tr", "td", "th", "h1", "h2", "h3", "h4", "h5", "h6", "hr", "table", "ul", "ol", "blockquote"];
+const HIDING_STYLES : [&str; 3] = ["display:none", "visibility:hidden", "opacity:0"];
+const TINY_FONT_STYLES : [&str; 6] = ["font-size:0", "font-size:1px", "font-size:2px", "font-size:0px", "font-size:0pt", "font-size:1pt"];
+const TINY_FONT_SIZES : [&str; 2] = ["0", "1"];
+const INVISIBLE_COLORS : [&str; 3] = ["#fff", "#ffffff", "white"];
+const MARKERS : [&str; 3] = ["<html", "<body", "<!doctype html"];
+
+/// A hyperlink and the text it is shown as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub target: String,
+    pub text: String,
+}
+
+impl Link {
+
+    /// Whether the text of the link names a different host than the one it points
+    /// to, as in `<a href="http://phish.example">www.bank.com</a>`.
+    pub fn is_mismatched(&self) -> bool {
+        let text = self.text.trim();
+        let text_is_address = !text.contains(char::is_whitespace) && text.contains('.')
+            && text.chars().any(char::is_alphabetic);
+        if !text_is_address {
+            return false;
+        }
+
+        match (url_host(&self.target), host(text)) {
+            (Some(target_host), Some(text_host)) => target_host != text_host,
+            _ => false,
+        }
+    }
+}
+
+/// The visible text of an HTML document and the structure spam hides behind it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HtmlDocument {
+    /// The text a reader sees, without tags, scripts, styles and hidden elements.
+    pub text: String,
+    pub links: Vec<Link>,
+    pub images: usize,
+    /// Elements hidden with `display:none`, `visibility:hidden`, `opacity:0` or the
+    /// `hidden` attribute.
+    pub hidden_elements: usize,
+    /// Elements whose text is made unreadable with a tiny or white font.
+    pub font_tricks: usize,
+}
+
+struct OpenElement {
+    name: String,
+    hides_text: bool,
+}
+
+impl HtmlDocument {
+
+    /// Parses an HTML document leniently, as browsers do: unclosed elements are
+    /// closed by the end of their parent and unknown markup is skipped.
+    pub fn parse(html: &str) -> HtmlDocument {
+        let mut document = HtmlDocument::default();
+        let mut open_elements: Vec<OpenElement> = Vec::new();
+        let mut open_link: Option<Link> = None;
+        let mut rest = html;
+
+        while !rest.is_empty() {
+            let tag_start = rest.find('<').unwrap_or(rest.len());
+            if open_elements.iter().all(|element| !element.hides_text) {
+                let text = decode_entities(&rest[..tag_start]);
+                if let Some(link) = open_link.as_mut() {
+                    link.text.push_str(&text);
+                }
+                document.text.push_str(&text);
+            }
+            rest = &rest[tag_start..];
+            if rest.is_empty() {
+                break;
+            }
+
+            let starts_markup = rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!' || c == '?');
+            if !starts_markup {
+                if open_elements.iter().all(|element| !element.hides_text) {
+                    if let Some(link) = open_link.as_mut() {
+                        link.text.push('<');
+                    }
+                    document.text.push('<');
+                }
+                rest = &rest[1..];
+                continue;
+            }
+
+            if rest.starts_with(COMMENT_START) {
+                rest = rest.find(COMMENT_END).map(|end| &rest[end + COMMENT_END.len()..]).unwrap_or("");
+                continue;
+            }
+
+            let tag_end = rest.find('>').unwrap_or(rest.len());
+            let tag = &rest[1..tag_end];
+            rest = if tag_end < rest.len() { &rest[tag_end + 1..] } else { "" };
+
+            if tag.starts_with('!') || tag.starts_with('?') {
+                continue;
+            }
+
+            if let Some(closing) = tag.strip_prefix('/') {
+                let name = closing.trim().to_lowercase();
+                if let Some(position) = open_elements.iter().rposition(|element| element.name == name) {
+                    open_elements.truncate(position);
+                }
+                if name == "a" {
+                    document.links.extend(open_link.take());
+                }
+                document.push_break_for(&name);
+                continue;
+            }
+
+            let (name, attributes) = parse_tag(tag);
+            if name.is_empty() {
+                continue;
+            }
+            document.push_break_for(&name);
+
+            let is_hidden = is_hidden(&attributes);
+            let is_font_trick = is_font_trick(&name, &attributes);
+            document.hidden_elements += is_hidden as usize;
+            document.font_tricks += is_font_trick as usize;
+
+            match name.as_str() {
+                "img" => document.images += 1,
+                "a" => {
+                    document.links.extend(open_link.take());
+                    open_link = attribute(&attributes, "href").map(|target| Link { target: target.to_string(), text: String::new() });
+                }
+                _ => {}
+            }
+
+            let is_self_closing = tag.trim_end().ends_with('/');
+            if !VOID_ELEMENTS.contains(&name.as_str()) && !is_self_closing {
+                let hides_text = is_hidden || is_font_trick || INVISIBLE_ELEMENTS.contains(&name.as_str());
+                open_elements.push(OpenElement { name, hides_text });
+            }
+        }
+
+        document.links.extend(open_link);
+        document
+    }
+
+    fn push_break_for(&mut self, name: &str) {
+        if BLOCK_ELEMENTS.contains(&name) && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+    }
+
+    pub fn mismatched_links(&self) -> impl Iterator<Item = &Link> {
+        self.links.iter().filter(|link| link.is_mismatched())
+    }
+
+    /// Features describing the structure of the document: the hosts it links to,
+    /// and the numbers of links, images, mismatched anchors, hidden elements and
+    /// font tricks, rounded up to a power of two so that similar documents share
+    /// them.
+    pub fn features(&self) -> Vec<String> {
+        let mut hosts: Vec<String> = self.links.iter().filter_map(|link| url_host(&link.target)).collect();
+        hosts.sort();
+        hosts.dedup();
+
+        let mut features: Vec<String> = hosts.into_iter()
+            .map(|host| format!("{}{}{}", FEATURE_PREFIX, LINK_FEATURE, host))
+            .collect();
+
+        let counts = [
+            (LINKS_FEATURE, self.links.len()),
+            (IMAGES_FEATURE, self.images),
+            (ANCHOR_MISMATCHES_FEATURE, self.mismatched_links().count()),
+            (HIDDEN_FEATURE, self.hidden_elements),
+            (FONT_TRICKS_FEATURE, self.font_tricks),
+        ];
+        for (feature, count) in counts.iter() {
+            if *count > 0 {
+                features.push(format!("{}{}{}", FEATURE_PREFIX, feature, count.next_power_of_two()));
+            }
+        }
+
+        features
+    }
+}
+
+/// Whether a message which is not an e-mail is an HTML document.
+pub fn looks_like_html(text: &str) -> bool {
+    let start: String = text.trim_start().chars().take(512).collect::<String>().to_lowercase();
+    MARKERS.iter().any(|marker| start.contains(marker))
+}
+
+/// Splits the inside of a start tag into the lowercase element name and its
+/// attributes, with lowercase names and unquoted values.
+fn parse_tag(tag: &str) -> (String, Vec<(String, String)>) {
+    let tag = tag.trim_end_matches('/');
+    let name_end = tag.find(|c: char| c.is_whitespace() || c == '/').unwrap_or(tag.len());
+    let name = tag[..name_end].to_lowercase();
+
+    let mut attributes = Vec::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let attribute_end = rest.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(rest.len());
+        let attribute_name = rest[..attribute_end].trim_matches('/').to_lowercase();
+        rest = rest[attribute_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after_equals) = rest.strip_prefix('=') {
+            let after_equals = after_equals.trim_start();
+            let (raw_value, remaining) = match after_equals.chars().next() {
+                Some(quote) if quote == '"' || quote == '\'' => {
+                    let end = after_equals[1..].find(quote).map(|end| end + 1).unwrap_or(after_equals.len());
+                    (&after_equals[1..end], after_equals.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after_equals.find(char::is_whitespace).unwrap_or(after_equals.len());
+                    (&after_equals[..end], &after_equals[end..])
+                }
+            };
+            value = decode_entities(raw_value);
+            rest = remaining.trim_start();
+        }
+
+        if !attribute_name.is_empty() {
+            attributes.push((attribute_name, value));
+        }
+    }
+
+    (name, attributes)
+}
+
+fn attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attributes.iter()
+        .find(|(attribute_name, _)| attribute_name == name)
+        .map(|(_, value)| value.as_str())
+}
+
+fn style(attributes: &[(String, String)]) -> String {
+    attribute(attributes, "style")
+        .map(|style| style.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn is_hidden(attributes: &[(String, String)]) -> bool {
+    let style = style(attributes);
+    attribute(attributes, "hidden").is_some() || HIDING_STYLES.iter().any(|hiding| style_declares(&style, hiding))
+}
+
+fn is_font_trick(name: &str, attributes: &[(String, String)]) -> bool {
+    let style = style(attributes);
+    let tiny_style = TINY_FONT_STYLES.iter().any(|tiny| style_declares(&style, tiny));
+    let invisible_style = INVISIBLE_COLORS.iter().any(|color| style_declares(&style, &format!("color:{}", color)));
+    if tiny_style || invisible_style {
+        return true;
+    }
+
+    if name != "font" {
+        return false;
+    }
+
+    let tiny_size = attribute(attributes, "size").is_some_and(|size| TINY_FONT_SIZES.contains(&size.trim()) || size.trim().starts_with('-'));
+    let invisible_color = attribute(attributes, "color").is_some_and(|color| INVISIBLE_COLORS.contains(&color.trim().to_lowercase().as_str()));
+    tiny_size || invisible_color
+}
+
+/// Whether a whitespace-free, lowercase style declares the property exactly, so
+/// that `color:#fff` does not match `background-color:#fff` or `color:#fff000`.
+fn style_declares(style: &str, declaration: &str) -> bool {
+    style.split(';').any(|property| property.trim_end_matches("!important") == declaration)
+}
+
+/// The host of an absolute http(s) URL. Relative links have none.
+fn url_host(url: &str) -> Option<String> {
+    let lowercase = url.trim().to_lowercase();
+    if !HTTP_SCHEMES.iter().any(|scheme| lowercase.starts_with(scheme)) {
+        return None;
+    }
+
+    host(&lowercase)
+}
+
+/// The lowercase host of an http(s) URL or of a bare `www.host.tld/path` address,
+/// without its `www.` prefix.
+fn host(address: &str) -> Option<String> {
+    let lowercase = address.trim().to_lowercase();
+    let without_scheme = match HTTP_SCHEMES.iter().find(|scheme| lowercase.starts_with(*scheme)) {
+        Some(scheme) => &lowercase[scheme.len()..],
+        None if lowercase.contains(':') => return None,
+        None => &lowercase[..],
+    };
+
+    let authority = without_scheme.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default().split(':').next().unwrap_or_default();
+    let host = host.strip_prefix(WWW_PREFIX).unwrap_or(host);
+    if host.contains('.') { Some(host.to_string()) } else { None }
+}
+
+/// Decodes the character references of HTML text. Unknown named references are
+/// kept as they are.
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = match rest.find(';') {
+            Some(end) if end <= 10 => end,
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+
+        match decode_entity(&rest[1..end]) {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+
+    decoded
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(number) = entity.strip_prefix('#') {
+        let code = match number.strip_prefix('x').or_else(|| number.strip_prefix('X')) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        "copy" => Some('©'),
+        "reg" => Some('®'),
+        "euro" => Some('€'),
+        "pound" => Some('£'),
+        "shy" => Some('\u{ad}'),
+        "zwnj" => Some('\u{200c}'),
+        _ => None,
+    }
+}
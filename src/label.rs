@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+pub const HAM : &str = "ham";
+pub const SPAM : &str = "spam";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Label {
+    Ham,
+    Spam,
+}
+
+impl Label {
+
+    pub fn from_dataset_label(label: &str) -> Option<Label> {
+        match label {
+            HAM => Some(Label::Ham),
+            SPAM => Some(Label::Spam),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Label::Ham => HAM,
+            Label::Spam => SPAM,
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::cache::fingerprint;
+use crate::label::Label;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Write;
+
+/// The fingerprints of the messages a model has learned, with the label each was
+/// learned as. Only fingerprints are kept, so the model file does not carry the
+/// text of the messages.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ledger {
+    messages: BTreeMap<String, Label>,
+    /// How many more times than once the messages which were learned again were
+    /// learned, by fingerprint.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    repetitions: BTreeMap<String, usize>,
+}
+
+impl Ledger {
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The label the message was learned as, if it was learned.
+    pub fn label_of(&self, msg: &str) -> Option<Label> {
+        self.messages.get(&fingerprint(msg)).copied()
+    }
+
+    /// How many times the message was learned, 0 if it was not.
+    pub fn times_learned(&self, msg: &str) -> usize {
+        let fingerprint = fingerprint(msg);
+        match self.messages.get(&fingerprint) {
+            Some(_) => 1 + self.repetitions.get(&fingerprint).copied().unwrap_or_default(),
+            None => 0,
+        }
+    }
+
+    /// Records that the message was learned as `label` and returns the label it
+    /// was learned as before and how many times, if it was learned. A message
+    /// learned as the other label before counts as learned once.
+    pub fn record(&mut self, msg: &str, label: Label) -> Option<(Label, usize)> {
+        let times = self.times_learned(msg);
+        let fingerprint = fingerprint(msg);
+        let previous = self.messages.insert(fingerprint.clone(), label)?;
+        if previous != label {
+            self.repetitions.remove(&fingerprint);
+        }
+        Some((previous, times))
+    }
+
+    /// Records that a message which was learned is learned once more.
+    pub fn repeat(&mut self, msg: &str) {
+        *self.repetitions.entry(fingerprint(msg)).or_default() += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn count(&self, label: Label) -> usize {
+        self.messages.values().filter(|message_label| **message_label == label).count()
+    }
+
+    /// The fingerprints and labels of the learned messages, ordered by fingerprint.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, Label)> {
+        self.messages.iter().map(|(fingerprint, label)| (fingerprint.as_str(), *label))
+    }
+
+    /// Writes a `fingerprint<TAB>label` line per learned message.
+    pub fn write(&self, writer: &mut dyn Write) -> Result<(), io::Error> {
+        for (fingerprint, label) in self.entries() {
+            writeln!(writer, "{}\t{}", fingerprint, label.as_str())?;
+        }
+        Ok(())
+    }
+}
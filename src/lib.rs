@@ -1,3 +1,32 @@
 pub mod utills;
 pub mod cache;
-pub mod bayesian_spam_classifier;
\ No newline at end of file
+pub mod label;
+pub mod random;
+pub mod html;
+pub mod attachments;
+pub mod email;
+pub mod tokenizer;
+pub mod features;
+pub mod header_features;
+pub mod classification;
+pub mod calibration;
+pub mod csv;
+pub mod dataset;
+pub mod mailbox;
+pub mod corpus;
+pub mod dataset_stats;
+pub mod deduplication;
+pub mod ledger;
+pub mod spam_classifier;
+pub mod training;
+pub mod evaluation;
+pub mod curves;
+pub mod cross_validation;
+pub mod tuning;
+pub mod bayesian_spam_classifier;
+pub mod logistic_regression_classifier;
+pub mod linear_svm_classifier;
+pub mod passive_aggressive_classifier;
+pub mod ensemble_classifier;
+pub mod calibrated_classifier;
+pub mod checkpoint;
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use serde::{Deserialize, Serialize};
+use serde_json::from_reader;
+use crate::calibration::PlattScaling;
+use crate::classification::{Classification, Explanation, FeatureContribution};
+use crate::dataset::LabelledMessage;
+use crate::label::Label;
+use crate::random::SeededRandom;
+use crate::spam_classifier::{SpamClassifier, LINEAR_SVM_MODEL_TYPE};
+use crate::utills::SpamClassifierError;
+
+const DEFAULT_LAMBDA : f64 = 0.0001;
+const DEFAULT_EPOCHS : usize = 10;
+const DEFAULT_SEED : u64 = 42;
+const MIN_SCALE : f64 = 1e-9;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinearSvmConfig {
+    pub lambda: f64,
+    pub epochs: usize,
+    pub seed: u64,
+}
+
+impl Default for LinearSvmConfig {
+    fn default() -> Self {
+        LinearSvmConfig {
+            lambda: DEFAULT_LAMBDA,
+            epochs: DEFAULT_EPOCHS,
+            seed: DEFAULT_SEED,
+        }
+    }
+}
+
+/// Linear SVM over binary token features, trained with the Pegasos stochastic
+/// sub-gradient method on the hinge loss. The margin is turned into a spam
+/// probability with Platt scaling fitted at the end of `train_batch`.
+///
+/// The weight vector is stored as `scale * weights` so that the regularisation
+/// shrink of every step does not have to touch every weight.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinearSvmClassifier {
+    config: LinearSvmConfig,
+    weights: HashMap<String, f64>,
+    scale: f64,
+    bias: f64,
+    steps: u64,
+    platt: PlattScaling,
+}
+
+impl Default for LinearSvmClassifier {
+    fn default() -> Self {
+        LinearSvmClassifier::with_config(LinearSvmConfig::default())
+    }
+}
+
+impl LinearSvmClassifier {
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_config(config: LinearSvmConfig) -> Self {
+        LinearSvmClassifier {
+            config,
+            weights: HashMap::new(),
+            scale: 1.0,
+            bias: 0.0,
+            steps: 0,
+            platt: PlattScaling::default(),
+        }
+    }
+
+    pub fn config(&self) -> &LinearSvmConfig {
+        &self.config
+    }
+
+    /// Signed distance of the message from the separating hyperplane (up to the norm
+    /// of the weights). Positive means spam, and the larger the absolute value the
+    /// more confident the model is.
+    pub fn margin(&self, msg: &str) -> f64 {
+        self.margin_of(&self.features(msg))
+    }
+
+    fn features(&self, msg: &str) -> Vec<String> {
+        crate::tokenizer::distinct_tokens(msg)
+    }
+
+    fn margin_of(&self, features: &[String]) -> f64 {
+        let dot: f64 = features.iter().filter_map(|feature| self.weights.get(feature)).sum();
+        self.scale * dot + self.bias
+    }
+
+    fn step(&mut self, features: &[String], label: Label) {
+        self.steps += 1;
+        let target = match label {
+            Label::Ham => -1.0,
+            Label::Spam => 1.0,
+        };
+        let learning_rate = 1.0 / (self.config.lambda * (self.steps + 1) as f64);
+        let violates_margin = target * self.margin_of(features) < 1.0;
+
+        self.scale *= 1.0 - learning_rate * self.config.lambda;
+        if violates_margin {
+            let update = learning_rate * target / self.scale;
+            for feature in features {
+                *self.weights.entry(feature.clone()).or_insert(0.0) += update;
+            }
+            self.bias += learning_rate * target;
+        }
+
+        if self.scale < MIN_SCALE {
+            self.rescale();
+        }
+    }
+
+    fn rescale(&mut self) {
+        let scale = self.scale;
+        for weight in self.weights.values_mut() {
+            *weight *= scale;
+        }
+        self.scale = 1.0;
+    }
+}
+
+impl SpamClassifier for LinearSvmClassifier {
+
+    fn model_type(&self) -> &'static str {
+        LINEAR_SVM_MODEL_TYPE
+    }
+
+    fn train(&mut self, msg: &str, label: Label) {
+        let features = self.features(msg);
+        self.step(&features, label);
+    }
+
+    fn train_batch(&mut self, messages: &[LabelledMessage]) {
+        let examples: Vec<Vec<String>> = messages.iter().map(|message| self.features(&message.text)).collect();
+
+        let mut order: Vec<usize> = (0..examples.len()).collect();
+        let mut random = SeededRandom::new(self.config.seed);
+        for _ in 0..self.config.epochs {
+            random.shuffle(&mut order);
+            for &index in &order {
+                self.step(&examples[index], messages[index].label);
+            }
+        }
+
+        let margins: Vec<f64> = examples.iter().map(|features| self.margin_of(features)).collect();
+        let labels: Vec<Label> = messages.iter().map(|message| message.label).collect();
+        self.platt = PlattScaling::fit(&margins, &labels);
+    }
+
+    fn classify(&self, msg: &str) -> Classification {
+        let spam_probability = self.platt.probability(self.margin(msg));
+        Classification::new(spam_probability, 1.0 - spam_probability)
+    }
+
+    fn explain(&self, msg: &str) -> Explanation {
+        let contributions = self.features(msg)
+            .into_iter()
+            .filter_map(|feature| {
+                let weight = self.scale * *self.weights.get(&feature)?;
+                Some(FeatureContribution { feature, weight })
+            })
+            .collect();
+
+        Explanation::new(self.classify(msg), contributions)
+    }
+
+    fn save(&self, writer: &mut dyn Write) -> Result<(), SpamClassifierError> {
+        crate::spam_classifier::save_tagged(writer, LINEAR_SVM_MODEL_TYPE, self)
+    }
+
+    fn load(reader: &mut dyn Read) -> Result<Self, SpamClassifierError> {
+        from_reader(reader).map_err(SpamClassifierError::Serde)
+    }
+}
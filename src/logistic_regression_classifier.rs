@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use serde::{Deserialize, Serialize};
+use serde_json::from_reader;
+use crate::classification::{Classification, Explanation, FeatureContribution};
+use crate::dataset::LabelledMessage;
+use crate::label::Label;
+use crate::random::SeededRandom;
+use crate::spam_classifier::{SpamClassifier, LOGISTIC_REGRESSION_MODEL_TYPE};
+use crate::utills::SpamClassifierError;
+
+pub const SGD : &str = "sgd";
+pub const ADAGRAD : &str = "adagrad";
+const DEFAULT_LEARNING_RATE : f64 = 0.1;
+const DEFAULT_L2 : f64 = 0.0001;
+const DEFAULT_EPOCHS : usize = 5;
+const DEFAULT_SEED : u64 = 42;
+const ADAGRAD_EPSILON : f64 = 1e-8;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Optimizer {
+    #[default]
+    Sgd,
+    AdaGrad,
+}
+
+impl Optimizer {
+
+    pub fn from_name(name: &str) -> Option<Optimizer> {
+        match name {
+            SGD => Some(Optimizer::Sgd),
+            ADAGRAD => Some(Optimizer::AdaGrad),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogisticRegressionConfig {
+    pub learning_rate: f64,
+    pub l1: f64,
+    pub l2: f64,
+    pub epochs: usize,
+    pub optimizer: Optimizer,
+    pub seed: u64,
+}
+
+impl Default for LogisticRegressionConfig {
+    fn default() -> Self {
+        LogisticRegressionConfig {
+            learning_rate: DEFAULT_LEARNING_RATE,
+            l1: 0.0,
+            l2: DEFAULT_L2,
+            epochs: DEFAULT_EPOCHS,
+            optimizer: Optimizer::Sgd,
+            seed: DEFAULT_SEED,
+        }
+    }
+}
+
+/// Logistic regression over binary token features. `train` makes a single online
+/// step, `train_batch` makes `epochs` shuffled passes over the messages.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LogisticRegressionClassifier {
+    config: LogisticRegressionConfig,
+    weights: HashMap<String, f64>,
+    bias: f64,
+    #[serde(default)]
+    squared_gradients: HashMap<String, f64>,
+    #[serde(default)]
+    bias_squared_gradient: f64,
+}
+
+impl LogisticRegressionClassifier {
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_config(config: LogisticRegressionConfig) -> Self {
+        LogisticRegressionClassifier { config, ..Default::default() }
+    }
+
+    pub fn config(&self) -> &LogisticRegressionConfig {
+        &self.config
+    }
+
+    fn features(&self, msg: &str) -> Vec<String> {
+        crate::tokenizer::distinct_tokens(msg)
+    }
+
+    fn spam_probability(&self, features: &[String]) -> f64 {
+        let margin: f64 = self.bias + features.iter().filter_map(|feature| self.weights.get(feature)).sum::<f64>();
+        sigmoid(margin)
+    }
+
+    fn step(&mut self, features: &[String], label: Label) {
+        let target = match label {
+            Label::Ham => 0.0,
+            Label::Spam => 1.0,
+        };
+        let error = self.spam_probability(features) - target;
+
+        let bias_rate = self.learning_rate_for(None, error);
+        self.bias -= bias_rate * error;
+
+        for feature in features {
+            let weight = self.weights.get(feature).copied().unwrap_or(0.0);
+            let gradient = error + self.config.l2 * weight;
+            let rate = self.learning_rate_for(Some(feature), gradient);
+
+            let mut updated = weight - rate * gradient;
+            if self.config.l1 > 0.0 {
+                updated = updated.signum() * (updated.abs() - rate * self.config.l1).max(0.0);
+            }
+
+            if updated == 0.0 {
+                self.weights.remove(feature);
+            } else {
+                self.weights.insert(feature.clone(), updated);
+            }
+        }
+    }
+
+    /// Returns the step size for a feature (or the bias when `feature` is `None`),
+    /// updating the AdaGrad accumulators with `gradient`.
+    fn learning_rate_for(&mut self, feature: Option<&String>, gradient: f64) -> f64 {
+        match self.config.optimizer {
+            Optimizer::Sgd => self.config.learning_rate,
+            Optimizer::AdaGrad => {
+                let accumulator = match feature {
+                    Some(feature) => self.squared_gradients.entry(feature.clone()).or_insert(0.0),
+                    None => &mut self.bias_squared_gradient,
+                };
+                *accumulator += gradient * gradient;
+                self.config.learning_rate / (accumulator.sqrt() + ADAGRAD_EPSILON)
+            }
+        }
+    }
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+impl SpamClassifier for LogisticRegressionClassifier {
+
+    fn model_type(&self) -> &'static str {
+        LOGISTIC_REGRESSION_MODEL_TYPE
+    }
+
+    fn train(&mut self, msg: &str, label: Label) {
+        let features = self.features(msg);
+        self.step(&features, label);
+    }
+
+    fn train_batch(&mut self, messages: &[LabelledMessage]) {
+        let examples: Vec<(Vec<String>, Label)> = messages.iter()
+            .map(|message| (self.features(&message.text), message.label))
+            .collect();
+
+        let mut order: Vec<usize> = (0..examples.len()).collect();
+        let mut random = SeededRandom::new(self.config.seed);
+        for _ in 0..self.config.epochs {
+            random.shuffle(&mut order);
+            for &index in &order {
+                let (features, label) = &examples[index];
+                self.step(features, *label);
+            }
+        }
+    }
+
+    fn classify(&self, msg: &str) -> Classification {
+        let spam_probability = self.spam_probability(&self.features(msg));
+        Classification::new(spam_probability, 1.0 - spam_probability)
+    }
+
+    fn explain(&self, msg: &str) -> Explanation {
+        let contributions = self.features(msg)
+            .into_iter()
+            .filter_map(|feature| {
+                let weight = *self.weights.get(&feature)?;
+                Some(FeatureContribution { feature, weight })
+            })
+            .collect();
+
+        Explanation::new(self.classify(msg), contributions)
+    }
+
+    fn save(&self, writer: &mut dyn Write) -> Result<(), SpamClassifierError> {
+        crate::spam_classifier::save_tagged(writer, LOGISTIC_REGRESSION_MODEL_TYPE, self)
+    }
+
+    fn load(reader: &mut dyn Read) -> Result<Self, SpamClassifierError> {
+        from_reader(reader).map_err(SpamClassifierError::Serde)
+    }
+}
@@ -0,0 +1,181 @@
+use crate::dataset::LabelledMessage;
+use crate::label::Label;
+use crate::utills::SpamClassifierError;
+use std::fs;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+pub const MAILBOX : &str = "mailbox";
+pub const DEFAULT_SPAM_FOLDERS : [&str; 2] = ["Junk", "Spam"];
+pub const DEFAULT_HAM_FOLDERS : [&str; 1] = ["INBOX"];
+const FROM_LINE : &[u8] = b"From ";
+const QUOTED_FROM_LINE : &[u8] = b">From ";
+const MAILDIR_SUBDIRECTORIES : [&str; 2] = ["cur", "new"];
+const MAILDIR_PLUS_PLUS_PREFIX : char = '.';
+const MAILDIR_PLUS_PLUS_INBOX : &str = "INBOX";
+const INDEX_EXTENSION : &str = "msf";
+
+/// Which mail folders hold spam and which hold ham. Folder names are compared
+/// case-insensitively, as IMAP does for `INBOX`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderMapping {
+    pub spam_folders: Vec<String>,
+    pub ham_folders: Vec<String>,
+}
+
+impl Default for FolderMapping {
+    fn default() -> Self {
+        FolderMapping::new(&DEFAULT_SPAM_FOLDERS, &DEFAULT_HAM_FOLDERS)
+    }
+}
+
+impl FolderMapping {
+
+    pub fn new(spam_folders: &[&str], ham_folders: &[&str]) -> Self {
+        FolderMapping {
+            spam_folders: spam_folders.iter().map(|folder| folder.to_string()).collect(),
+            ham_folders: ham_folders.iter().map(|folder| folder.to_string()).collect(),
+        }
+    }
+
+    pub fn label_for(&self, folder: &str) -> Option<Label> {
+        if self.spam_folders.iter().any(|spam_folder| spam_folder.eq_ignore_ascii_case(folder)) {
+            return Some(Label::Spam);
+        }
+
+        if self.ham_folders.iter().any(|ham_folder| ham_folder.eq_ignore_ascii_case(folder)) {
+            return Some(Label::Ham);
+        }
+
+        None
+    }
+}
+
+/// Reads the messages of a mailbox export and labels them by the folder they are in.
+///
+/// `path` may be a single mbox file, a single Maildir, or a directory of folders in
+/// either format, as exported by Thunderbird or stored by Dovecot. mbox folders are
+/// named after their file and Maildir folders after their directory, without the
+/// leading dot of Maildir++. The messages directly in a Maildir++ root belong to
+/// `INBOX`. Folders which map to neither label are skipped.
+pub fn read_mailbox(path: &Path, mapping: &FolderMapping) -> Result<Vec<LabelledMessage>, SpamClassifierError> {
+    let mut messages = Vec::new();
+    if path.is_file() {
+        add_folder(&mut messages, &folder_name(path), read_mbox(File::open(path).map_err(SpamClassifierError::IO)?)?, mapping);
+        return Ok(messages);
+    }
+
+    let mut entries = fs::read_dir(path).map_err(SpamClassifierError::IO)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(SpamClassifierError::IO)?;
+    entries.sort();
+
+    if is_maildir(path) {
+        let is_maildir_plus_plus = entries.iter().any(|entry| is_maildir(entry) && folder_file_name(entry).starts_with(MAILDIR_PLUS_PLUS_PREFIX));
+        let name = if is_maildir_plus_plus { MAILDIR_PLUS_PLUS_INBOX.to_string() } else { folder_name(path) };
+        add_folder(&mut messages, &name, read_maildir(path)?, mapping);
+    }
+
+    for entry in entries {
+        let is_index = entry.extension().is_some_and(|extension| extension == INDEX_EXTENSION);
+        if entry.is_file() && !is_index && !is_maildir(path) {
+            add_folder(&mut messages, &folder_name(&entry), read_mbox(File::open(&entry).map_err(SpamClassifierError::IO)?)?, mapping);
+        } else if is_maildir(&entry) {
+            add_folder(&mut messages, &folder_name(&entry), read_maildir(&entry)?, mapping);
+        }
+    }
+
+    Ok(messages)
+}
+
+fn add_folder(messages: &mut Vec<LabelledMessage>, folder: &str, folder_messages: Vec<String>, mapping: &FolderMapping) {
+    if let Some(label) = mapping.label_for(folder) {
+        messages.extend(folder_messages.iter().map(|message| LabelledMessage::new(label, message)));
+    }
+}
+
+fn folder_file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn folder_name(path: &Path) -> String {
+    let name = if path.is_file() {
+        path.file_stem().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+    } else {
+        folder_file_name(path)
+    };
+
+    name.trim_start_matches(MAILDIR_PLUS_PLUS_PREFIX).to_string()
+}
+
+pub fn is_maildir(path: &Path) -> bool {
+    MAILDIR_SUBDIRECTORIES.iter().all(|subdirectory| path.join(subdirectory).is_dir())
+}
+
+/// Splits an mbox file into its messages. Every message starts with a `From `
+/// line at the beginning of the file or after an empty line, and `>From ` lines
+/// quoted by mboxrd writers are unquoted. Bytes which are not UTF-8 are replaced.
+pub fn read_mbox(mbox: impl Read) -> Result<Vec<String>, SpamClassifierError> {
+    let mut reader = BufReader::new(mbox);
+    let mut messages = Vec::new();
+    let mut message: Option<Vec<u8>> = None;
+    let mut previous_line_empty = true;
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).map_err(SpamClassifierError::IO)? == 0 {
+            break;
+        }
+
+        if previous_line_empty && line.starts_with(FROM_LINE) {
+            if let Some(finished) = message.take() {
+                messages.push(finish_mbox_message(finished));
+            }
+            message = Some(Vec::new());
+            previous_line_empty = false;
+            continue;
+        }
+
+        previous_line_empty = line == b"\n" || line == b"\r\n";
+        if let Some(message) = message.as_mut() {
+            let quotes = line.iter().take_while(|&&byte| byte == b'>').count();
+            let is_quoted_from = quotes > 0 && line[quotes - 1..].starts_with(QUOTED_FROM_LINE);
+            message.extend_from_slice(if is_quoted_from { &line[1..] } else { &line });
+        }
+    }
+
+    if let Some(finished) = message {
+        messages.push(finish_mbox_message(finished));
+    }
+
+    Ok(messages)
+}
+
+/// Drops the empty line which separates a message from the next `From ` line.
+fn finish_mbox_message(message: Vec<u8>) -> String {
+    let text = String::from_utf8_lossy(&message);
+    let without_separator = text.strip_suffix("\r\n").or_else(|| text.strip_suffix('\n')).unwrap_or(&text);
+    without_separator.to_string()
+}
+
+/// Reads every message of a Maildir, delivered (`cur`) or not (`new`). Bytes
+/// which are not UTF-8 are replaced.
+pub fn read_maildir(path: &Path) -> Result<Vec<String>, SpamClassifierError> {
+    let mut files = Vec::new();
+    for subdirectory in MAILDIR_SUBDIRECTORIES.iter() {
+        for entry in fs::read_dir(path.join(subdirectory)).map_err(SpamClassifierError::IO)? {
+            let file = entry.map_err(SpamClassifierError::IO)?.path();
+            if file.is_file() {
+                files.push(file);
+            }
+        }
+    }
+    files.sort();
+
+    files.iter()
+        .map(|file| fs::read(file).map(|bytes| String::from_utf8_lossy(&bytes).to_string()).map_err(SpamClassifierError::IO))
+        .collect()
+}
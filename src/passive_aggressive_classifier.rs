@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use serde::{Deserialize, Serialize};
+use serde_json::from_reader;
+use crate::classification::{Classification, Explanation, FeatureContribution};
+use crate::label::Label;
+use crate::spam_classifier::{SpamClassifier, PASSIVE_AGGRESSIVE_MODEL_TYPE};
+use crate::utills::SpamClassifierError;
+
+pub const PA : &str = "pa";
+pub const PA_I : &str = "pa-1";
+pub const PA_II : &str = "pa-2";
+const DEFAULT_AGGRESSIVENESS : f64 = 0.1;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PassiveAggressiveVariant {
+    /// Unbounded step which fully corrects every margin violation.
+    #[serde(rename = "pa")]
+    Pa,
+    /// Step clipped at the aggressiveness parameter.
+    #[default]
+    #[serde(rename = "pa-1")]
+    PaI,
+    /// Step damped by the aggressiveness parameter.
+    #[serde(rename = "pa-2")]
+    PaII,
+}
+
+impl PassiveAggressiveVariant {
+
+    pub fn from_name(name: &str) -> Option<PassiveAggressiveVariant> {
+        match name {
+            PA => Some(PassiveAggressiveVariant::Pa),
+            PA_I => Some(PassiveAggressiveVariant::PaI),
+            PA_II => Some(PassiveAggressiveVariant::PaII),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassiveAggressiveConfig {
+    pub variant: PassiveAggressiveVariant,
+    pub aggressiveness: f64,
+}
+
+impl Default for PassiveAggressiveConfig {
+    fn default() -> Self {
+        PassiveAggressiveConfig {
+            variant: PassiveAggressiveVariant::PaI,
+            aggressiveness: DEFAULT_AGGRESSIVENESS,
+        }
+    }
+}
+
+/// Online passive-aggressive learner (Crammer et al., 2006) over binary token
+/// features. Every `train` call touches only the features of the message, so an
+/// update takes time proportional to the message length and not to the model size.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PassiveAggressiveClassifier {
+    config: PassiveAggressiveConfig,
+    weights: HashMap<String, f64>,
+    bias: f64,
+    updates: u64,
+}
+
+impl PassiveAggressiveClassifier {
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_config(config: PassiveAggressiveConfig) -> Self {
+        PassiveAggressiveClassifier { config, ..Default::default() }
+    }
+
+    pub fn config(&self) -> &PassiveAggressiveConfig {
+        &self.config
+    }
+
+    /// Number of `train` calls which changed the model.
+    pub fn updates(&self) -> u64 {
+        self.updates
+    }
+
+    pub fn margin(&self, msg: &str) -> f64 {
+        self.margin_of(&self.features(msg))
+    }
+
+    fn features(&self, msg: &str) -> Vec<String> {
+        crate::tokenizer::distinct_tokens(msg)
+    }
+
+    fn margin_of(&self, features: &[String]) -> f64 {
+        self.bias + features.iter().filter_map(|feature| self.weights.get(feature)).sum::<f64>()
+    }
+
+    fn step_size(&self, loss: f64, squared_norm: f64) -> f64 {
+        let aggressiveness = self.config.aggressiveness;
+        match self.config.variant {
+            PassiveAggressiveVariant::Pa => loss / squared_norm,
+            PassiveAggressiveVariant::PaI => (loss / squared_norm).min(aggressiveness),
+            PassiveAggressiveVariant::PaII => loss / (squared_norm + 1.0 / (2.0 * aggressiveness)),
+        }
+    }
+}
+
+impl SpamClassifier for PassiveAggressiveClassifier {
+
+    fn model_type(&self) -> &'static str {
+        PASSIVE_AGGRESSIVE_MODEL_TYPE
+    }
+
+    fn train(&mut self, msg: &str, label: Label) {
+        let features = self.features(msg);
+        let target = match label {
+            Label::Ham => -1.0,
+            Label::Spam => 1.0,
+        };
+
+        let loss = (1.0 - target * self.margin_of(&features)).max(0.0);
+        if loss == 0.0 {
+            return;
+        }
+
+        // The bias is a feature present in every message.
+        let squared_norm = features.len() as f64 + 1.0;
+        let update = self.step_size(loss, squared_norm) * target;
+        for feature in features {
+            *self.weights.entry(feature).or_insert(0.0) += update;
+        }
+        self.bias += update;
+        self.updates += 1;
+    }
+
+    fn classify(&self, msg: &str) -> Classification {
+        let spam_probability = 1.0 / (1.0 + (-self.margin(msg)).exp());
+        Classification::new(spam_probability, 1.0 - spam_probability)
+    }
+
+    fn explain(&self, msg: &str) -> Explanation {
+        let contributions = self.features(msg)
+            .into_iter()
+            .filter_map(|feature| {
+                let weight = *self.weights.get(&feature)?;
+                Some(FeatureContribution { feature, weight })
+            })
+            .collect();
+
+        Explanation::new(self.classify(msg), contributions)
+    }
+
+    fn save(&self, writer: &mut dyn Write) -> Result<(), SpamClassifierError> {
+        crate::spam_classifier::save_tagged(writer, PASSIVE_AGGRESSIVE_MODEL_TYPE, self)
+    }
+
+    fn load(reader: &mut dyn Read) -> Result<Self, SpamClassifierError> {
+        from_reader(reader).map_err(SpamClassifierError::Serde)
+    }
+}
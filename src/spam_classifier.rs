@@ -0,0 +1,125 @@
+use crate::classification::{Classification, Explanation};
+use crate::dataset::LabelledMessage;
+use crate::label::Label;
+use crate::ledger::Ledger;
+use crate::utills::SpamClassifierError;
+use rayon::ThreadPool;
+use serde::Serialize;
+use serde_json::{from_reader, to_value, to_writer, Value};
+use std::io::{Read, Write};
+
+pub const MODEL_TYPE_FIELD : &str = "model_type";
+pub const BAYESIAN_MODEL_TYPE : &str = "bayesian";
+pub const LOGISTIC_REGRESSION_MODEL_TYPE : &str = "logistic-regression";
+pub const LINEAR_SVM_MODEL_TYPE : &str = "linear-svm";
+pub const PASSIVE_AGGRESSIVE_MODEL_TYPE : &str = "passive-aggressive";
+pub const ENSEMBLE_MODEL_TYPE : &str = "ensemble";
+pub const CALIBRATED_MODEL_TYPE : &str = "calibrated";
+const UNKNOWN_MODEL_TYPE : &str = "The model file contains an unknown model type";
+
+/// A model that can learn messages and tell spam from ham. Model files carry the
+/// `model_type` of the implementation, so `load_model` can restore any of them.
+pub trait SpamClassifier: Send + Sync {
+
+    fn model_type(&self) -> &'static str;
+
+    fn train(&mut self, msg: &str, label: Label);
+
+    fn classify(&self, msg: &str) -> Classification;
+
+    /// Learns the message again while it is misclassified, even if a model with a
+    /// ledger has already learned it as `label`.
+    fn reinforce(&mut self, msg: &str, label: Label) {
+        self.train(msg, label);
+    }
+
+    /// Learns every message of the batch. Models which need several passes over
+    /// their training data override this.
+    fn train_batch(&mut self, messages: &[LabelledMessage]) {
+        for message in messages {
+            self.train(&message.text, message.label);
+        }
+    }
+
+    /// Classifies the message and reports how much each of its features pushed the
+    /// result towards spam (positive weight) or ham (negative weight).
+    fn explain(&self, msg: &str) -> Explanation;
+
+    fn save(&self, writer: &mut dyn Write) -> Result<(), SpamClassifierError>;
+
+    fn load(reader: &mut dyn Read) -> Result<Self, SpamClassifierError> where Self: Sized;
+
+    fn to_bytes(&self) -> Result<Vec<u8>, SpamClassifierError> {
+        let mut bytes = Vec::new();
+        self.save(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SpamClassifierError> where Self: Sized {
+        Self::load(&mut &bytes[..])
+    }
+
+    /// Classifies the messages in parallel. The result has the same order as `messages`.
+    fn classify_batch(&self, messages: &[&str]) -> Vec<Classification> {
+        crate::classification::classify_in_parallel(messages, |message| self.classify(message))
+    }
+
+    /// The messages the model has learned, for models which learn every message
+    /// at most once.
+    fn ledger(&self) -> Option<&Ledger> {
+        None
+    }
+
+    /// Returns whether the current model assigns the message a label different from `label`.
+    fn is_misclassified(&self, msg: &str, label: Label) -> bool {
+        self.classify(msg).label() != label
+    }
+}
+
+/// Writes `model` as JSON, tagged with its model type.
+pub fn save_tagged<T: Serialize>(writer: &mut dyn Write, model_type: &str, model: &T) -> Result<(), SpamClassifierError> {
+    let mut value = to_value(model).map_err(SpamClassifierError::Serde)?;
+    if let Value::Object(fields) = &mut value {
+        fields.insert(MODEL_TYPE_FIELD.to_string(), Value::String(model_type.to_string()));
+    }
+
+    to_writer(writer, &value).map_err(SpamClassifierError::Serde)
+}
+
+/// Runs `classify_batch` on a dedicated pool, for example one with a fixed number
+/// of threads, instead of the global rayon pool.
+pub fn classify_batch_on<C: SpamClassifier + ?Sized>(pool: &ThreadPool, classifier: &C, messages: &[&str]) -> Vec<Classification> {
+    pool.install(|| classifier.classify_batch(messages))
+}
+
+/// Restores a model saved by any `SpamClassifier`. Model files without a
+/// `model_type` are Bayesian models written before the field existed.
+pub fn load_model(reader: &mut dyn Read) -> Result<Box<dyn SpamClassifier>, SpamClassifierError> {
+    let value: Value = from_reader(reader).map_err(SpamClassifierError::Serde)?;
+    model_from_value(value)
+}
+
+pub fn load_model_from_bytes(bytes: &[u8]) -> Result<Box<dyn SpamClassifier>, SpamClassifierError> {
+    load_model(&mut &bytes[..])
+}
+
+pub fn model_from_value(value: Value) -> Result<Box<dyn SpamClassifier>, SpamClassifierError> {
+    let model_type = value.get(MODEL_TYPE_FIELD)
+        .and_then(|model_type| model_type.as_str())
+        .unwrap_or(BAYESIAN_MODEL_TYPE)
+        .to_string();
+
+    match model_type.as_str() {
+        BAYESIAN_MODEL_TYPE => Ok(Box::new(from_value::<crate::bayesian_spam_classifier::BayesianSpamClassifier>(value)?)),
+        LOGISTIC_REGRESSION_MODEL_TYPE => Ok(Box::new(from_value::<crate::logistic_regression_classifier::LogisticRegressionClassifier>(value)?)),
+        LINEAR_SVM_MODEL_TYPE => Ok(Box::new(from_value::<crate::linear_svm_classifier::LinearSvmClassifier>(value)?)),
+        PASSIVE_AGGRESSIVE_MODEL_TYPE => Ok(Box::new(from_value::<crate::passive_aggressive_classifier::PassiveAggressiveClassifier>(value)?)),
+        ENSEMBLE_MODEL_TYPE => Ok(Box::new(crate::ensemble_classifier::EnsembleClassifier::from_value(value)?)),
+        CALIBRATED_MODEL_TYPE => Ok(Box::new(crate::calibrated_classifier::CalibratedClassifier::from_value(value)?)),
+        _ => Err(SpamClassifierError::InvalidModelError(format!("{} - {:?}", UNKNOWN_MODEL_TYPE, model_type))),
+    }
+}
+
+fn from_value<T: serde::de::DeserializeOwned>(value: Value) -> Result<T, SpamClassifierError> {
+    serde_json::from_value(value).map_err(SpamClassifierError::Serde)
+}
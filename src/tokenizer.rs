@@ -0,0 +1,73 @@
+use crate::attachments::attachment_features;
+use crate::email::{looks_like_email, Email};
+use crate::html::{looks_like_html, HtmlDocument};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The words of a message and the features describing its structure, such as
+/// the links and hidden text of its HTML or the types of its attachments.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MessageTokens {
+    pub words: Vec<String>,
+    pub structure: Vec<String>,
+}
+
+/// Splits a message into the words every model is trained on. E-mails are
+/// parsed first, so that only their subject and decoded text parts are split,
+/// and HTML is reduced to its visible text.
+pub fn tokenize(msg: &str) -> Vec<String> {
+    analyze(msg).words
+}
+
+/// Splits a message into its words and its structural features.
+pub fn analyze(msg: &str) -> MessageTokens {
+    if looks_like_email(msg) {
+        return analyze_email(&Email::parse(msg));
+    }
+
+    analyze_text(msg)
+}
+
+/// Splits a message which is not an e-mail, reducing HTML to its visible text.
+pub fn analyze_text(msg: &str) -> MessageTokens {
+    if looks_like_html(msg) {
+        let document = HtmlDocument::parse(msg);
+        return MessageTokens { words: words(&document.text), structure: document.features() };
+    }
+
+    MessageTokens { words: words(msg), structure: Vec::new() }
+}
+
+/// Splits an already parsed e-mail into the words of its subject and text parts
+/// and its structural features. The words of a `multipart/alternative` come from
+/// one version only, while an HTML version still adds its structure.
+pub fn analyze_email(email: &Email) -> MessageTokens {
+    let mut tokens = MessageTokens { words: words(email.subject()), structure: Vec::new() };
+    for part in email.parts.iter().filter(|part| part.is_text()) {
+        if part.is_html() {
+            let document = HtmlDocument::parse(&part.text());
+            if !part.is_alternative_copy {
+                tokens.words.extend(words(&document.text));
+            }
+            tokens.structure.extend(document.features());
+        } else if !part.is_alternative_copy {
+            tokens.words.extend(words(&part.text()));
+        }
+    }
+    tokens.structure.extend(attachment_features(email));
+    tokens
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.unicode_words().map(|word| word.to_string()).collect()
+}
+
+/// Returns the distinct words and structural features of a message in order of
+/// first appearance.
+pub fn distinct_tokens(msg: &str) -> Vec<String> {
+    let tokens = analyze(msg);
+    let mut seen = std::collections::HashSet::new();
+    tokens.words.into_iter()
+        .chain(tokens.structure)
+        .filter(|token| seen.insert(token.clone()))
+        .collect()
+}
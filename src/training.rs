@@ -0,0 +1,153 @@
+use crate::dataset::LabelledMessage;
+use crate::label::Label;
+use crate::spam_classifier::SpamClassifier;
+use crate::utills::SpamClassifierError;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Read;
+
+pub const TRAIN_EVERYTHING : &str = "train-everything";
+pub const TRAIN_ON_ERROR : &str = "train-on-error";
+pub const TRAIN_UNTIL_MATURE : &str = "train-until-mature";
+pub const TRAIN_UNTIL_NO_ERROR : &str = "train-until-no-error";
+pub const DEFAULT_MATURITY : usize = 50;
+pub const DEFAULT_MAX_PASSES : usize = 10;
+
+/// Decides which messages of a batch are actually fed into the model.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TrainingStrategy {
+    /// Learns every message once.
+    #[default]
+    TrainEverything,
+    /// Learns a message only when the current model misclassifies it.
+    TrainOnError,
+    /// Learns everything until `maturity` messages of a label have been learned,
+    /// then only the messages of that label which are misclassified.
+    TrainUntilMature { maturity: usize },
+    /// Repeats train-on-error passes until a pass learns nothing or `max_passes` is
+    /// reached. Messages which are still misclassified are learned again, even
+    /// those the ledger of the model already has.
+    TrainUntilNoError { max_passes: usize },
+}
+
+impl TrainingStrategy {
+
+    pub fn from_name(name: &str, maturity: usize, max_passes: usize) -> Option<TrainingStrategy> {
+        match name {
+            TRAIN_EVERYTHING => Some(TrainingStrategy::TrainEverything),
+            TRAIN_ON_ERROR => Some(TrainingStrategy::TrainOnError),
+            TRAIN_UNTIL_MATURE => Some(TrainingStrategy::TrainUntilMature { maturity }),
+            TRAIN_UNTIL_NO_ERROR => Some(TrainingStrategy::TrainUntilNoError { max_passes }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TrainingReport {
+    pub messages_seen: usize,
+    pub messages_learned: usize,
+    pub ham_learned: usize,
+    pub spam_learned: usize,
+    pub passes: usize,
+}
+
+impl TrainingReport {
+
+    fn record_learned(&mut self, label: Label) {
+        self.messages_learned += 1;
+        match label {
+            Label::Ham => self.ham_learned += 1,
+            Label::Spam => self.spam_learned += 1,
+        }
+    }
+
+    fn learned_of(&self, label: Label) -> usize {
+        match label {
+            Label::Ham => self.ham_learned,
+            Label::Spam => self.spam_learned,
+        }
+    }
+}
+
+pub fn train_messages<C: SpamClassifier + ?Sized>(classifier: &mut C, messages: &[LabelledMessage], strategy: TrainingStrategy) -> TrainingReport {
+    let mut report = TrainingReport { messages_seen: messages.len(), ..Default::default() };
+
+    match strategy {
+        TrainingStrategy::TrainEverything => {
+            let unlearned = unlearned_messages(classifier, messages);
+            classifier.train_batch(&unlearned);
+            report.passes = 1;
+            for message in unlearned.iter() {
+                report.record_learned(message.label);
+            }
+        }
+        TrainingStrategy::TrainOnError => {
+            train_pass(classifier, messages, &mut report, false, |classifier, message, _| classifier.is_misclassified(&message.text, message.label));
+        }
+        TrainingStrategy::TrainUntilMature { maturity } => {
+            train_pass(classifier, messages, &mut report, false, |classifier, message, report| {
+                report.learned_of(message.label) < maturity || classifier.is_misclassified(&message.text, message.label)
+            });
+        }
+        TrainingStrategy::TrainUntilNoError { max_passes } => {
+            while report.passes < max_passes {
+                let learned_before = report.messages_learned;
+                train_pass(classifier, messages, &mut report, true, |classifier, message, _| classifier.is_misclassified(&message.text, message.label));
+                if report.messages_learned == learned_before {
+                    break;
+                }
+            }
+        }
+    }
+
+    report
+}
+
+/// Trains `classifier` on a `label<TAB>text` dataset with the given strategy.
+pub fn train_from_dataset<C: SpamClassifier + ?Sized>(classifier: &mut C, dataset: impl Read, strategy: TrainingStrategy) -> Result<TrainingReport, SpamClassifierError> {
+    let messages = crate::dataset::read_labelled_messages(dataset)?;
+    Ok(train_messages(classifier, &messages, strategy))
+}
+
+/// The messages the classifier would actually learn: all of them, unless it keeps
+/// a ledger, in which case the ones already learned with the same label, in the
+/// ledger or earlier in the batch, are left out.
+fn unlearned_messages<'a, C: SpamClassifier + ?Sized>(classifier: &C, messages: &'a [LabelledMessage]) -> Cow<'a, [LabelledMessage]> {
+    let ledger = match classifier.ledger() {
+        Some(ledger) => ledger,
+        None => return Cow::Borrowed(messages),
+    };
+
+    let mut batch_labels: HashMap<&str, Label> = HashMap::new();
+    messages.iter()
+        .filter(|message| {
+            let previous = batch_labels.insert(&message.text, message.label).or_else(|| ledger.label_of(&message.text));
+            previous != Some(message.label)
+        })
+        .cloned()
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Learns the messages `should_learn` picks. Messages the ledger has with the
+/// same label are skipped, unless `reinforce` asks for learning them again.
+fn train_pass<C, F>(classifier: &mut C, messages: &[LabelledMessage], report: &mut TrainingReport, reinforce: bool, should_learn: F)
+    where C: SpamClassifier + ?Sized, F: Fn(&C, &LabelledMessage, &TrainingReport) -> bool {
+    report.passes += 1;
+
+    for message in messages {
+        let is_learned = !reinforce && classifier.ledger().is_some_and(|ledger| ledger.label_of(&message.text) == Some(message.label));
+        if is_learned || !should_learn(classifier, message, report) {
+            continue;
+        }
+
+        if reinforce {
+            classifier.reinforce(&message.text, message.label);
+        } else {
+            classifier.train(&message.text, message.label);
+        }
+        report.record_learned(message.label);
+    }
+}
@@ -0,0 +1,403 @@
+use crate::bayesian_spam_classifier::BayesianSpamClassifier;
+use crate::cross_validation::{validate, MetricSummary, Validation, ValidationReport};
+use crate::dataset::LabelledMessage;
+use crate::evaluation::Cutoffs;
+use crate::features::{FeatureExtractor, DEFAULT_WINDOW, OSB, UNIGRAM};
+use crate::header_features::{HeaderFeatures, DEFAULT_HEADERS};
+use crate::linear_svm_classifier::{LinearSvmClassifier, LinearSvmConfig};
+use crate::logistic_regression_classifier::{LogisticRegressionClassifier, LogisticRegressionConfig, Optimizer};
+use crate::passive_aggressive_classifier::{PassiveAggressiveClassifier, PassiveAggressiveConfig, PassiveAggressiveVariant};
+use crate::random::SeededRandom;
+use crate::spam_classifier::{SpamClassifier, BAYESIAN_MODEL_TYPE, LINEAR_SVM_MODEL_TYPE, LOGISTIC_REGRESSION_MODEL_TYPE, PASSIVE_AGGRESSIVE_MODEL_TYPE};
+use crate::training::{TrainingStrategy, DEFAULT_MATURITY, DEFAULT_MAX_PASSES, TRAIN_EVERYTHING};
+use crate::utills::SpamClassifierError;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+pub const GRID : &str = "grid";
+pub const RANDOM : &str = "random";
+pub const DEFAULT_TRIALS : usize = 20;
+pub const DEFAULT_FOLDS : usize = 5;
+
+pub const MODEL_TYPE : &str = "model-type";
+pub const FEATURES : &str = "features";
+pub const FEATURE_WINDOW : &str = "feature-window";
+pub const TRAINING_STRATEGY : &str = "training-strategy";
+pub const MATURITY : &str = "maturity";
+pub const MAX_PASSES : &str = "max-passes";
+pub const LEARNING_RATE : &str = "learning-rate";
+pub const L1 : &str = "l1";
+pub const L2 : &str = "l2";
+pub const EPOCHS : &str = "epochs";
+pub const OPTIMIZER : &str = "optimizer";
+pub const LAMBDA : &str = "lambda";
+pub const PA_VARIANT : &str = "pa-variant";
+pub const AGGRESSIVENESS : &str = "aggressiveness";
+pub const SEED : &str = "seed";
+pub const HAM_CUTOFF : &str = "ham-cutoff";
+pub const SPAM_CUTOFF : &str = "spam-cutoff";
+/// The hyperparameters which can be set by name, named as the options of
+/// `create-model-from-dataset`.
+pub const TUNABLE_PARAMETERS : [&str; 17] = [
+    MODEL_TYPE, FEATURES, FEATURE_WINDOW, TRAINING_STRATEGY, MATURITY, MAX_PASSES, LEARNING_RATE, L1, L2,
+    EPOCHS, OPTIMIZER, LAMBDA, PA_VARIANT, AGGRESSIVENESS, SEED, HAM_CUTOFF, SPAM_CUTOFF,
+];
+
+pub const ACCURACY : &str = "accuracy";
+pub const PRECISION : &str = "precision";
+pub const RECALL : &str = "recall";
+pub const F1 : &str = "f1";
+pub const FALSE_POSITIVE_RATE : &str = "false-positive-rate";
+pub const UNSURE_RATE : &str = "unsure-rate";
+pub const ROC_AUC : &str = "roc-auc";
+pub const PR_AUC : &str = "pr-auc";
+pub const EXPECTED_CALIBRATION_ERROR : &str = "expected-calibration-error";
+
+/// Everything which decides how a new model is built and trained, and where its
+/// cutoffs are. The model type picks which of the configurations is used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hyperparameters {
+    pub model_type: String,
+    pub features: String,
+    pub feature_window: usize,
+    pub header_features: HeaderFeatures,
+    pub training_strategy: String,
+    pub maturity: usize,
+    pub max_passes: usize,
+    pub logistic_regression: LogisticRegressionConfig,
+    pub linear_svm: LinearSvmConfig,
+    pub passive_aggressive: PassiveAggressiveConfig,
+    pub cutoffs: Cutoffs,
+}
+
+impl Default for Hyperparameters {
+    fn default() -> Self {
+        Hyperparameters {
+            model_type: BAYESIAN_MODEL_TYPE.to_string(),
+            features: UNIGRAM.to_string(),
+            feature_window: DEFAULT_WINDOW,
+            header_features: HeaderFeatures::from_list(DEFAULT_HEADERS),
+            training_strategy: TRAIN_EVERYTHING.to_string(),
+            maturity: DEFAULT_MATURITY,
+            max_passes: DEFAULT_MAX_PASSES,
+            logistic_regression: LogisticRegressionConfig::default(),
+            linear_svm: LinearSvmConfig::default(),
+            passive_aggressive: PassiveAggressiveConfig::default(),
+            cutoffs: Cutoffs::default(),
+        }
+    }
+}
+
+impl Hyperparameters {
+
+    /// Sets one of the `TUNABLE_PARAMETERS` from its textual value. `epochs` and
+    /// `seed` are shared by the logistic regression and the linear SVM. A feature
+    /// window is only checked against the widest features, `check` tells whether
+    /// it suits the features once both are set.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), SpamClassifierError> {
+        match name {
+            MODEL_TYPE => {
+                let model_types = [BAYESIAN_MODEL_TYPE, LOGISTIC_REGRESSION_MODEL_TYPE, LINEAR_SVM_MODEL_TYPE, PASSIVE_AGGRESSIVE_MODEL_TYPE];
+                if !model_types.contains(&value) {
+                    return Err(invalid_value(name, value));
+                }
+                self.model_type = value.to_string();
+            }
+            FEATURES => {
+                FeatureExtractor::from_name(value, DEFAULT_WINDOW)?;
+                self.features = value.to_string();
+            }
+            FEATURE_WINDOW => {
+                let window = parse(name, value)?;
+                FeatureExtractor::from_name(OSB, window)?;
+                self.feature_window = window;
+            }
+            TRAINING_STRATEGY => {
+                if TrainingStrategy::from_name(value, self.maturity, self.max_passes).is_none() {
+                    return Err(invalid_value(name, value));
+                }
+                self.training_strategy = value.to_string();
+            }
+            MATURITY => self.maturity = parse(name, value)?,
+            MAX_PASSES => self.max_passes = parse(name, value)?,
+            LEARNING_RATE => self.logistic_regression.learning_rate = parse(name, value)?,
+            L1 => self.logistic_regression.l1 = parse(name, value)?,
+            L2 => self.logistic_regression.l2 = parse(name, value)?,
+            EPOCHS => {
+                self.logistic_regression.epochs = parse(name, value)?;
+                self.linear_svm.epochs = self.logistic_regression.epochs;
+            }
+            OPTIMIZER => self.logistic_regression.optimizer = Optimizer::from_name(value).ok_or_else(|| invalid_value(name, value))?,
+            LAMBDA => self.linear_svm.lambda = parse(name, value)?,
+            PA_VARIANT => self.passive_aggressive.variant = PassiveAggressiveVariant::from_name(value).ok_or_else(|| invalid_value(name, value))?,
+            AGGRESSIVENESS => self.passive_aggressive.aggressiveness = parse(name, value)?,
+            SEED => {
+                self.logistic_regression.seed = parse(name, value)?;
+                self.linear_svm.seed = self.logistic_regression.seed;
+            }
+            HAM_CUTOFF => self.cutoffs.ham_cutoff = parse(name, value)?,
+            SPAM_CUTOFF => self.cutoffs.spam_cutoff = parse(name, value)?,
+            _ => return Err(SpamClassifierError::InvalidParameter(format!("{:?} is not a tunable hyperparameter", name))),
+        }
+        Ok(())
+    }
+
+    /// Checks the values which depend on each other, which `set` cannot do one
+    /// value at a time.
+    pub fn check(&self) -> Result<(), SpamClassifierError> {
+        FeatureExtractor::from_name(&self.features, self.feature_window).map(|_| ())
+    }
+
+    /// A copy of these hyperparameters with the values of the configuration set.
+    pub fn with_configuration(&self, configuration: &Configuration) -> Result<Hyperparameters, SpamClassifierError> {
+        let mut hyperparameters = self.clone();
+        for (name, value) in configuration {
+            hyperparameters.set(name, value)?;
+        }
+        hyperparameters.check()?;
+        Ok(hyperparameters)
+    }
+
+    pub fn feature_extractor(&self) -> FeatureExtractor {
+        FeatureExtractor::from_name(&self.features, self.feature_window).unwrap_or_default()
+    }
+
+    pub fn strategy(&self) -> TrainingStrategy {
+        TrainingStrategy::from_name(&self.training_strategy, self.maturity, self.max_passes).unwrap_or_default()
+    }
+
+    /// A new, untrained model of the model type.
+    pub fn new_classifier(&self) -> Box<dyn SpamClassifier> {
+        match self.model_type.as_str() {
+            LOGISTIC_REGRESSION_MODEL_TYPE => Box::new(LogisticRegressionClassifier::with_config(self.logistic_regression.clone())),
+            LINEAR_SVM_MODEL_TYPE => Box::new(LinearSvmClassifier::with_config(self.linear_svm.clone())),
+            PASSIVE_AGGRESSIVE_MODEL_TYPE => Box::new(PassiveAggressiveClassifier::with_config(self.passive_aggressive.clone())),
+            _ => {
+                let mut classifier = BayesianSpamClassifier::with_features(self.feature_extractor());
+                classifier.set_header_features(self.header_features.clone());
+                Box::new(classifier)
+            }
+        }
+    }
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, SpamClassifierError> {
+    value.parse().map_err(|_| invalid_value(name, value))
+}
+
+fn invalid_value(name: &str, value: &str) -> SpamClassifierError {
+    SpamClassifierError::InvalidParameter(format!("{:?} is not a valid value of {}", value, name))
+}
+
+/// The values of the searched hyperparameters for one trial, by name.
+pub type Configuration = BTreeMap<String, String>;
+
+/// A hyperparameter and the values the search tries for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchDimension {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+impl SearchDimension {
+
+    /// Parses `name=value,value,...`, checking every value against the hyperparameter.
+    pub fn parse(specification: &str) -> Result<SearchDimension, SpamClassifierError> {
+        let (name, values) = match specification.split_once('=') {
+            Some((name, values)) => (name.trim(), values),
+            None => return Err(SpamClassifierError::InvalidParameter(format!("{:?} is not of the form name=value,value", specification))),
+        };
+
+        let values: Vec<String> = values.split(',').map(|value| value.trim().to_string()).filter(|value| !value.is_empty()).collect();
+        if values.is_empty() {
+            return Err(SpamClassifierError::InvalidParameter(format!("No values to search for {}", name)));
+        }
+        for value in &values {
+            Hyperparameters::default().set(name, value)?;
+        }
+
+        Ok(SearchDimension { name: name.to_string(), values })
+    }
+}
+
+/// How the configurations to try are picked from the search dimensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Search {
+    /// Every combination of the values.
+    Grid,
+    /// `trials` distinct combinations drawn at random, or every combination when
+    /// there are not that many.
+    Random { trials: usize, seed: u64 },
+}
+
+impl Search {
+
+    pub fn from_name(name: &str, trials: usize, seed: u64) -> Option<Search> {
+        match name {
+            GRID => Some(Search::Grid),
+            RANDOM => Some(Search::Random { trials, seed }),
+            _ => None,
+        }
+    }
+
+    /// The configurations to try. A hyperparameter may only be searched once.
+    pub fn configurations(&self, dimensions: &[SearchDimension]) -> Result<Vec<Configuration>, SpamClassifierError> {
+        let mut names = HashSet::new();
+        if let Some(duplicate) = dimensions.iter().find(|dimension| !names.insert(dimension.name.as_str())) {
+            return Err(SpamClassifierError::InvalidParameter(format!("{} is searched more than once", duplicate.name)));
+        }
+
+        let combinations = dimensions.iter().fold(1usize, |count, dimension| count.saturating_mul(dimension.values.len()));
+        let choices: Vec<Vec<usize>> = match *self {
+            Search::Random { trials, seed } if trials < combinations => {
+                let mut random = SeededRandom::new(seed);
+                let mut seen = HashSet::new();
+                let mut choices = Vec::with_capacity(trials);
+                while choices.len() < trials {
+                    let choice: Vec<usize> = dimensions.iter().map(|dimension| random.next_below(dimension.values.len())).collect();
+                    if seen.insert(choice.clone()) {
+                        choices.push(choice);
+                    }
+                }
+                choices
+            }
+            _ => (0..combinations)
+                .map(|mut combination| {
+                    let mut choice = vec![0; dimensions.len()];
+                    for (position, dimension) in dimensions.iter().enumerate().rev() {
+                        choice[position] = combination % dimension.values.len();
+                        combination /= dimension.values.len();
+                    }
+                    choice
+                })
+                .collect(),
+        };
+
+        Ok(choices.into_iter()
+            .map(|choice| dimensions.iter().zip(choice).map(|(dimension, value)| (dimension.name.clone(), dimension.values[value].clone())).collect())
+            .collect())
+    }
+}
+
+/// The validation metric configurations are ranked by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Accuracy,
+    Precision,
+    Recall,
+    F1,
+    FalsePositiveRate,
+    UnsureRate,
+    RocAuc,
+    PrAuc,
+    ExpectedCalibrationError,
+}
+
+impl Metric {
+
+    pub fn from_name(name: &str) -> Option<Metric> {
+        match name {
+            ACCURACY => Some(Metric::Accuracy),
+            PRECISION => Some(Metric::Precision),
+            RECALL => Some(Metric::Recall),
+            F1 => Some(Metric::F1),
+            FALSE_POSITIVE_RATE => Some(Metric::FalsePositiveRate),
+            UNSURE_RATE => Some(Metric::UnsureRate),
+            ROC_AUC => Some(Metric::RocAuc),
+            PR_AUC => Some(Metric::PrAuc),
+            EXPECTED_CALIBRATION_ERROR => Some(Metric::ExpectedCalibrationError),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metric::Accuracy => ACCURACY,
+            Metric::Precision => PRECISION,
+            Metric::Recall => RECALL,
+            Metric::F1 => F1,
+            Metric::FalsePositiveRate => FALSE_POSITIVE_RATE,
+            Metric::UnsureRate => UNSURE_RATE,
+            Metric::RocAuc => ROC_AUC,
+            Metric::PrAuc => PR_AUC,
+            Metric::ExpectedCalibrationError => EXPECTED_CALIBRATION_ERROR,
+        }
+    }
+
+    /// Whether a configuration with a lower value of the metric is the better one.
+    pub fn is_lower_better(&self) -> bool {
+        matches!(self, Metric::FalsePositiveRate | Metric::UnsureRate | Metric::ExpectedCalibrationError)
+    }
+
+    pub fn summary(&self, report: &ValidationReport) -> MetricSummary {
+        match self {
+            Metric::Accuracy => report.accuracy,
+            Metric::Precision => report.precision,
+            Metric::Recall => report.recall,
+            Metric::F1 => report.f1,
+            Metric::FalsePositiveRate => report.false_positive_rate,
+            Metric::UnsureRate => report.unsure_rate,
+            Metric::RocAuc => report.roc_auc,
+            Metric::PrAuc => report.pr_auc,
+            Metric::ExpectedCalibrationError => report.expected_calibration_error,
+        }
+    }
+}
+
+/// A configuration which was validated, with its value of the ranking metric.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Trial {
+    pub metric: &'static str,
+    pub score: MetricSummary,
+    pub configuration: Configuration,
+    #[serde(skip)]
+    pub report: ValidationReport,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TuningReport {
+    /// The trials, best first. Trials which score the same keep the search order.
+    pub trials: Vec<Trial>,
+    /// The configurations which were not tried because their ham cutoff is above
+    /// their spam cutoff.
+    pub skipped: Vec<Configuration>,
+}
+
+impl TuningReport {
+
+    pub fn best(&self) -> Option<&Trial> {
+        self.trials.first()
+    }
+}
+
+/// Validates a model for every configuration, on top of the `base` hyperparameters,
+/// and ranks them by `metric`. Every configuration is checked before the first
+/// trial runs. `on_trial` is called as each trial finishes.
+pub fn tune<F>(messages: &[LabelledMessage], base: &Hyperparameters, configurations: Vec<Configuration>, validation: Validation, metric: Metric, mut on_trial: F) -> Result<TuningReport, SpamClassifierError>
+    where F: FnMut(&Trial) {
+    let hyperparameters = configurations.iter()
+        .map(|configuration| base.with_configuration(configuration))
+        .collect::<Result<Vec<Hyperparameters>, SpamClassifierError>>()?;
+
+    let mut trials = Vec::with_capacity(configurations.len());
+    let mut skipped = Vec::new();
+    for (configuration, hyperparameters) in configurations.into_iter().zip(hyperparameters) {
+        if hyperparameters.cutoffs.ham_cutoff > hyperparameters.cutoffs.spam_cutoff {
+            skipped.push(configuration);
+            continue;
+        }
+
+        let report = validate(messages, validation, hyperparameters.strategy(), hyperparameters.cutoffs, || hyperparameters.new_classifier())?;
+        let trial = Trial { metric: metric.as_str(), score: metric.summary(&report), configuration, report };
+        on_trial(&trial);
+        trials.push(trial);
+    }
+
+    trials.sort_by(|first, second| {
+        let ordering = second.score.mean.partial_cmp(&first.score.mean).unwrap_or(std::cmp::Ordering::Equal);
+        if metric.is_lower_better() { ordering.reverse() } else { ordering }
+    });
+    Ok(TuningReport { trials, skipped })
+}
+
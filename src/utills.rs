@@ -1,8 +1,11 @@
 #[derive(Debug)]
 pub enum SpamClassifierError {
     InvalidDatasetFormatError(String),
+    InvalidModelError(String),
     Serde(serde_json::Error),
     IO(std::io::Error),
+    ThreadPool(rayon::ThreadPoolBuildError),
+    InvalidParameter(String),
 }
 
 fn skip_next(input: &str, target: char) -> Option<&str> {
@@ -10,22 +13,22 @@ fn skip_next(input: &str, target: char) -> Option<&str> {
       return None
    }
 
-   let first_letter = input.chars().nth(0).unwrap();
+   let first_letter = input.chars().next().unwrap();
    if first_letter == target {
-       return Some(&input[1..]);
+       return Some(&input[first_letter.len_utf8()..]);
    } 
    
-   return None;
+   None
 }
 
 fn take_until(input: &str, target: char) -> (&str, &str) {
-   for (i, c) in input.chars().enumerate() {
+   for (i, c) in input.char_indices() {
        if c == target {
            return input.split_at(i);
        } 
    }
 
-   return (input, "");
+   (input, "")
 }
 
 pub fn take_and_skip(input: &str, target: char) -> Option<(&str, &str)> {
@@ -34,7 +37,7 @@ pub fn take_and_skip(input: &str, target: char) -> Option<(&str, &str)> {
        return None
    }
    
-   let second_without_target = skip_next(&second, target).unwrap();
+   let second_without_target = skip_next(second, target).unwrap();
    
-   return Some((first, second_without_target))
-}
\ No newline at end of file
+   Some((first, second_without_target))
+}
@@ -0,0 +1,87 @@
+use spamclassifier::attachments::{list_zip_entries, sniff_content_type, Attachment};
+use spamclassifier::email::{decode_base64, Email};
+use spamclassifier::tokenizer::analyze;
+
+const ZIP_WITH_SCRIPT : &str = "UEsDBBQAAAAAAJAkU13Hk5lQCAAAAAgAAAAQAAAAc2Nhbl8wMDQyLnBkZi5qc3ZhciB4PTE7UEsDBBQAAAAAAJAkU12GphA2BQAAAAUAAAAKAAAAcmVhZG1lLlRYVGhlbGxvUEsBAhQDFAAAAAAAkCRTXceTmVAIAAAACAAAABAAAAAAAAAAAAAAAIABAAAAAHNjYW5fMDA0Mi5wZGYuanNQSwECFAMUAAAAAACQJFNdhqYQNgUAAAAFAAAACgAAAAAAAAAAAAAAgAE2AAAAcmVhZG1lLlRYVFBLBQYAAAAAAgACAHYAAABjAAAAAAA=";
+
+fn malware_email() -> String {
+    format!("From: billing@example.com\n\
+             Subject: Invoice\n\
+             Content-Type: multipart/mixed; boundary=b\n\
+             \n\
+             --b\n\
+             Content-Type: text/plain\n\
+             \n\
+             See attached.\n\
+             --b\n\
+             Content-Type: application/pdf\n\
+             Content-Disposition: attachment; filename=\"invoice.pdf.exe\"\n\
+             Content-Transfer-Encoding: base64\n\
+             \n\
+             TVqQAAMAAAAEAAAA\n\
+             --b\n\
+             Content-Type: application/zip\n\
+             Content-Disposition: attachment; filename=\"scans.zip\"\n\
+             Content-Transfer-Encoding: base64\n\
+             \n\
+             {}\n\
+             --b--\n", ZIP_WITH_SCRIPT)
+}
+
+#[test]
+fn test_zip_entries_are_listed() {
+    let entries = list_zip_entries(&decode_base64(ZIP_WITH_SCRIPT));
+
+    let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+    assert_eq!(names, vec!["scan_0042.pdf.js", "readme.TXT"]);
+    assert!(entries.iter().all(|entry| !entry.is_encrypted));
+    assert!(list_zip_entries(b"PK\x03\x04 truncated").is_empty());
+}
+
+#[test]
+fn test_disguised_executable_is_detected() {
+    let email = Email::parse(&malware_email());
+    let executable = Attachment::from_part(email.attachments().next().unwrap());
+
+    assert_eq!(sniff_content_type(b"MZ\x90\x00"), Some("application/x-msdownload"));
+    assert_eq!(executable.extensions(), vec!["pdf", "exe"]);
+    assert!(executable.has_type_mismatch());
+    assert!(executable.has_double_extension());
+}
+
+#[test]
+fn test_macro_enabled_document_is_zip_based() {
+    let docm = format!("From: hr@example.com\n\
+                        Subject: Rota\n\
+                        Content-Type: application/vnd.ms-word.document.macroEnabled.12\n\
+                        Content-Disposition: attachment; filename=\"rota.docm\"\n\
+                        Content-Transfer-Encoding: base64\n\
+                        \n\
+                        {}\n", ZIP_WITH_SCRIPT);
+    let email = Email::parse(&docm);
+    let document = Attachment::from_part(email.attachments().next().unwrap());
+
+    assert_eq!(document.sniffed_type, Some("application/zip"));
+    assert!(!document.has_type_mismatch());
+    assert!(!analyze(&docm).structure.contains(&"attachment:type-mismatch".to_string()));
+}
+
+#[test]
+fn test_attachment_features() {
+    let structure = analyze(&malware_email()).structure;
+
+    assert_eq!(structure, vec![
+        "attachment:count=2",
+        "attachment:type:application/pdf",
+        "attachment:sniffed:application/x-msdownload",
+        "attachment:type-mismatch",
+        "attachment:ext:.exe",
+        "attachment:double-ext",
+        "attachment:type:application/zip",
+        "attachment:sniffed:application/zip",
+        "attachment:ext:.zip",
+        "attachment:archive-entries=2",
+        "attachment:archive-ext:.js",
+        "attachment:archive-ext:.txt",
+    ]);
+}
@@ -5,6 +5,7 @@ use std::io;
 use std::fs;
 use crate::utills::SpamClassifierError;
 use spamclassifier::bayesian_spam_classifier::BayesianSpamClassifier;
+use spamclassifier::spam_classifier::{classify_batch_on, SpamClassifier};
 use std::path::Path;
 
 const DEFAULT_DATASET_PATH: &str = "resources/SMSSpamCollection";
@@ -59,7 +60,8 @@ fn test_create_model_from_dataset() -> Result<(), SpamClassifierError> {
         Err(error) => return Err(SpamClassifierError::IO(error)),
     }
     
-    classifier.create_model_from_dataset(&mut dataset_file.unwrap(),&mut model_file.unwrap())?;
+    classifier.create_model_from_dataset(dataset_file.unwrap())?;
+    classifier.save(&mut model_file.unwrap())?;
 
     assert!(Path::new(DEFAULT_MODEL_TEST_PATH).exists());
 
@@ -70,7 +72,93 @@ fn test_create_model_from_dataset() -> Result<(), SpamClassifierError> {
     assert!(spam_prob < ham_prob);
 
     match fs::remove_file(DEFAULT_MODEL_TEST_PATH) {
-        Ok(_) => return Ok(()),
-        Err(error) => return Err(SpamClassifierError::IO(error)),
+        Ok(_) => Ok(()),
+        Err(error) => Err(SpamClassifierError::IO(error)),
+    }
+}
+
+#[test]
+fn test_classify_batch_preserves_order() -> Result<(), SpamClassifierError> {
+    let mut file = File::open(DEFAULT_MODEL_PATH).map_err(SpamClassifierError::IO)?;
+    let classifier = BayesianSpamClassifier::new_from_pre_trained(&mut file).map_err(SpamClassifierError::IO)?;
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().map_err(SpamClassifierError::ThreadPool)?;
+
+    let messages = [TYPICAL_SPAM_MESSAGE, TYPICAL_HAM_MESSAGE, TYPICAL_SPAM_MESSAGE];
+    let classifications = classify_batch_on(&pool, &classifier, &messages);
+
+    assert_eq!(classifications.len(), messages.len());
+    for (message, classification) in messages.iter().zip(classifications.iter()) {
+        assert_eq!(*classification, classifier.classify(message));
     }
+    assert!(classifications[0].is_spam());
+    assert!(!classifications[1].is_spam());
+
+    Ok(())
+}
+
+#[test]
+fn test_create_model_from_in_memory_dataset() -> Result<(), SpamClassifierError> {
+    let dataset = "spam\tSpecial promotion on weightloss pills, only today!\nham\tHi Bob, did you finish the homework?\n";
+    let mut classifier = BayesianSpamClassifier::new();
+
+    let report = classifier.create_model_from_dataset(dataset.as_bytes())?;
+
+    assert_eq!(report.messages_learned, 2);
+    Ok(())
+}
+
+#[test]
+fn test_to_bytes_and_from_bytes() -> Result<(), SpamClassifierError> {
+    let mut classifier = BayesianSpamClassifier::new();
+    classifier.train_spam("Special promotion on weightloss pills, only today!");
+    classifier.train_ham("Hi Bob, did you finish the homework?");
+
+    let bytes = classifier.to_bytes()?;
+    let restored = BayesianSpamClassifier::from_bytes(&bytes)?;
+
+    assert_eq!(restored.classify(TYPICAL_SPAM_MESSAGE), classifier.classify(TYPICAL_SPAM_MESSAGE));
+    assert_eq!(restored.classify(TYPICAL_HAM_MESSAGE), classifier.classify(TYPICAL_HAM_MESSAGE));
+    Ok(())
+}
+
+#[test]
+fn test_training_a_message_twice_counts_it_once() -> Result<(), SpamClassifierError> {
+    let spam = "Special promotion on weightloss pills, only today!";
+    let ham = "Hi Bob, did you finish the homework?";
+    let mut once = BayesianSpamClassifier::new();
+    once.train_spam(spam);
+    once.train_ham(ham);
+    let mut twice = BayesianSpamClassifier::new();
+    twice.train_spam(spam);
+    twice.train_spam(spam);
+    twice.train_ham(ham);
+
+    assert_eq!(twice.get_spam_ham_probabilities(TYPICAL_SPAM_MESSAGE), once.get_spam_ham_probabilities(TYPICAL_SPAM_MESSAGE));
+    assert_eq!(twice.ledger().len(), 2);
+
+    let restored = BayesianSpamClassifier::from_bytes(&twice.to_bytes()?)?;
+    assert_eq!(restored.ledger(), twice.ledger());
+    assert_eq!(restored.ledger().label_of(spam), Some(label::Label::Spam));
+    Ok(())
+}
+
+#[test]
+fn test_relabelling_a_message_replaces_its_counts() {
+    let message = "Special promotion on weightloss pills, only today!";
+    let ham = "Hi Bob, did you finish the homework today?";
+    let mut relabelled = BayesianSpamClassifier::new();
+    relabelled.train_ham(message);
+    relabelled.reinforce(message, label::Label::Ham);
+    relabelled.train_ham(ham);
+    assert_eq!(relabelled.ledger().times_learned(message), 2);
+    relabelled.train_spam(message);
+    let mut fresh = BayesianSpamClassifier::new();
+    fresh.train_ham(ham);
+    fresh.train_spam(message);
+
+    assert_eq!(relabelled.get_spam_ham_probabilities(TYPICAL_SPAM_MESSAGE), fresh.get_spam_ham_probabilities(TYPICAL_SPAM_MESSAGE));
+    assert_eq!(relabelled.get_spam_ham_probabilities(TYPICAL_HAM_MESSAGE), fresh.get_spam_ham_probabilities(TYPICAL_HAM_MESSAGE));
+    assert_eq!(relabelled.ledger().count(label::Label::Spam), 1);
+    assert_eq!(relabelled.ledger().count(label::Label::Ham), 1);
+    assert_eq!(relabelled.ledger().times_learned(message), 1);
 }
@@ -0,0 +1,46 @@
+use spamclassifier::bayesian_spam_classifier::BayesianSpamClassifier;
+use spamclassifier::cache::{model_fingerprint, Cache};
+use spamclassifier::dataset::LabelledMessage;
+use spamclassifier::label::Label;
+use spamclassifier::logistic_regression_classifier::LogisticRegressionClassifier;
+use spamclassifier::spam_classifier::SpamClassifier;
+use spamclassifier::utills::SpamClassifierError;
+
+const SPAM : &str = "win a free prize now";
+const HAM : &str = "see you at lunch tomorrow";
+
+#[test]
+fn test_models_do_not_share_cached_classifications() -> Result<(), SpamClassifierError> {
+    let mut bayesian = BayesianSpamClassifier::new();
+    bayesian.train_spam(SPAM);
+    bayesian.train_ham(HAM);
+    let mut logistic_regression = LogisticRegressionClassifier::new();
+    logistic_regression.train_batch(&[
+        LabelledMessage::new(Label::Spam, SPAM),
+        LabelledMessage::new(Label::Ham, HAM),
+    ]);
+    let bayesian_key = model_fingerprint(&[bayesian.to_bytes()?], "");
+    let logistic_regression_key = model_fingerprint(&[logistic_regression.to_bytes()?], "");
+
+    let mut cache = Cache::default();
+    cache.insert(&bayesian_key, "free prize", &bayesian.classify("free prize"));
+    cache.insert(&logistic_regression_key, "free prize", &logistic_regression.classify("free prize"));
+
+    assert_ne!(bayesian_key, logistic_regression_key);
+    assert_eq!(cache.get_from_cache(&bayesian_key, "free prize"), Some(bayesian.classify("free prize")));
+    assert_eq!(cache.get_from_cache(&logistic_regression_key, "free prize"), Some(logistic_regression.classify("free prize")));
+    assert_ne!(cache.get_from_cache(&bayesian_key, "free prize"), cache.get_from_cache(&logistic_regression_key, "free prize"));
+    assert_eq!(cache.get_from_cache(&bayesian_key, "lunch"), None);
+    Ok(())
+}
+
+#[test]
+fn test_model_fingerprint_covers_files_and_combination() {
+    let first = b"{\"model_type\":\"bayesian\"}".to_vec();
+    let second = b"{\"model_type\":\"linear-svm\"}".to_vec();
+
+    let ensemble = model_fingerprint(&[first.clone(), second.clone()], "weighted-average");
+    assert_ne!(ensemble, model_fingerprint(&[first.clone(), second.clone()], "majority-vote"));
+    assert_ne!(ensemble, model_fingerprint(&[second.clone(), first.clone()], "weighted-average"));
+    assert_ne!(model_fingerprint(&[first], ""), model_fingerprint(&[second], ""));
+}
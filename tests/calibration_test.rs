@@ -0,0 +1,57 @@
+use spamclassifier::calibrated_classifier::CalibratedClassifier;
+use spamclassifier::calibration::{CalibrationMethod, IsotonicRegression, Reliability};
+use spamclassifier::curves::LabelledScore;
+use spamclassifier::dataset::LabelledMessage;
+use spamclassifier::label::Label;
+use spamclassifier::logistic_regression_classifier::LogisticRegressionClassifier;
+use spamclassifier::spam_classifier::{load_model, SpamClassifier, CALIBRATED_MODEL_TYPE};
+use spamclassifier::utills::SpamClassifierError;
+
+#[test]
+fn test_isotonic_regression_is_non_decreasing() {
+    let scores = [-2.0, -1.0, 0.0, 1.0, 2.0, 3.0];
+    let labels = [Label::Ham, Label::Spam, Label::Ham, Label::Ham, Label::Spam, Label::Spam];
+    let isotonic = IsotonicRegression::fit(&scores, &labels);
+
+    assert_eq!(isotonic.probability(-5.0), 0.0);
+    assert_eq!(isotonic.probability(0.0), 1.0 / 3.0);
+    assert_eq!(isotonic.probability(5.0), 1.0);
+    let probabilities: Vec<f64> = (-30..=30).map(|step| isotonic.probability(step as f64 / 10.0)).collect();
+    assert!(probabilities.windows(2).all(|pair| pair[0] <= pair[1]));
+}
+
+#[test]
+fn test_expected_calibration_error() -> Result<(), SpamClassifierError> {
+    let score = |label, spam_probability| LabelledScore { label, spam_probability };
+    let calibrated = Reliability::from_scores(&[score(Label::Ham, 0.25), score(Label::Spam, 0.75), score(Label::Ham, 0.75), score(Label::Spam, 0.75), score(Label::Spam, 0.75)], 2)?;
+    let overconfident = Reliability::from_scores(&[score(Label::Ham, 0.99), score(Label::Spam, 0.99)], 2)?;
+
+    assert_eq!(calibrated.bins[1].messages, 4);
+    assert_eq!(calibrated.bins[1].spam_fraction, 0.75);
+    assert!(calibrated.expected_calibration_error < 0.06);
+    assert!((overconfident.expected_calibration_error - 0.49).abs() < 1e-9);
+    assert!(matches!(Reliability::from_scores(&[score(Label::Ham, 0.5)], 0), Err(SpamClassifierError::InvalidParameter(_))));
+    Ok(())
+}
+
+#[test]
+fn test_calibrated_model_roundtrip() -> Result<(), SpamClassifierError> {
+    let messages = vec![
+        LabelledMessage::new(Label::Spam, "win a free prize now"),
+        LabelledMessage::new(Label::Spam, "free cash prize call now"),
+        LabelledMessage::new(Label::Ham, "see you at lunch tomorrow"),
+        LabelledMessage::new(Label::Ham, "call me when you get home"),
+    ];
+    let mut model = LogisticRegressionClassifier::new();
+    model.train_batch(&messages);
+
+    let calibrated = CalibratedClassifier::fit(Box::new(model), &messages, CalibrationMethod::Platt);
+    let mut bytes = Vec::new();
+    calibrated.save(&mut bytes)?;
+    let loaded = load_model(&mut bytes.as_slice())?;
+
+    assert_eq!(loaded.model_type(), CALIBRATED_MODEL_TYPE);
+    assert_eq!(loaded.classify("free prize").spam_probability(), calibrated.classify("free prize").spam_probability());
+    assert!(loaded.classify("free prize").spam_probability() > loaded.classify("lunch tomorrow").spam_probability());
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use spamclassifier::corpus::{default_directory_mapping, read_corpus};
+use spamclassifier::label::Label;
+use spamclassifier::mailbox::FolderMapping;
+use spamclassifier::utills::SpamClassifierError;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_corpus_files_take_the_label_of_their_directory() -> Result<(), SpamClassifierError> {
+    let root = std::env::temp_dir().join(format!("spamclassifier-corpus-{}", std::process::id()));
+    write_message(&root.join("enron1").join("ham"), "0001.txt", "Subject: meeting moved to 3pm")?;
+    write_message(&root.join("enron1").join("spam"), "0002.txt", "Subject: cheap meds")?;
+    write_message(&root.join("easy_ham"), "0003", "Subject: lunch")?;
+    write_message(&root.join("easy_ham"), "cmds", "mv 1 0003")?;
+    write_message(&root.join("easy_ham"), ".DS_Store", "")?;
+    write_message(&root.join("notes"), "readme", "Not a message")?;
+
+    let corpus = read_corpus(&root, &default_directory_mapping());
+    fs::remove_dir_all(&root).map_err(SpamClassifierError::IO)?;
+    let corpus = corpus?;
+
+    assert_eq!(corpus.count(Label::Ham), 2);
+    assert_eq!(corpus.count(Label::Spam), 1);
+    assert!(corpus.unreadable_files.is_empty());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unreadable_corpus_files_are_reported() -> Result<(), SpamClassifierError> {
+    let root = std::env::temp_dir().join(format!("spamclassifier-corpus-unreadable-{}", std::process::id()));
+    write_message(&root.join("junk"), "1", "Win a prize")?;
+    std::os::unix::fs::symlink(root.join("missing"), root.join("junk").join("2")).map_err(SpamClassifierError::IO)?;
+
+    let corpus = read_corpus(&root, &FolderMapping::new(&["junk"], &["inbox"]));
+    fs::remove_dir_all(&root).map_err(SpamClassifierError::IO)?;
+    let corpus = corpus?;
+
+    assert_eq!(corpus.count(Label::Spam), 1);
+    assert_eq!(corpus.unreadable_files.len(), 1);
+    assert!(corpus.unreadable_files[0].path.ends_with("2"));
+    Ok(())
+}
+
+fn write_message(directory: &Path, name: &str, message: &str) -> Result<(), SpamClassifierError> {
+    fs::create_dir_all(directory).map_err(SpamClassifierError::IO)?;
+    fs::write(directory.join(name), message).map_err(SpamClassifierError::IO)
+}
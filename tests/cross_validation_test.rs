@@ -0,0 +1,65 @@
+use spamclassifier::bayesian_spam_classifier::BayesianSpamClassifier;
+use spamclassifier::cross_validation::{validate, MetricSummary, Validation};
+use spamclassifier::dataset::{read_labelled_messages, LabelledMessage};
+use spamclassifier::evaluation::Cutoffs;
+use spamclassifier::label::Label;
+use spamclassifier::training::TrainingStrategy;
+use spamclassifier::utills::SpamClassifierError;
+use std::fs::File;
+
+const DEFAULT_DATASET_PATH: &str = "resources/SMSSpamCollection";
+
+fn messages() -> Vec<LabelledMessage> {
+    (0..30).map(|index| {
+        let label = if index % 3 == 0 { Label::Spam } else { Label::Ham };
+        LabelledMessage::new(label, &format!("message {}", index))
+    }).collect()
+}
+
+#[test]
+fn test_cross_validation_folds_are_stratified_and_disjoint() -> Result<(), SpamClassifierError> {
+    let messages = messages();
+    let splits = Validation::CrossValidation { folds: 5, seed: 7 }.splits(&messages)?;
+
+    assert_eq!(splits.len(), 5);
+    let mut tested: Vec<usize> = splits.iter().flat_map(|split| split.test.iter().copied()).collect();
+    tested.sort_unstable();
+    assert_eq!(tested, (0..messages.len()).collect::<Vec<_>>());
+    for split in &splits {
+        assert_eq!(split.train.len() + split.test.len(), messages.len());
+        assert!(split.train.iter().all(|index| !split.test.contains(index)));
+        assert_eq!(split.test.iter().filter(|&&index| messages[index].label == Label::Spam).count(), 2);
+    }
+
+    assert_eq!(splits, Validation::CrossValidation { folds: 5, seed: 7 }.splits(&messages)?);
+    assert_ne!(splits, Validation::CrossValidation { folds: 5, seed: 8 }.splits(&messages)?);
+    assert!(Validation::CrossValidation { folds: 1, seed: 7 }.splits(&messages).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_holdout_keeps_the_share_of_spam() -> Result<(), SpamClassifierError> {
+    let messages = messages();
+    let splits = Validation::Holdout { test_fraction: 0.2, seed: 7 }.splits(&messages)?;
+
+    let split = &splits[0];
+    assert_eq!((split.train.len(), split.test.len()), (24, 6));
+    assert_eq!(split.test.iter().filter(|&&index| messages[index].label == Label::Spam).count(), 2);
+    assert!(Validation::Holdout { test_fraction: 1.0, seed: 7 }.splits(&messages).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_cross_validate_bayesian_model() -> Result<(), SpamClassifierError> {
+    let mut dataset_file = File::open(DEFAULT_DATASET_PATH).map_err(SpamClassifierError::IO)?;
+    let messages = read_labelled_messages(&mut dataset_file)?;
+
+    let report = validate(&messages, Validation::CrossValidation { folds: 3, seed: 7 }, TrainingStrategy::TrainEverything,
+                          Cutoffs::default(), || Box::new(BayesianSpamClassifier::new()))?;
+
+    assert_eq!(report.splits.len(), 3);
+    assert_eq!(report.splits.iter().map(|split| split.messages).sum::<usize>(), messages.len());
+    assert!(report.accuracy.mean > 0.9);
+    assert_eq!(report.accuracy, MetricSummary::of(&report.splits.iter().map(|split| split.accuracy).collect::<Vec<_>>()));
+    Ok(())
+}
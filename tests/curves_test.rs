@@ -0,0 +1,49 @@
+use spamclassifier::curves::{Curves, LabelledScore};
+use spamclassifier::label::Label;
+
+fn score(label: Label, spam_probability: f64) -> LabelledScore {
+    LabelledScore { label, spam_probability }
+}
+
+#[test]
+fn test_roc_and_pr_auc() {
+    let curves = Curves::from_scores(&[
+        score(Label::Ham, 0.1),
+        score(Label::Spam, 0.9),
+        score(Label::Spam, 0.7),
+        score(Label::Ham, 0.8),
+    ]);
+
+    let roc: Vec<(f64, f64)> = curves.roc.iter().map(|point| (point.false_positive_rate, point.true_positive_rate)).collect();
+    assert_eq!(roc, vec![(0.0, 0.0), (0.0, 0.5), (0.5, 0.5), (0.5, 1.0), (1.0, 1.0)]);
+    assert!((curves.roc_auc - 0.75).abs() < 1e-9);
+    assert!((curves.pr_auc - (0.5 + 0.5 * 2.0 / 3.0)).abs() < 1e-9);
+}
+
+#[test]
+fn test_tied_scores_make_a_single_point() {
+    let curves = Curves::from_scores(&[
+        score(Label::Spam, 0.5),
+        score(Label::Ham, 0.5),
+        score(Label::Spam, 1.0),
+    ]);
+
+    assert_eq!(curves.roc.iter().map(|point| point.threshold).collect::<Vec<_>>(), vec![f64::INFINITY, 1.0, 0.5]);
+    assert_eq!(curves.pr[2].precision, 2.0 / 3.0);
+    assert_eq!(curves.roc_auc, 0.75);
+}
+
+#[test]
+fn test_csv_and_svg_output() {
+    let curves = Curves::from_scores(&[score(Label::Spam, 0.9), score(Label::Ham, 0.2)]);
+    let mut csv = Vec::new();
+    curves.write_roc_csv(&mut csv).unwrap();
+
+    assert_eq!(String::from_utf8(csv).unwrap(), "threshold,false_positive_rate,true_positive_rate\ninf,0,0\n0.9,0,1\n0.2,1,1\n");
+    assert_eq!(curves.roc_auc, 1.0);
+    assert_eq!(curves.pr_auc, 1.0);
+    let svg = curves.roc_svg();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.contains("<polyline points=\"60.00,420.00 60.00,60.00 420.00,60.00\""));
+    assert!(svg.ends_with("</svg>\n"));
+}
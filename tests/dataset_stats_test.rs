@@ -0,0 +1,35 @@
+use spamclassifier::dataset::{validate_dataset, ColumnMapping, DatasetFormat};
+use spamclassifier::dataset_stats::{DatasetStats, LengthDistribution};
+use spamclassifier::utills::SpamClassifierError;
+
+#[test]
+fn test_dataset_stats_per_label() -> Result<(), SpamClassifierError> {
+    let mut dataset = b"ham\thello there\n\
+                        spam\tfree cash free\n\
+                        spam\tfree cash free\n\
+                        unknown\tignored\n\
+                        no tab\n".to_vec();
+    dataset.extend_from_slice(b"ham\t\xff\xfe\n");
+
+    let validated = validate_dataset(dataset.as_slice(), DatasetFormat::Tsv, &ColumnMapping::default())?;
+    let stats = DatasetStats::compute(&validated, 1);
+
+    assert_eq!(stats.messages, 3);
+    assert_eq!((stats.ham.messages, stats.spam.messages), (1, 2));
+    assert_eq!((stats.duplicates, stats.spam.duplicates), (1, 1));
+    assert_eq!((stats.invalid_utf8_lines, stats.invalid_records, stats.unknown_labels), (1, 1, 1));
+    assert_eq!(stats.vocabulary_size, 4);
+    assert_eq!(stats.spam.top_tokens.len(), 1);
+    assert_eq!((stats.spam.top_tokens[0].token.as_str(), stats.spam.top_tokens[0].count), ("free", 4));
+    assert_eq!(stats.spam.characters.max, 14);
+    Ok(())
+}
+
+#[test]
+fn test_length_distribution() {
+    let distribution = LengthDistribution::of(&[10, 1, 4, 3, 2, 6, 5, 9, 8, 7]);
+
+    assert_eq!((distribution.min, distribution.median, distribution.p90, distribution.max), (1, 5, 9, 10));
+    assert!((distribution.mean - 5.5).abs() < 1e-9);
+    assert_eq!(LengthDistribution::of(&[]), LengthDistribution::default());
+}
@@ -0,0 +1,115 @@
+use spamclassifier::dataset::{read_dataset, validate_dataset, ColumnMapping, DatasetFormat, LabelledMessage, ProblemKind};
+use spamclassifier::label::Label;
+use spamclassifier::utills::SpamClassifierError;
+
+#[test]
+fn test_csv_dataset_with_quoted_and_multi_line_fields() -> Result<(), SpamClassifierError> {
+    let dataset = "id,label,text\r\n\
+                   1,ham,\"Hi Joe, how are you?\"\r\n\
+                   2,spam,\"Say \"\"yes\"\" to\nfree cash\"\r\n\
+                   3,unknown,ignored\r\n";
+
+    let messages = read_dataset(dataset.as_bytes(), DatasetFormat::Csv, &ColumnMapping::default())?;
+
+    assert_eq!(messages, vec![
+        LabelledMessage::new(Label::Ham, "Hi Joe, how are you?"),
+        LabelledMessage::new(Label::Spam, "Say \"yes\" to\nfree cash"),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn test_jsonl_dataset_with_mapped_columns_and_labels() -> Result<(), SpamClassifierError> {
+    let dataset = "{\"is_spam\": 1, \"body\": \"Free ice cream\"}\n\
+                   \n\
+                   {\"is_spam\": 0, \"body\": \"See you tomorrow\"}\n";
+    let mapping = ColumnMapping {
+        label_column: "is_spam".to_string(),
+        text_column: "body".to_string(),
+        spam_labels: vec!["1".to_string()],
+        ham_labels: vec!["0".to_string()],
+    };
+
+    let messages = read_dataset(dataset.as_bytes(), DatasetFormat::Jsonl, &mapping)?;
+
+    assert_eq!(messages, vec![
+        LabelledMessage::new(Label::Spam, "Free ice cream"),
+        LabelledMessage::new(Label::Ham, "See you tomorrow"),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn test_invalid_records_report_their_line() {
+    let unterminated = "label,text\nspam,\"free\n";
+    let missing_field = "{\"label\": \"junk\"}\n";
+    let mapping = ColumnMapping { spam_labels: vec!["junk".to_string()], ..Default::default() };
+
+    let csv_error = read_dataset(unterminated.as_bytes(), DatasetFormat::Csv, &mapping).unwrap_err();
+    let jsonl_error = read_dataset(missing_field.as_bytes(), DatasetFormat::Jsonl, &mapping).unwrap_err();
+
+    assert!(matches!(csv_error, SpamClassifierError::InvalidDatasetFormatError(reason) if reason.contains("Line 2")));
+    assert!(matches!(jsonl_error, SpamClassifierError::InvalidDatasetFormatError(reason) if reason.contains("Line 1")));
+}
+
+#[test]
+fn test_validation_reports_every_problem() -> Result<(), SpamClassifierError> {
+    let dataset: &[u8] = b"ham\tSee you at noon\nspam without a tab\n\nunsure\tMaybe\nham\t\xff\xfe\nspam\tFree cash\n";
+
+    let validated = validate_dataset(dataset, DatasetFormat::Tsv, &ColumnMapping::default())?;
+
+    let problems: Vec<(usize, ProblemKind)> = validated.problems.iter().map(|problem| (problem.line, problem.kind)).collect();
+    assert_eq!(problems, vec![(2, ProblemKind::Malformed), (4, ProblemKind::UnknownLabel), (5, ProblemKind::InvalidUtf8)]);
+    assert_eq!((validated.count(Label::Ham), validated.count(Label::Spam)), (1, 1));
+    assert_eq!(validated.invalid_records().count(), 2);
+
+    let mut rejects = Vec::new();
+    validated.write_rejects(&mut rejects)?;
+    assert!(String::from_utf8_lossy(&rejects).starts_with("spam without a tab\nunsure\tMaybe\n"));
+    Ok(())
+}
+
+#[test]
+fn test_csv_rejects_keep_the_header() -> Result<(), SpamClassifierError> {
+    let dataset = "label,text\nspam,\"Free\ncash\"\nspam\n";
+
+    let validated = validate_dataset(dataset.as_bytes(), DatasetFormat::Csv, &ColumnMapping::default())?;
+    let mut rejects = Vec::new();
+    validated.write_rejects(&mut rejects)?;
+
+    assert_eq!(validated.messages, vec![LabelledMessage::new(Label::Spam, "Free\ncash")]);
+    assert_eq!(validated.problems[0].line, 4);
+    assert_eq!(String::from_utf8_lossy(&rejects), "label,text\nspam\n");
+    Ok(())
+}
+
+#[test]
+fn test_rows_after_a_malformed_record_are_kept() -> Result<(), SpamClassifierError> {
+    let dataset = "label,text\nham,\"hello there\"\nspam,\"win\" now\nspam,Free cash\nham,\"See you\nat noon\"\nunsure,\"Maybe\" later\nham,Bye\n";
+
+    let validated = validate_dataset(dataset.as_bytes(), DatasetFormat::Csv, &ColumnMapping::default())?;
+    let mut rejects = Vec::new();
+    validated.write_rejects(&mut rejects)?;
+
+    assert_eq!((validated.count(Label::Ham), validated.count(Label::Spam)), (3, 1));
+    assert_eq!(validated.messages[2], LabelledMessage::new(Label::Ham, "See you\nat noon"));
+    let problems: Vec<(usize, ProblemKind)> = validated.problems.iter().map(|problem| (problem.line, problem.kind)).collect();
+    assert_eq!(problems, vec![(3, ProblemKind::Malformed), (7, ProblemKind::Malformed)]);
+    assert_eq!(String::from_utf8_lossy(&rejects), "label,text\nspam,\"win\" now\nunsure,\"Maybe\" later\n");
+    Ok(())
+}
+
+#[test]
+fn test_rows_before_an_unterminated_quote_are_kept() -> Result<(), SpamClassifierError> {
+    let dataset = "label,text\nham,See you at noon\nspam,Free cash\nspam,\"Win\nham,Later\n";
+
+    let validated = validate_dataset(dataset.as_bytes(), DatasetFormat::Csv, &ColumnMapping::default())?;
+    let mut rejects = Vec::new();
+    validated.write_rejects(&mut rejects)?;
+
+    assert_eq!(validated.messages, vec![LabelledMessage::new(Label::Ham, "See you at noon"), LabelledMessage::new(Label::Spam, "Free cash")]);
+    assert_eq!(validated.problems.len(), 1);
+    assert_eq!((validated.problems[0].line, validated.problems[0].kind), (4, ProblemKind::Malformed));
+    assert_eq!(String::from_utf8_lossy(&rejects), "label,text\nspam,\"Win\nham,Later\n");
+    Ok(())
+}
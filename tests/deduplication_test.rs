@@ -0,0 +1,54 @@
+use spamclassifier::cache::fingerprint;
+use spamclassifier::dataset::LabelledMessage;
+use spamclassifier::deduplication::{deduplicate, simhash, Deduplication, DEFAULT_MAX_DISTANCE};
+use spamclassifier::label::Label;
+
+fn messages() -> Vec<LabelledMessage> {
+    vec![
+        LabelledMessage::new(Label::Spam, "WINNER!! You have won a 1000 cash prize, call 09061701461 to claim your reward now"),
+        LabelledMessage::new(Label::Spam, "WINNER!! You have won a 1000 cash prize, call 09061701461 to claim your reward now"),
+        LabelledMessage::new(Label::Spam, "WINNER!! You have won a 2000 cash prize, call 09061701461 to claim your reward now"),
+        LabelledMessage::new(Label::Ham, "WINNER!! You have won a 1000 cash prize, call 09061701461 to claim your reward now"),
+        LabelledMessage::new(Label::Ham, "See you at the cinema tonight"),
+        LabelledMessage::new(Label::Ham, "See you at the cinema tonight"),
+    ]
+}
+
+#[test]
+fn test_exact_deduplication_per_label() {
+    let (kept, report) = deduplicate(messages(), Deduplication::Exact);
+
+    assert_eq!(kept.len(), 4);
+    assert_eq!((report.ham_dropped, report.spam_dropped), (1, 1));
+    assert_eq!(report.dropped(Label::Spam), 1);
+    assert_eq!(kept[1].text, messages()[2].text);
+}
+
+#[test]
+fn test_near_deduplication_drops_messages_differing_in_a_word() {
+    let (kept, report) = deduplicate(messages(), Deduplication::Near { max_distance: DEFAULT_MAX_DISTANCE });
+
+    assert_eq!(kept.len(), 3);
+    assert_eq!((report.ham_dropped, report.spam_dropped), (1, 2));
+    assert_ne!(simhash("See you at the cinema tonight"), simhash(&messages()[0].text));
+}
+
+#[test]
+fn test_near_deduplication_keeps_messages_without_words() {
+    let messages = vec![
+        LabelledMessage::new(Label::Ham, ":)"),
+        LabelledMessage::new(Label::Ham, "..."),
+        LabelledMessage::new(Label::Ham, ":)"),
+    ];
+
+    let (kept, report) = deduplicate(messages, Deduplication::Near { max_distance: 63 });
+
+    assert_eq!(kept.len(), 2);
+    assert_eq!(report.ham_dropped, 1);
+    assert_eq!(simhash(":)"), 0);
+}
+
+#[test]
+fn test_fingerprint_is_sha256() {
+    assert_eq!(fingerprint("abc"), "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD");
+}
@@ -0,0 +1,92 @@
+use spamclassifier::email::{decode_encoded_words, looks_like_email, Email};
+use spamclassifier::tokenizer::{analyze, tokenize};
+
+const MULTIPART_EMAIL : &str = "From: Alice <alice@example.com>\r\n\
+To: bob@example.com\r\n\
+Subject: =?UTF-8?B?R3LDvMOfZSA=?= =?ISO-8859-1?Q?aus_K=F6ln?=\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"outer\"\r\n\
+\r\n\
+This is a multi-part message in MIME format.\r\n\
+--outer\r\n\
+Content-Type: multipart/alternative; boundary=inner\r\n\
+\r\n\
+--inner\r\n\
+Content-Type: text/plain; charset=iso-8859-1\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+Sch=F6ne Gr=FC=DFe, bis =\r\n\
+morgen\r\n\
+--inner\r\n\
+Content-Type: text/html; charset=utf-8\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+PHA+SGVsbG88L3A+\r\n\
+--inner--\r\n\
+--outer\r\n\
+Content-Type: application/pdf; name=\"invoice.pdf\"\r\n\
+Content-Disposition: attachment; filename=\"invoice.pdf\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+JVBERi0xLjQKJcfsj6IKNSAwIG9iago8PC9MZW5ndGggNiAwIFI+PgpzdHJlYW0K\r\n\
+--outer--\r\n";
+
+#[test]
+fn test_multipart_email_is_decoded() {
+    let email = Email::parse(MULTIPART_EMAIL);
+
+    assert_eq!(email.header("subject"), Some("Grüße aus Köln"));
+    assert_eq!(email.parts.len(), 3);
+
+    let text_parts: Vec<String> = email.text_parts().map(|part| part.text()).collect();
+    assert_eq!(text_parts, vec!["Schöne Grüße, bis morgen".to_string()]);
+    assert!(email.parts[1].is_alternative_copy);
+
+    let attachment = email.attachments().next().unwrap();
+    assert_eq!(attachment.filename.as_deref(), Some("invoice.pdf"));
+    assert!(attachment.body.starts_with(b"%PDF-1.4"));
+}
+
+#[test]
+fn test_only_subject_and_text_parts_are_tokenized() {
+    let tokens = tokenize(MULTIPART_EMAIL);
+
+    assert!(tokens.contains(&"Köln".to_string()));
+    assert!(tokens.contains(&"morgen".to_string()));
+    assert!(!tokens.contains(&"alice".to_string()));
+    assert!(!tokens.iter().any(|token| token.starts_with("JVBERi0")));
+}
+
+#[test]
+fn test_alternatives_are_tokenized_once() {
+    let alternative = |html_only: bool| format!("From: news@example.com\n\
+                                                 Subject: Sale\n\
+                                                 Content-Type: multipart/alternative; boundary=b\n\
+                                                 \n\
+                                                 --b\n\
+                                                 Content-Type: {}\n\
+                                                 \n\
+                                                 Big savings today\n\
+                                                 --b\n\
+                                                 Content-Type: text/html\n\
+                                                 \n\
+                                                 <p>Big <a href=\"http://example.com\">savings</a> today</p>\n\
+                                                 --b--\n", if html_only { "text/html" } else { "text/plain" });
+
+    assert_eq!(tokenize(&alternative(false)), vec!["Sale", "Big", "savings", "today"]);
+    assert_eq!(tokenize(&alternative(true)), vec!["Sale", "Big", "savings", "today"]);
+    assert!(analyze(&alternative(false)).structure.iter().any(|feature| feature.starts_with("html:")));
+}
+
+#[test]
+fn test_short_messages_are_not_taken_for_e_mails() {
+    assert!(looks_like_email(MULTIPART_EMAIL));
+    assert!(!looks_like_email("Hi Joe, how are you?"));
+    assert!(!looks_like_email("Subject: free money\nCall now!"));
+}
+
+#[test]
+fn test_encoded_words_next_to_plain_text() {
+    assert_eq!(decode_encoded_words("Re: =?utf-8?q?caf=C3=A9?= tonight"), "Re: café tonight");
+    assert_eq!(decode_encoded_words("=?bogus"), "=?bogus");
+}
@@ -0,0 +1,105 @@
+use spamclassifier::bayesian_spam_classifier::BayesianSpamClassifier;
+use spamclassifier::dataset::LabelledMessage;
+use spamclassifier::ensemble_classifier::{CombinationMethod, EnsembleClassifier};
+use spamclassifier::label::Label;
+use spamclassifier::logistic_regression_classifier::LogisticRegressionClassifier;
+use spamclassifier::spam_classifier::{load_model, SpamClassifier, ENSEMBLE_MODEL_TYPE};
+use spamclassifier::utills::SpamClassifierError;
+
+fn training_messages() -> Vec<LabelledMessage> {
+    vec![
+        LabelledMessage::new(Label::Spam, "win a free prize now"),
+        LabelledMessage::new(Label::Spam, "free cash prize call now"),
+        LabelledMessage::new(Label::Ham, "see you at lunch tomorrow"),
+        LabelledMessage::new(Label::Ham, "call me when you get home"),
+    ]
+}
+
+fn members() -> Vec<Box<dyn SpamClassifier>> {
+    let mut bayesian = BayesianSpamClassifier::new();
+    let mut logistic_regression = LogisticRegressionClassifier::new();
+    bayesian.train_batch(&training_messages());
+    logistic_regression.train_batch(&training_messages());
+    vec![Box::new(bayesian), Box::new(logistic_regression)]
+}
+
+#[test]
+fn test_majority_vote() -> Result<(), SpamClassifierError> {
+    let ensemble = EnsembleClassifier::new(members(), CombinationMethod::MajorityVote)?;
+
+    assert!(ensemble.classify("free prize").is_spam());
+    assert!(!ensemble.classify("lunch tomorrow").is_spam());
+    Ok(())
+}
+
+#[test]
+fn test_weighted_average_follows_heavier_member() -> Result<(), SpamClassifierError> {
+    let members = members();
+    let logistic_regression_probability = members[1].classify("free prize").spam_probability();
+
+    let ensemble = EnsembleClassifier::with_weights(members, vec![0.0, 1.0], CombinationMethod::WeightedAverage)?;
+
+    assert!((ensemble.classify("free prize").spam_probability() - logistic_regression_probability).abs() < 1e-9);
+    Ok(())
+}
+
+#[test]
+fn test_zero_weights_count_members_equally() -> Result<(), SpamClassifierError> {
+    for method in [CombinationMethod::MajorityVote, CombinationMethod::WeightedAverage] {
+        let weighted = EnsembleClassifier::with_weights(members(), vec![0.0, 0.0], method)?;
+        let equal = EnsembleClassifier::new(members(), method)?;
+
+        let probability = weighted.classify("free prize").spam_probability();
+        assert!(!probability.is_nan());
+        assert_eq!(probability, equal.classify("free prize").spam_probability());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_stacking_save_and_load() -> Result<(), SpamClassifierError> {
+    let mut ensemble = EnsembleClassifier::new(members(), CombinationMethod::Stacking)?;
+    ensemble.fit_stacking(&training_messages());
+
+    let mut model = Vec::new();
+    ensemble.save(&mut model)?;
+    let loaded = load_model(&mut model.as_slice())?;
+
+    assert_eq!(loaded.model_type(), ENSEMBLE_MODEL_TYPE);
+    assert_eq!(loaded.classify("free prize"), ensemble.classify("free prize"));
+    assert!(loaded.classify("free prize").is_spam());
+    Ok(())
+}
+
+#[test]
+fn test_stacking_is_fitted_out_of_fold() -> Result<(), SpamClassifierError> {
+    let untrained = || -> Vec<Box<dyn SpamClassifier>> { vec![Box::new(BayesianSpamClassifier::new()), Box::new(LogisticRegressionClassifier::new())] };
+    let mut out_of_fold = EnsembleClassifier::new(untrained(), CombinationMethod::Stacking)?;
+    out_of_fold.train_batch(&training_messages());
+    let mut in_sample = EnsembleClassifier::new(members(), CombinationMethod::Stacking)?;
+    in_sample.fit_stacking(&training_messages());
+
+    assert!(out_of_fold.stacking().is_some());
+    assert_ne!(out_of_fold.stacking(), in_sample.stacking());
+    assert_eq!(out_of_fold.members()[0].classify("free prize"), in_sample.members()[0].classify("free prize"));
+    Ok(())
+}
+
+#[test]
+fn test_explain_contains_member_explanations() -> Result<(), SpamClassifierError> {
+    let ensemble = EnsembleClassifier::new(members(), CombinationMethod::MajorityVote)?;
+
+    let explanation = ensemble.explain("free prize");
+
+    assert_eq!(explanation.members.len(), 2);
+    assert_eq!(explanation.members[0].model_type, "bayesian");
+    assert_eq!(explanation.members[1].model_type, "logistic-regression");
+    Ok(())
+}
+
+#[test]
+fn test_rejects_mismatched_weights() {
+    let result = EnsembleClassifier::with_weights(members(), vec![1.0], CombinationMethod::WeightedAverage);
+
+    assert!(matches!(result, Err(SpamClassifierError::InvalidModelError(_))));
+}
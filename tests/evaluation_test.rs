@@ -0,0 +1,69 @@
+use spamclassifier::classification::Classification;
+use spamclassifier::dataset::LabelledMessage;
+use spamclassifier::evaluation::{evaluate, ConfusionMatrix, Cutoffs, Evaluation, Verdict};
+use spamclassifier::label::Label;
+use spamclassifier::bayesian_spam_classifier::BayesianSpamClassifier;
+
+#[test]
+fn test_metrics_with_an_unsure_band() {
+    let messages = vec![
+        LabelledMessage::new(Label::Spam, "spam caught"),
+        LabelledMessage::new(Label::Spam, "spam left unsure"),
+        LabelledMessage::new(Label::Spam, "spam missed"),
+        LabelledMessage::new(Label::Ham, "ham kept"),
+        LabelledMessage::new(Label::Ham, "ham flagged"),
+    ];
+    let classifications = vec![
+        Classification::new(0.9, 0.1),
+        Classification::new(0.6, 0.4),
+        Classification::new(0.1, 0.9),
+        Classification::new(0.2, 0.8),
+        Classification::new(0.95, 0.05),
+    ];
+
+    let evaluation = Evaluation::from_classifications(&messages, &classifications, Cutoffs { ham_cutoff: 0.3, spam_cutoff: 0.7 });
+
+    assert_eq!(evaluation.confusion_matrix, ConfusionMatrix {
+        ham_as_ham: 1, ham_as_unsure: 0, ham_as_spam: 1,
+        spam_as_ham: 1, spam_as_unsure: 1, spam_as_spam: 1,
+    });
+    assert!((evaluation.accuracy - 0.4).abs() < 1e-9);
+    assert!((evaluation.precision - 0.5).abs() < 1e-9);
+    assert!((evaluation.recall - 1.0 / 3.0).abs() < 1e-9);
+    assert!((evaluation.f1 - 0.4).abs() < 1e-9);
+    assert!((evaluation.false_positive_rate - 0.5).abs() < 1e-9);
+    assert_eq!(evaluation.mistakes.iter().map(|mistake| (mistake.message, mistake.verdict)).collect::<Vec<_>>(),
+               vec![(2, Verdict::Unsure), (3, Verdict::Ham), (5, Verdict::Spam)]);
+}
+
+#[test]
+fn test_verdict_follows_asymmetric_cutoffs() {
+    let verdict = |cutoffs: Cutoffs, spam_probability: f64| cutoffs.verdict(&Classification::new(spam_probability, 1.0 - spam_probability));
+    let cautious = Cutoffs { ham_cutoff: 0.8, spam_cutoff: 0.95 };
+    let eager = Cutoffs { ham_cutoff: 0.05, spam_cutoff: 0.2 };
+
+    assert_eq!(verdict(cautious, 0.7), Verdict::Ham);
+    assert_eq!(verdict(cautious, 0.9), Verdict::Unsure);
+    assert_eq!(verdict(cautious, 0.95), Verdict::Spam);
+    assert_eq!(verdict(eager, 0.05), Verdict::Ham);
+    assert_eq!(verdict(eager, 0.1), Verdict::Unsure);
+    assert_eq!(verdict(eager, 0.3), Verdict::Spam);
+    assert_eq!(verdict(Cutoffs::default(), 0.5), Verdict::Ham);
+}
+
+#[test]
+fn test_evaluate_trained_model() {
+    let mut classifier = BayesianSpamClassifier::new();
+    classifier.train_spam("Special promotion on weightloss pills, only today!");
+    classifier.train_ham("Hi Bob, did you finish the homework?");
+    let messages = vec![
+        LabelledMessage::new(Label::Spam, "Lose weight with our special promotion on pills"),
+        LabelledMessage::new(Label::Ham, "Hi Bob, can you send me the homework?"),
+    ];
+
+    let evaluation = evaluate(&classifier, &messages, Cutoffs::default());
+
+    assert_eq!(evaluation.messages, 2);
+    assert_eq!(evaluation.accuracy, 1.0);
+    assert!(evaluation.mistakes.is_empty());
+}
@@ -0,0 +1,68 @@
+use spamclassifier::bayesian_spam_classifier::BayesianSpamClassifier;
+use spamclassifier::features::FeatureExtractor;
+use spamclassifier::label::Label;
+use spamclassifier::spam_classifier::{load_model, SpamClassifier};
+use spamclassifier::utills::SpamClassifierError;
+
+#[test]
+fn test_unigram_features_are_the_words() {
+    let features = FeatureExtractor::Unigram.extract("free money now");
+
+    assert_eq!(features, vec!["free", "money", "now"]);
+}
+
+#[test]
+fn test_out_of_range_windows_are_rejected() {
+    for (name, window) in [("osb", 0), ("osb", 1), ("osb", 17), ("sbph", 1), ("sbph", 9), ("sbph", 70)] {
+        assert!(matches!(FeatureExtractor::from_name(name, window), Err(SpamClassifierError::InvalidParameter(_))), "{} {}", name, window);
+    }
+    assert!(matches!(FeatureExtractor::from_name("bigram", 3), Err(SpamClassifierError::InvalidParameter(_))));
+    assert_eq!(FeatureExtractor::from_name("sbph", 8).unwrap(), FeatureExtractor::Sbph { window: 8 });
+    assert_eq!(FeatureExtractor::from_name("unigram", 70).unwrap(), FeatureExtractor::Unigram);
+
+    let words = (0..70).map(|word| word.to_string()).collect::<Vec<_>>().join(" ");
+    assert_eq!(FeatureExtractor::Sbph { window: 70 }.extract(&words), FeatureExtractor::Sbph { window: 8 }.extract(&words));
+}
+
+#[test]
+fn test_osb_features() {
+    let features = FeatureExtractor::Osb { window: 3 }.extract("a b c");
+
+    // 3 words, then (a,b) for b and (b,c), (a,_,c) for c.
+    assert_eq!(features.len(), 6);
+    assert_eq!(&features[..3], &["a", "b", "c"]);
+    assert!(features[3..].iter().all(|feature| feature.starts_with("osb:")));
+}
+
+#[test]
+fn test_osb_pairs_depend_on_the_gap() {
+    let adjacent = FeatureExtractor::Osb { window: 3 }.extract("free money");
+    let with_gap = FeatureExtractor::Osb { window: 3 }.extract("free cash money");
+
+    assert!(!with_gap.contains(&adjacent[2]));
+    assert_eq!(FeatureExtractor::Osb { window: 3 }.extract("free money"), adjacent);
+}
+
+#[test]
+fn test_sbph_features() {
+    let features = FeatureExtractor::Sbph { window: 3 }.extract("a b c");
+
+    // 1 pattern for a, 2 for b and 4 for c.
+    assert_eq!(features.len(), 7);
+    assert!(features.iter().all(|feature| feature.starts_with("sbph:")));
+}
+
+#[test]
+fn test_bayesian_model_keeps_its_feature_extractor() -> Result<(), SpamClassifierError> {
+    let mut classifier = BayesianSpamClassifier::with_features(FeatureExtractor::Osb { window: 4 });
+    classifier.train("claim your free prize now", Label::Spam);
+    classifier.train("are we still on for lunch", Label::Ham);
+
+    let mut model = Vec::new();
+    classifier.save(&mut model)?;
+    let loaded = load_model(&mut model.as_slice())?;
+
+    assert_eq!(loaded.classify("your free prize"), classifier.classify("your free prize"));
+    assert!(loaded.classify("your free prize").is_spam());
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use spamclassifier::bayesian_spam_classifier::BayesianSpamClassifier;
+use spamclassifier::header_features::{HeaderFeatures, DEFAULT_HEADERS};
+use spamclassifier::label::Label;
+use spamclassifier::spam_classifier::{load_model_from_bytes, SpamClassifier};
+use spamclassifier::utills::SpamClassifierError;
+
+const EMAIL : &str = "From: \"Prize Team\" <winner@lucky.example>\n\
+To: a@example.com, \"Doe, John\" <john@example.com>, c@example.com\n\
+Subject: Free prize\n\
+X-Mailer: BulkMail 2.0\n\
+\n\
+Claim it today\n";
+
+#[test]
+fn test_header_tokens_are_prefixed() {
+    let tokens = HeaderFeatures::from_list(DEFAULT_HEADERS).extract(EMAIL);
+
+    assert_eq!(tokens, vec![
+        "subject:Free", "subject:prize",
+        "from:lucky.example",
+        "to:count=3",
+        "x-mailer:BulkMail", "x-mailer:2.0",
+    ]);
+    assert!(HeaderFeatures::default().extract(EMAIL).is_empty());
+    assert!(HeaderFeatures::from_list(DEFAULT_HEADERS).extract("Free prize, call now").is_empty());
+}
+
+#[test]
+fn test_header_tokens_are_learned_and_kept_in_the_model() -> Result<(), SpamClassifierError> {
+    let mut classifier = BayesianSpamClassifier::new();
+    classifier.set_header_features(HeaderFeatures::new(&["subject"]));
+    classifier.train(EMAIL, Label::Spam);
+
+    let loaded = load_model_from_bytes(&classifier.to_bytes()?)?;
+    let explanation = loaded.explain(EMAIL);
+
+    assert!(explanation.contributions.iter().any(|contribution| contribution.feature == "subject:prize"));
+    Ok(())
+}
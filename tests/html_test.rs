@@ -0,0 +1,45 @@
+use spamclassifier::html::{decode_entities, HtmlDocument};
+use spamclassifier::tokenizer::{analyze, tokenize};
+
+const HTML : &str = "<!DOCTYPE html><html><head><title>Offer</title><style>p { color: red }</style></head>\
+<body><p>Dear customer,</p><!-- tracking -->\
+<p>V<b></b>iagra &amp; more &#8211; <a href=\"http://cheap-pills.example/buy\">www.mybank.com</a></p>\
+<a href=\"https://www.cheap-pills.example/unsubscribe\">unsubscribe</a>\
+<img src=\"cid:banner\"><img src=\"http://tracker.example/pixel.gif\" width=1>\
+<div style=\"display: none\">lorem ipsum dolor</div>\
+<font color=\"#FFFFFF\" size=\"1\">good ham words</font>\
+<script>var x = 1 < 2;</script></body></html>";
+
+#[test]
+fn test_visible_text_is_extracted() {
+    let document = HtmlDocument::parse(HTML);
+
+    assert_eq!(tokenize(HTML), vec!["Dear", "customer", "Viagra", "more", "www.mybank.com", "unsubscribe"]);
+    assert!(!document.text.contains("lorem"));
+    assert!(!document.text.contains("Offer"));
+    assert_eq!(decode_entities("1 &lt; 2 &unknown; &#x41;"), "1 < 2 &unknown; A");
+}
+
+#[test]
+fn test_structural_features() {
+    let document = HtmlDocument::parse(HTML);
+
+    assert_eq!(document.links.len(), 2);
+    assert_eq!(document.mismatched_links().count(), 1);
+    assert_eq!(document.images, 2);
+    assert_eq!(document.hidden_elements, 1);
+    assert_eq!(document.font_tricks, 1);
+    assert_eq!(analyze(HTML).structure, vec![
+        "html:link:cheap-pills.example",
+        "html:links=2",
+        "html:images=2",
+        "html:anchor-mismatches=1",
+        "html:hidden=1",
+        "html:font-tricks=1",
+    ]);
+}
+
+#[test]
+fn test_plain_text_has_no_structure() {
+    assert!(analyze("Free entry <b>now</b>").structure.is_empty());
+}
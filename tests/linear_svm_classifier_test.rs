@@ -0,0 +1,53 @@
+use spamclassifier::calibration::PlattScaling;
+use spamclassifier::dataset::read_labelled_messages;
+use spamclassifier::label::Label;
+use spamclassifier::linear_svm_classifier::LinearSvmClassifier;
+use spamclassifier::spam_classifier::{load_model, SpamClassifier, LINEAR_SVM_MODEL_TYPE};
+use spamclassifier::utills::SpamClassifierError;
+use std::fs::File;
+
+const DEFAULT_DATASET_PATH: &str = "resources/SMSSpamCollection";
+const TYPICAL_SPAM_MESSAGE : &str = "Congratulations! You have won a free prize. Call now to claim your cash reward";
+const TYPICAL_HAM_MESSAGE : &str = "Hi Bob, can you send me your machine learning homework?";
+
+#[test]
+fn test_train_batch() -> Result<(), SpamClassifierError> {
+    let mut dataset_file = File::open(DEFAULT_DATASET_PATH).map_err(SpamClassifierError::IO)?;
+    let messages = read_labelled_messages(&mut dataset_file)?;
+
+    let mut classifier = LinearSvmClassifier::new();
+    classifier.train_batch(&messages);
+
+    assert!(classifier.margin(TYPICAL_SPAM_MESSAGE) > 0.0);
+    assert!(classifier.margin(TYPICAL_HAM_MESSAGE) < 0.0);
+    assert!(classifier.classify(TYPICAL_SPAM_MESSAGE).spam_probability() > 0.5);
+    assert!(classifier.classify(TYPICAL_HAM_MESSAGE).spam_probability() < 0.5);
+    Ok(())
+}
+
+#[test]
+fn test_platt_scaling_is_monotonic_in_the_score() {
+    let scores = [-2.0, -1.5, -1.0, -0.2, 0.3, 1.0, 1.5, 2.5];
+    let labels = [Label::Ham, Label::Ham, Label::Ham, Label::Spam, Label::Ham, Label::Spam, Label::Spam, Label::Spam];
+
+    let platt = PlattScaling::fit(&scores, &labels);
+
+    assert!(platt.a < 0.0);
+    assert!(platt.probability(-2.0) < platt.probability(0.0));
+    assert!(platt.probability(0.0) < platt.probability(2.0));
+}
+
+#[test]
+fn test_save_and_load() -> Result<(), SpamClassifierError> {
+    let mut classifier = LinearSvmClassifier::new();
+    classifier.train("free prize", Label::Spam);
+    classifier.train("lunch tomorrow", Label::Ham);
+
+    let mut model = Vec::new();
+    classifier.save(&mut model)?;
+    let loaded = load_model(&mut model.as_slice())?;
+
+    assert_eq!(loaded.model_type(), LINEAR_SVM_MODEL_TYPE);
+    assert_eq!(loaded.classify("free prize"), classifier.classify("free prize"));
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use spamclassifier::dataset::read_labelled_messages;
+use spamclassifier::label::Label;
+use spamclassifier::logistic_regression_classifier::{LogisticRegressionClassifier, LogisticRegressionConfig, Optimizer};
+use spamclassifier::spam_classifier::{load_model, SpamClassifier, LOGISTIC_REGRESSION_MODEL_TYPE};
+use spamclassifier::utills::SpamClassifierError;
+use std::fs::File;
+
+const DEFAULT_DATASET_PATH: &str = "resources/SMSSpamCollection";
+const TYPICAL_SPAM_MESSAGE : &str = "Congratulations! You have won a free prize. Call now to claim your cash reward";
+const TYPICAL_HAM_MESSAGE : &str = "Hi Bob, can you send me your machine learning homework?";
+
+fn train_on_default_dataset(config: LogisticRegressionConfig) -> Result<LogisticRegressionClassifier, SpamClassifierError> {
+    let mut dataset_file = File::open(DEFAULT_DATASET_PATH).map_err(SpamClassifierError::IO)?;
+    let messages = read_labelled_messages(&mut dataset_file)?;
+
+    let mut classifier = LogisticRegressionClassifier::with_config(config);
+    classifier.train_batch(&messages);
+    Ok(classifier)
+}
+
+#[test]
+fn test_train_batch_with_sgd() -> Result<(), SpamClassifierError> {
+    let classifier = train_on_default_dataset(LogisticRegressionConfig::default())?;
+
+    assert!(classifier.classify(TYPICAL_SPAM_MESSAGE).is_spam());
+    assert!(!classifier.classify(TYPICAL_HAM_MESSAGE).is_spam());
+    Ok(())
+}
+
+#[test]
+fn test_train_batch_with_adagrad_and_l1() -> Result<(), SpamClassifierError> {
+    let config = LogisticRegressionConfig { optimizer: Optimizer::AdaGrad, l1: 0.0001, ..Default::default() };
+    let classifier = train_on_default_dataset(config)?;
+
+    assert!(classifier.classify(TYPICAL_SPAM_MESSAGE).is_spam());
+    assert!(!classifier.classify(TYPICAL_HAM_MESSAGE).is_spam());
+    Ok(())
+}
+
+#[test]
+fn test_online_training_moves_probability_towards_label() {
+    let mut classifier = LogisticRegressionClassifier::new();
+    let before = classifier.classify("free prize").spam_probability();
+
+    classifier.train("free prize", Label::Spam);
+
+    assert!(classifier.classify("free prize").spam_probability() > before);
+}
+
+#[test]
+fn test_save_and_load() -> Result<(), SpamClassifierError> {
+    let mut classifier = LogisticRegressionClassifier::new();
+    classifier.train("free prize", Label::Spam);
+    classifier.train("lunch tomorrow", Label::Ham);
+
+    let mut model = Vec::new();
+    classifier.save(&mut model)?;
+    let loaded = load_model(&mut model.as_slice())?;
+
+    assert_eq!(loaded.model_type(), LOGISTIC_REGRESSION_MODEL_TYPE);
+    assert_eq!(loaded.classify("free prize"), classifier.classify("free prize"));
+    Ok(())
+}
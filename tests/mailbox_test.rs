@@ -0,0 +1,54 @@
+use spamclassifier::dataset::LabelledMessage;
+use spamclassifier::label::Label;
+use spamclassifier::mailbox::{read_mailbox, read_mbox, FolderMapping};
+use spamclassifier::utills::SpamClassifierError;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_mbox_is_split_on_from_lines() -> Result<(), SpamClassifierError> {
+    let mbox = "From alice@example.com Mon Jan  1 00:00:00 2024\n\
+                Subject: hi\n\
+                \n\
+                >From the start\n\
+                From here on, no separator\n\
+                \n\
+                From bob@example.com Tue Jan  2 00:00:00 2024\n\
+                Subject: free cash\n\
+                \n\
+                Win now\n";
+
+    let messages = read_mbox(mbox.as_bytes())?;
+
+    assert_eq!(messages, vec![
+        "Subject: hi\n\nFrom the start\nFrom here on, no separator\n".to_string(),
+        "Subject: free cash\n\nWin now".to_string(),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn test_mailbox_folders_are_mapped_to_labels() -> Result<(), SpamClassifierError> {
+    let root = std::env::temp_dir().join(format!("spamclassifier-mailbox-{}", std::process::id()));
+    write_message(&root.join("cur"), "1", "Subject: lunch\n\nSee you at noon")?;
+    fs::create_dir_all(root.join("new")).map_err(SpamClassifierError::IO)?;
+    fs::create_dir_all(root.join("tmp")).map_err(SpamClassifierError::IO)?;
+    write_message(&root.join(".Junk").join("new"), "2", "Subject: prize\n\nClaim your prize")?;
+    fs::create_dir_all(root.join(".Junk").join("cur")).map_err(SpamClassifierError::IO)?;
+    write_message(&root.join(".Archive").join("cur"), "3", "Subject: old\n\nIgnored")?;
+    fs::create_dir_all(root.join(".Archive").join("new")).map_err(SpamClassifierError::IO)?;
+
+    let messages = read_mailbox(&root, &FolderMapping::default());
+    fs::remove_dir_all(&root).map_err(SpamClassifierError::IO)?;
+
+    assert_eq!(messages?, vec![
+        LabelledMessage::new(Label::Ham, "Subject: lunch\n\nSee you at noon"),
+        LabelledMessage::new(Label::Spam, "Subject: prize\n\nClaim your prize"),
+    ]);
+    Ok(())
+}
+
+fn write_message(directory: &Path, name: &str, message: &str) -> Result<(), SpamClassifierError> {
+    fs::create_dir_all(directory).map_err(SpamClassifierError::IO)?;
+    fs::write(directory.join(name), message).map_err(SpamClassifierError::IO)
+}
@@ -0,0 +1,51 @@
+use spamclassifier::checkpoint::CheckpointedTrainer;
+use spamclassifier::label::Label;
+use spamclassifier::passive_aggressive_classifier::{PassiveAggressiveClassifier, PassiveAggressiveConfig, PassiveAggressiveVariant};
+use spamclassifier::spam_classifier::{load_model, SpamClassifier, PASSIVE_AGGRESSIVE_MODEL_TYPE};
+use spamclassifier::utills::SpamClassifierError;
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+
+const CHECKPOINT_TEST_PATH: &str = "resources/checkpointTest.json";
+
+#[test]
+fn test_update_corrects_margin_violation() {
+    for variant in [PassiveAggressiveVariant::Pa, PassiveAggressiveVariant::PaI, PassiveAggressiveVariant::PaII] {
+        let mut classifier = PassiveAggressiveClassifier::with_config(PassiveAggressiveConfig { variant, aggressiveness: 1.0 });
+
+        classifier.train("free prize now", Label::Spam);
+        classifier.train("lunch at noon", Label::Ham);
+
+        assert!(classifier.margin("free prize now") > 0.0);
+        assert!(classifier.margin("lunch at noon") < 0.0);
+        assert_eq!(classifier.updates(), 2);
+    }
+}
+
+#[test]
+fn test_no_update_when_margin_is_satisfied() {
+    let mut classifier = PassiveAggressiveClassifier::with_config(PassiveAggressiveConfig { variant: PassiveAggressiveVariant::Pa, aggressiveness: 1.0 });
+
+    classifier.train("free prize now", Label::Spam);
+    classifier.train("free prize now", Label::Spam);
+
+    assert_eq!(classifier.updates(), 1);
+}
+
+#[test]
+fn test_checkpointed_trainer_writes_loadable_model() -> Result<(), SpamClassifierError> {
+    let classifier = Box::new(PassiveAggressiveClassifier::new());
+    let mut trainer = CheckpointedTrainer::new(classifier, Path::new(CHECKPOINT_TEST_PATH), 2);
+
+    assert!(!trainer.train("free prize now", Label::Spam)?);
+    assert!(trainer.train("lunch at noon", Label::Ham)?);
+    assert_eq!(trainer.checkpoints(), 1);
+
+    let mut file = File::open(CHECKPOINT_TEST_PATH).map_err(SpamClassifierError::IO)?;
+    let loaded = load_model(&mut file)?;
+    assert_eq!(loaded.model_type(), PASSIVE_AGGRESSIVE_MODEL_TYPE);
+    assert_eq!(loaded.classify("free prize now"), trainer.classifier().classify("free prize now"));
+
+    fs::remove_file(CHECKPOINT_TEST_PATH).map_err(SpamClassifierError::IO)
+}
@@ -0,0 +1,61 @@
+use spamclassifier::bayesian_spam_classifier::BayesianSpamClassifier;
+use spamclassifier::label::Label;
+use spamclassifier::spam_classifier::{load_model, SpamClassifier, BAYESIAN_MODEL_TYPE};
+use spamclassifier::utills::SpamClassifierError;
+use std::fs::File;
+
+const DEFAULT_MODEL_PATH: &str = "resources/model.json";
+const TYPICAL_SPAM_MESSAGE : &str = "Lose up to 19% weight. Special promotion on our new weightloss.";
+const TYPICAL_HAM_MESSAGE : &str = "Hi Bob, can you send me your machine learning homework?";
+
+fn trained_classifier() -> BayesianSpamClassifier {
+    let mut classifier = BayesianSpamClassifier::new();
+    classifier.train("Don't forget our special promotion: -30% on men shoes, only today!", Label::Spam);
+    classifier.train("Hi Bob, don't forget our meeting today at 4pm.", Label::Ham);
+    classifier
+}
+
+#[test]
+fn test_load_model_without_model_type_is_bayesian() -> Result<(), SpamClassifierError> {
+    let mut file = File::open(DEFAULT_MODEL_PATH).map_err(SpamClassifierError::IO)?;
+    let classifier = load_model(&mut file)?;
+
+    assert_eq!(classifier.model_type(), BAYESIAN_MODEL_TYPE);
+    assert!(classifier.classify(TYPICAL_SPAM_MESSAGE).is_spam());
+    assert!(!classifier.classify(TYPICAL_HAM_MESSAGE).is_spam());
+    Ok(())
+}
+
+#[test]
+fn test_save_and_load_model() -> Result<(), SpamClassifierError> {
+    let classifier = trained_classifier();
+    let mut model = Vec::new();
+    classifier.save(&mut model)?;
+
+    let loaded = load_model(&mut model.as_slice())?;
+
+    assert_eq!(loaded.model_type(), BAYESIAN_MODEL_TYPE);
+    assert_eq!(loaded.classify(TYPICAL_SPAM_MESSAGE), classifier.classify(TYPICAL_SPAM_MESSAGE));
+    assert_eq!(loaded.classify(TYPICAL_HAM_MESSAGE), classifier.classify(TYPICAL_HAM_MESSAGE));
+    Ok(())
+}
+
+#[test]
+fn test_load_model_rejects_unknown_model_type() {
+    let model = r#"{"model_type":"unknown"}"#;
+
+    let result = load_model(&mut model.as_bytes());
+
+    assert!(matches!(result, Err(SpamClassifierError::InvalidModelError(_))));
+}
+
+#[test]
+fn test_explain_weights_spam_features_positively() {
+    let classifier = trained_classifier();
+
+    let explanation = classifier.explain("special promotion");
+
+    assert_eq!(explanation.classification, classifier.classify("special promotion"));
+    assert_eq!(explanation.contributions.len(), 2);
+    assert!(explanation.contributions.iter().all(|contribution| contribution.weight > 0.0));
+}
@@ -0,0 +1,93 @@
+use spamclassifier::bayesian_spam_classifier::BayesianSpamClassifier;
+use spamclassifier::dataset::{read_labelled_messages, LabelledMessage};
+use spamclassifier::deduplication::{deduplicate, Deduplication};
+use spamclassifier::label::Label;
+use spamclassifier::training::TrainingStrategy;
+use spamclassifier::utills::SpamClassifierError;
+use std::fs::File;
+
+const DEFAULT_DATASET_PATH: &str = "resources/SMSSpamCollection";
+const TYPICAL_SPAM_MESSAGE : &str = "Lose up to 19% weight. Special promotion on our new weightloss.";
+const TYPICAL_HAM_MESSAGE : &str = "Hi Bob, can you send me your machine learning homework?";
+
+fn read_default_dataset() -> Result<Vec<LabelledMessage>, SpamClassifierError> {
+    let mut dataset_file = File::open(DEFAULT_DATASET_PATH).map_err(SpamClassifierError::IO)?;
+    read_labelled_messages(&mut dataset_file)
+}
+
+#[test]
+fn test_train_everything_learns_every_message_once() -> Result<(), SpamClassifierError> {
+    let messages = read_default_dataset()?;
+    let mut classifier = BayesianSpamClassifier::new();
+
+    let report = classifier.train_with_strategy(&messages, TrainingStrategy::TrainEverything);
+    let (unique_messages, _) = deduplicate(messages.clone(), Deduplication::Exact);
+
+    assert_eq!(report.messages_seen, messages.len());
+    assert_eq!(report.messages_learned, unique_messages.len());
+    assert_eq!(report.passes, 1);
+
+    let report = classifier.train_with_strategy(&messages, TrainingStrategy::TrainEverything);
+    assert_eq!(report.messages_learned, 0);
+    Ok(())
+}
+
+#[test]
+fn test_train_on_error_learns_fewer_messages() -> Result<(), SpamClassifierError> {
+    let messages = read_default_dataset()?;
+    let mut classifier = BayesianSpamClassifier::new();
+
+    let report = classifier.train_with_strategy(&messages, TrainingStrategy::TrainOnError);
+
+    assert!(report.messages_learned > 0);
+    assert!(report.messages_learned < messages.len());
+    assert_eq!(report.messages_learned, report.ham_learned + report.spam_learned);
+
+    let (spam_prob, ham_prob) = classifier.get_spam_ham_probabilities(TYPICAL_SPAM_MESSAGE);
+    assert!(spam_prob > ham_prob);
+
+    let (spam_prob, ham_prob) = classifier.get_spam_ham_probabilities(TYPICAL_HAM_MESSAGE);
+    assert!(spam_prob < ham_prob);
+    Ok(())
+}
+
+#[test]
+fn test_train_until_mature_learns_maturity_messages_per_label() {
+    let messages = vec![
+        LabelledMessage::new(Label::Ham, "see you at lunch"),
+        LabelledMessage::new(Label::Ham, "see you at lunch"),
+        LabelledMessage::new(Label::Spam, "win a free prize now"),
+        LabelledMessage::new(Label::Spam, "win a free prize now"),
+    ];
+    let mut classifier = BayesianSpamClassifier::new();
+
+    let report = classifier.train_with_strategy(&messages, TrainingStrategy::TrainUntilMature { maturity: 1 });
+
+    assert_eq!(report.ham_learned, 1);
+    assert_eq!(report.spam_learned, 1);
+}
+
+#[test]
+fn test_train_until_no_error_learns_every_training_message() -> Result<(), SpamClassifierError> {
+    let messages = read_default_dataset()?;
+    let mut classifier = BayesianSpamClassifier::new();
+
+    let report = classifier.train_with_strategy(&messages, TrainingStrategy::TrainUntilNoError { max_passes: 10 });
+
+    assert!(report.passes < 10);
+    assert!(messages.iter().all(|message| !classifier.is_misclassified(&message.text, message.label)));
+    Ok(())
+}
+
+#[test]
+fn test_train_until_no_error_respects_max_passes() -> Result<(), SpamClassifierError> {
+    let messages = read_default_dataset()?;
+    let mut classifier = BayesianSpamClassifier::new();
+
+    let report = classifier.train_with_strategy(&messages, TrainingStrategy::TrainUntilNoError { max_passes: 2 });
+
+    let is_error_free = messages.iter().all(|message| !classifier.is_misclassified(&message.text, message.label));
+    assert!(is_error_free || report.passes == 2);
+    assert!(report.passes <= 2);
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use spamclassifier::cross_validation::Validation;
+use spamclassifier::dataset::LabelledMessage;
+use spamclassifier::label::Label;
+use spamclassifier::tuning::{tune, Hyperparameters, Metric, Search, SearchDimension};
+use spamclassifier::utills::SpamClassifierError;
+
+fn dimensions() -> Vec<SearchDimension> {
+    vec![
+        SearchDimension::parse("model-type=bayesian,logistic-regression").unwrap(),
+        SearchDimension::parse("spam-cutoff=0.5, 0.7,0.9").unwrap(),
+    ]
+}
+
+#[test]
+fn test_search_dimensions_are_checked() {
+    assert!(matches!(SearchDimension::parse("smoothing=1,2"), Err(SpamClassifierError::InvalidParameter(_))));
+    assert!(matches!(SearchDimension::parse("l2=0.1,lots"), Err(SpamClassifierError::InvalidParameter(_))));
+    assert!(matches!(SearchDimension::parse("features"), Err(SpamClassifierError::InvalidParameter(_))));
+    assert_eq!(dimensions()[1].values, vec!["0.5", "0.7", "0.9"]);
+}
+
+#[test]
+fn test_grid_and_random_configurations() {
+    let grid = Search::Grid.configurations(&dimensions()).unwrap();
+    assert_eq!(grid.len(), 6);
+    assert_eq!(grid[1]["model-type"], "bayesian");
+    assert_eq!(grid[1]["spam-cutoff"], "0.7");
+
+    let random = Search::Random { trials: 4, seed: 7 }.configurations(&dimensions()).unwrap();
+    assert_eq!(random.len(), 4);
+    assert!(random.iter().all(|configuration| grid.contains(configuration)));
+    assert!(random.iter().enumerate().all(|(index, configuration)| !random[index + 1..].contains(configuration)));
+    assert_eq!(random, Search::Random { trials: 4, seed: 7 }.configurations(&dimensions()).unwrap());
+    assert_eq!(Search::Random { trials: 10, seed: 7 }.configurations(&dimensions()).unwrap(), grid);
+}
+
+#[test]
+fn test_tune_ranks_configurations_by_metric() -> Result<(), SpamClassifierError> {
+    let mut messages = Vec::new();
+    for index in 0..10 {
+        messages.push(LabelledMessage::new(Label::Spam, &format!("win a free prize now {}", index)));
+        messages.push(LabelledMessage::new(Label::Ham, &format!("see you at lunch tomorrow {}", index)));
+    }
+    let configurations = Search::Grid.configurations(&[
+        SearchDimension::parse("ham-cutoff=0.5,0.9").unwrap(),
+        SearchDimension::parse("spam-cutoff=0.5,0.99999").unwrap(),
+    ])?;
+
+    let mut tried = 0;
+    let validation = Validation::CrossValidation { folds: 2, seed: 1 };
+    let report = tune(&messages, &Hyperparameters::default(), configurations, validation, Metric::UnsureRate, |_| tried += 1)?;
+
+    assert_eq!(tried, 3);
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0]["ham-cutoff"], "0.9");
+    let best = report.best().unwrap();
+    assert_eq!(best.score.mean, 0.0);
+    assert_eq!(best.configuration["ham-cutoff"], "0.5");
+    assert_eq!(best.configuration["spam-cutoff"], "0.5");
+    assert!(report.trials.windows(2).all(|pair| pair[0].score.mean <= pair[1].score.mean));
+    assert_eq!(report.trials[1].score.mean, 0.5);
+    Ok(())
+}
+
+#[test]
+fn test_conflicting_searches_are_rejected() {
+    let repeated = [SearchDimension::parse("l2=0.1").unwrap(), SearchDimension::parse("l2=0.01").unwrap()];
+    assert!(matches!(Search::Grid.configurations(&repeated), Err(SpamClassifierError::InvalidParameter(_))));
+    assert!(matches!(SearchDimension::parse("feature-window=3,40"), Err(SpamClassifierError::InvalidParameter(_))));
+
+    let configurations = Search::Grid.configurations(&[
+        SearchDimension::parse("features=osb,sbph").unwrap(),
+        SearchDimension::parse("feature-window=12").unwrap(),
+    ]).unwrap();
+    let mut tried = 0;
+    let messages = vec![LabelledMessage::new(Label::Spam, "free prize"), LabelledMessage::new(Label::Ham, "see you")];
+    let validation = Validation::CrossValidation { folds: 2, seed: 1 };
+    let result = tune(&messages, &Hyperparameters::default(), configurations, validation, Metric::F1, |_| tried += 1);
+
+    assert!(matches!(result, Err(SpamClassifierError::InvalidParameter(_))));
+    assert_eq!(tried, 0);
+}